@@ -0,0 +1,1163 @@
+//! Two pass assembler for the documented 6502 instructions.
+//!
+//! Every line holds an optional label, then an instruction or a directive,
+//! and an optional `;` comment:
+//!
+//! ```text
+//! screen = $0400          ; constant
+//!         .org $0200
+//! main:   ldx #0          ; global label
+//! @loop:  lda message,x   ; local label, scoped to `main`
+//!         beq @done
+//!         sta screen,x
+//!         inx
+//!         bne @loop
+//! @done:  rts
+//! message: .byte "HI", 0
+//! ```
+//!
+//! Supported directives are `.org ADDRESS`, `.byte` (expressions and
+//! strings), `.word`, `.res COUNT[, FILL]` and `.include "FILE"`. Operands
+//! whose value is known when the line is first seen and fits in a byte use
+//! zero page addressing; forward references get the absolute form.
+//! See [`expression`] for the expression syntax.
+//!
+//! [`assemble_object`] builds a relocatable o65 object instead, to be
+//! combined with others by the [`linker`](crate::linker). There `.org` is
+//! not allowed; `.segment "NAME"` switches between the `CODE`, `DATA`, `BSS`
+//! and `ZEROPAGE` segments, each assembled from offset 0. `.import NAME, ...`
+//! declares symbols defined by other objects (`.importzp` for zero page
+//! ones) and `.export NAME, ...` makes symbols visible to them. `BSS` and
+//! `ZEROPAGE` can only hold labels and `.res` without a fill value.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    consts::{Byte, Word},
+    loaders::{
+        o65::{Export, Object, Relocation, RelocationKind, RelocationTarget, SegmentId},
+        Image,
+    },
+    opcodes::{self, Mode},
+};
+
+use self::expression::{local_name, Base, EvalError, Expr, Part, Value};
+
+pub mod expression;
+pub mod inline;
+
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+#[derive(Debug, PartialEq)]
+pub enum AsmErrorKind {
+    Syntax(String),
+    UnknownInstruction(String),
+    UnknownDirective(String),
+    InvalidAddressingMode(String),
+    UndefinedSymbol(String),
+    DuplicateSymbol(String),
+    /// The value of `.org` and `.res` must be known in the first pass.
+    ForwardReference(String),
+    DivisionByZero,
+    ValueOutOfRange(i64),
+    BranchOutOfRange(i64),
+    AddressOverflow,
+    Include {
+        path: PathBuf,
+        message: String,
+    },
+    IncludeDepth,
+    /// The value cannot be expressed by the object's relocation records.
+    NotRelocatable,
+    UnknownSegment(String),
+    /// The directive is only allowed by [`assemble`].
+    AbsoluteOnly(String),
+    /// The directive is only allowed by [`assemble_object`].
+    RelocatableOnly(String),
+    /// Only labels and `.res` without a fill value are allowed in `BSS` and `ZEROPAGE`.
+    UninitializedSegment,
+}
+
+/// Error at `line` (counted from 1) of `file`, `None` being the source given to [`assemble`].
+#[derive(Debug, PartialEq)]
+pub struct AsmError {
+    pub file: Option<PathBuf>,
+    pub line: usize,
+    pub kind: AsmErrorKind,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}: ", file.display(), self.line)?,
+            None => write!(f, "line {}: ", self.line)?,
+        }
+        return match &self.kind {
+            AsmErrorKind::Syntax(message) => write!(f, "{message}"),
+            AsmErrorKind::UnknownInstruction(name) => write!(f, "unknown instruction \"{name}\""),
+            AsmErrorKind::UnknownDirective(name) => write!(f, "unknown directive \"{name}\""),
+            AsmErrorKind::InvalidAddressingMode(mnemonic) => {
+                write!(f, "addressing mode not supported by {mnemonic}")
+            }
+            AsmErrorKind::UndefinedSymbol(name) => write!(f, "undefined symbol \"{name}\""),
+            AsmErrorKind::DuplicateSymbol(name) => write!(f, "symbol \"{name}\" already defined"),
+            AsmErrorKind::ForwardReference(name) => {
+                write!(f, "\"{name}\" must be defined before it is used here")
+            }
+            AsmErrorKind::DivisionByZero => write!(f, "division by zero"),
+            AsmErrorKind::ValueOutOfRange(value) => write!(f, "value {value} is out of range"),
+            AsmErrorKind::BranchOutOfRange(offset) => {
+                write!(
+                    f,
+                    "branch target is {offset} bytes away, limit is -128..127"
+                )
+            }
+            AsmErrorKind::AddressOverflow => write!(f, "code runs past $FFFF"),
+            AsmErrorKind::Include { path, message } => {
+                write!(f, "cannot include {}: {message}", path.display())
+            }
+            AsmErrorKind::IncludeDepth => {
+                write!(f, "includes nested deeper than {MAX_INCLUDE_DEPTH} levels")
+            }
+            AsmErrorKind::NotRelocatable => write!(f, "value cannot be relocated"),
+            AsmErrorKind::UnknownSegment(name) => write!(f, "unknown segment \"{name}\""),
+            AsmErrorKind::AbsoluteOnly(directive) => {
+                write!(f, "{directive} is not allowed in relocatable objects")
+            }
+            AsmErrorKind::RelocatableOnly(directive) => {
+                write!(f, "{directive} is only allowed in relocatable objects")
+            }
+            AsmErrorKind::UninitializedSegment => {
+                write!(f, "segment cannot hold data, only labels and .res")
+            }
+        };
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// Assembled program.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Assembly {
+    pub image: Image,
+    /// Addresses of all labels, local ones named `global@local`.
+    pub labels: BTreeMap<String, Word>,
+    /// Values of symbols defined with `name = value`.
+    pub constants: BTreeMap<String, i64>,
+}
+
+impl Assembly {
+    pub fn label(&self, name: &str) -> Option<Word> {
+        return self.labels.get(name).copied();
+    }
+
+    /// Lowest address anything was assembled to.
+    pub fn origin(&self) -> Option<Word> {
+        return self
+            .image
+            .segments
+            .iter()
+            .map(|segment| segment.address)
+            .min();
+    }
+
+    /// Address/value pairs accepted by `VecMemory::from`.
+    pub fn to_pairs(&self) -> Vec<(Word, Byte)> {
+        return self.image.to_pairs();
+    }
+
+    /// Bytes from [`Assembly::origin`] up to the last assembled byte, gaps filled with zeros.
+    pub fn to_bytes(&self) -> Vec<Byte> {
+        let origin = match self.origin() {
+            Some(origin) => usize::from(origin),
+            None => return vec![],
+        };
+        let end = self
+            .image
+            .segments
+            .iter()
+            .map(|segment| usize::from(segment.address) + segment.data.len())
+            .max()
+            .unwrap_or(origin);
+
+        let mut bytes = vec![0; end - origin];
+        for segment in &self.image.segments {
+            let start = usize::from(segment.address) - origin;
+            bytes[start..start + segment.data.len()].copy_from_slice(&segment.data);
+        }
+
+        return bytes;
+    }
+}
+
+/// Assembles `source`, files named by `.include` are looked up relative to `base_dir`.
+pub fn assemble(source: &str, base_dir: &Path) -> Result<Assembly, AsmError> {
+    return Assembler::new(false).run(&parse_program(source, base_dir)?);
+}
+
+/// Assembles the file at `path`, includes are relative to the file.
+pub fn assemble_file<P: AsRef<Path>>(path: P) -> Result<Assembly, AsmError> {
+    return Assembler::new(false).run(&parse_program_file(path.as_ref())?);
+}
+
+/// Assembles `source` into a relocatable object, see [`assemble`].
+pub fn assemble_object(source: &str, base_dir: &Path) -> Result<Object, AsmError> {
+    return Assembler::new(true).run_object(&parse_program(source, base_dir)?);
+}
+
+/// Assembles the file at `path` into a relocatable object.
+pub fn assemble_object_file<P: AsRef<Path>>(path: P) -> Result<Object, AsmError> {
+    return Assembler::new(true).run_object(&parse_program_file(path.as_ref())?);
+}
+
+fn parse_program(source: &str, base_dir: &Path) -> Result<Vec<Statement>, AsmError> {
+    let mut statements = vec![];
+    let mut scope = String::new();
+    parse_source(source, None, base_dir, 0, &mut scope, &mut statements)?;
+
+    return Ok(statements);
+}
+
+fn parse_program_file(path: &Path) -> Result<Vec<Statement>, AsmError> {
+    let source = read_include(path, None, 0)?;
+    let mut statements = vec![];
+    let mut scope = String::new();
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    parse_source(
+        &source,
+        Some(path),
+        base_dir,
+        0,
+        &mut scope,
+        &mut statements,
+    )?;
+
+    return Ok(statements);
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Operand {
+    None,
+    Accumulator,
+    Immediate(Expr),
+    Address(Expr, Option<char>),
+    Indirect(Expr),
+    IndexedIndirectX(Expr),
+    IndirectIndexedY(Expr),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Data {
+    Value(Expr),
+    Text(Vec<Byte>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Item {
+    Label(String),
+    Constant(String, Expr),
+    Org(Expr),
+    Bytes(Vec<Data>),
+    Words(Vec<Expr>),
+    Reserve(Expr, Option<Expr>),
+    Instruction(&'static str, Operand),
+    Segment(SegmentId),
+    /// Imported names, `true` for zero page ones.
+    Import(Vec<String>, bool),
+    Export(Vec<String>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Statement {
+    file: Option<PathBuf>,
+    line: usize,
+    item: Item,
+}
+
+impl Statement {
+    fn error(&self, kind: AsmErrorKind) -> AsmError {
+        return AsmError {
+            file: self.file.clone(),
+            line: self.line,
+            kind,
+        };
+    }
+}
+
+fn read_include(path: &Path, from: Option<&Statement>, depth: usize) -> Result<String, AsmError> {
+    let error = |kind| AsmError {
+        file: from.and_then(|statement| statement.file.clone()),
+        line: from.map_or(0, |statement| statement.line),
+        kind,
+    };
+    if depth > MAX_INCLUDE_DEPTH {
+        return Err(error(AsmErrorKind::IncludeDepth));
+    }
+
+    return fs::read_to_string(path).map_err(|err| {
+        error(AsmErrorKind::Include {
+            path: path.to_path_buf(),
+            message: err.to_string(),
+        })
+    });
+}
+
+fn parse_source(
+    source: &str,
+    file: Option<&Path>,
+    base_dir: &Path,
+    depth: usize,
+    scope: &mut String,
+    statements: &mut Vec<Statement>,
+) -> Result<(), AsmError> {
+    for (idx, text) in source.lines().enumerate() {
+        let mut location = Statement {
+            file: file.map(Path::to_path_buf),
+            line: idx + 1,
+            item: Item::Label(String::new()),
+        };
+        let syntax = |message: String| location.error(AsmErrorKind::Syntax(message));
+
+        let mut rest = strip_comment(text).trim();
+        if rest.is_empty() {
+            continue;
+        }
+
+        if let Some((name, value)) = rest.split_once('=') {
+            let name = name.trim();
+            if expression::is_identifier(name) {
+                let value = expression::parse(value, scope).map_err(syntax)?;
+                location.item = Item::Constant(name.to_string(), value);
+                statements.push(location);
+                continue;
+            }
+        }
+
+        if let Some((label, remainder)) = split_label(rest) {
+            let name = if label.starts_with('@') {
+                local_name(scope, label)
+            } else {
+                if !expression::is_identifier(label) {
+                    return Err(syntax(format!("invalid label \"{label}\"")));
+                }
+                *scope = label.to_string();
+                label.to_string()
+            };
+            statements.push(Statement {
+                item: Item::Label(name),
+                ..location.clone()
+            });
+            rest = remainder.trim();
+            if rest.is_empty() {
+                continue;
+            }
+        }
+
+        let (word, arguments) = match rest.split_once(char::is_whitespace) {
+            Some((word, arguments)) => (word, arguments.trim()),
+            None => (rest, ""),
+        };
+
+        if let Some(directive) = word.strip_prefix('.') {
+            if directive.eq_ignore_ascii_case("include") {
+                let path = base_dir.join(parse_string(arguments).map_err(syntax)?);
+                let path = path.as_path();
+                let included = read_include(path, Some(&location), depth + 1)?;
+                let included_dir = path.parent().unwrap_or(Path::new(""));
+                parse_source(
+                    &included,
+                    Some(path),
+                    included_dir,
+                    depth + 1,
+                    scope,
+                    statements,
+                )?;
+                continue;
+            }
+
+            location.item = parse_directive(directive, arguments, scope)
+                .map_err(|kind| location.error(kind))?;
+        } else {
+            let info = opcodes::OPCODES
+                .iter()
+                .find(|entry| entry.mnemonic.eq_ignore_ascii_case(word))
+                .ok_or_else(|| {
+                    location.error(AsmErrorKind::UnknownInstruction(word.to_string()))
+                })?;
+            let operand = parse_operand(arguments, scope).map_err(syntax)?;
+            location.item = Item::Instruction(info.mnemonic, operand);
+        }
+        statements.push(location);
+    }
+
+    return Ok(());
+}
+
+/// Drops a `;` comment which is not inside quotes.
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    for (idx, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if open == c => quote = None,
+            (None, ';') => return &text[..idx],
+            _ => (),
+        }
+    }
+
+    return text;
+}
+
+/// Splits `label: rest`.
+fn split_label(text: &str) -> Option<(&str, &str)> {
+    let end = text.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '@'))?;
+    return match text[end..].strip_prefix(':') {
+        Some(rest) if end > 0 => Some((&text[..end], rest)),
+        _ => None,
+    };
+}
+
+/// Splits arguments on commas outside of quotes and parentheses.
+fn split_arguments(text: &str) -> Vec<&str> {
+    let mut arguments = vec![];
+    let (mut depth, mut quote, mut start) = (0, None, 0);
+    for (idx, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if open == c => quote = None,
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                arguments.push(text[start..idx].trim());
+                start = idx + 1;
+            }
+            _ => (),
+        }
+    }
+    arguments.push(text[start..].trim());
+
+    return arguments;
+}
+
+fn parse_string(text: &str) -> Result<&str, String> {
+    return text
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .ok_or_else(|| format!("expected a quoted string, found \"{text}\""));
+}
+
+fn parse_expressions(arguments: &str, scope: &str) -> Result<Vec<Expr>, AsmErrorKind> {
+    return split_arguments(arguments)
+        .into_iter()
+        .map(|argument| expression::parse(argument, scope).map_err(AsmErrorKind::Syntax))
+        .collect();
+}
+
+fn parse_directive(directive: &str, arguments: &str, scope: &str) -> Result<Item, AsmErrorKind> {
+    let single = |arguments: &str| -> Result<Expr, AsmErrorKind> {
+        return expression::parse(arguments, scope).map_err(AsmErrorKind::Syntax);
+    };
+
+    return match directive.to_ascii_lowercase().as_str() {
+        "org" => Ok(Item::Org(single(arguments)?)),
+        "word" => Ok(Item::Words(parse_expressions(arguments, scope)?)),
+        "byte" => {
+            let mut data = vec![];
+            for argument in split_arguments(arguments) {
+                if argument.starts_with('"') {
+                    let text = parse_string(argument).map_err(AsmErrorKind::Syntax)?;
+                    data.push(Data::Text(text.bytes().collect()));
+                } else {
+                    data.push(Data::Value(single(argument)?));
+                }
+            }
+            Ok(Item::Bytes(data))
+        }
+        "res" => {
+            let mut values = parse_expressions(arguments, scope)?.into_iter();
+            match (values.next(), values.next(), values.next()) {
+                (Some(count), fill, None) => Ok(Item::Reserve(count, fill)),
+                _ => Err(AsmErrorKind::Syntax(
+                    ".res expects a count and an optional fill value".to_string(),
+                )),
+            }
+        }
+        "segment" => {
+            let name = parse_string(arguments).map_err(AsmErrorKind::Syntax)?;
+            SegmentId::from_name(name)
+                .map(Item::Segment)
+                .ok_or_else(|| AsmErrorKind::UnknownSegment(name.to_string()))
+        }
+        "import" => Ok(Item::Import(parse_names(arguments)?, false)),
+        "importzp" => Ok(Item::Import(parse_names(arguments)?, true)),
+        "export" => Ok(Item::Export(parse_names(arguments)?)),
+        _ => Err(AsmErrorKind::UnknownDirective(format!(".{directive}"))),
+    };
+}
+
+fn parse_names(arguments: &str) -> Result<Vec<String>, AsmErrorKind> {
+    return split_arguments(arguments)
+        .into_iter()
+        .map(|name| {
+            if !expression::is_identifier(name) {
+                return Err(AsmErrorKind::Syntax(format!("invalid symbol \"{name}\"")));
+            }
+            Ok(name.to_string())
+        })
+        .collect();
+}
+
+/// Index of the parenthesis closing the one opening `text`.
+fn closing_parenthesis(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (idx, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    return None;
+}
+
+fn parse_operand(text: &str, scope: &str) -> Result<Operand, String> {
+    let mut quote = None;
+    let compact: String = text
+        .chars()
+        .filter(|c| {
+            if *c == '\'' {
+                quote = if quote.is_some() { None } else { Some(*c) };
+            }
+            quote.is_some() || !c.is_whitespace()
+        })
+        .collect();
+    let upper = compact.to_ascii_uppercase();
+    let parse = |text: &str| expression::parse(text, scope);
+    let len = compact.len();
+
+    if compact.is_empty() {
+        return Ok(Operand::None);
+    }
+    if upper == "A" {
+        return Ok(Operand::Accumulator);
+    }
+    if let Some(value) = compact.strip_prefix('#') {
+        return Ok(Operand::Immediate(parse(value)?));
+    }
+    if compact.starts_with('(') {
+        let closing = closing_parenthesis(&compact);
+        if upper.ends_with(",X)") && closing == Some(len - 1) {
+            return Ok(Operand::IndexedIndirectX(parse(&compact[1..len - 3])?));
+        }
+        if upper.ends_with("),Y") && closing == Some(len - 3) {
+            return Ok(Operand::IndirectIndexedY(parse(&compact[1..len - 3])?));
+        }
+        // only JMP has this mode, `lda ($12)` is rejected when selecting the mode
+        if closing == Some(len - 1) {
+            return Ok(Operand::Indirect(parse(&compact[1..len - 1])?));
+        }
+    }
+    for index in ['X', 'Y'] {
+        if upper.ends_with(&format!(",{index}")) {
+            return Ok(Operand::Address(parse(&compact[..len - 2])?, Some(index)));
+        }
+    }
+
+    return Ok(Operand::Address(parse(&compact)?, None));
+}
+
+/// Size and range of an operand or data value.
+#[derive(Copy, Clone, PartialEq)]
+enum Field {
+    /// Signed or unsigned byte.
+    Byte,
+    ZeroPage,
+    Word,
+}
+
+/// Bytes emitted by a statement with relocations relative to its first byte.
+type Encoded = (Vec<Byte>, Vec<Relocation>);
+
+struct Assembler {
+    relocatable: bool,
+    symbols: HashMap<String, Value>,
+    zero_page_imports: HashSet<String>,
+    imports: Vec<String>,
+    labels: BTreeMap<String, Word>,
+    constants: BTreeMap<String, i64>,
+    segment: SegmentId,
+    counters: [u32; 4],
+    image: Image,
+    object: Object,
+}
+
+impl Assembler {
+    fn new(relocatable: bool) -> Self {
+        return Assembler {
+            relocatable,
+            symbols: HashMap::new(),
+            zero_page_imports: HashSet::new(),
+            imports: vec![],
+            labels: BTreeMap::new(),
+            constants: BTreeMap::new(),
+            segment: SegmentId::Text,
+            counters: [0; 4],
+            image: Image::default(),
+            object: Object::default(),
+        };
+    }
+
+    fn counter(&self) -> u32 {
+        return self.counters[self.segment.index()];
+    }
+
+    fn set_counter(&mut self, value: u32) {
+        self.counters[self.segment.index()] = value;
+    }
+
+    /// Value of `*`.
+    fn here(&self) -> Value {
+        let counter = i64::from(self.counter());
+        if self.relocatable {
+            return Value::relative(Base::Segment(self.segment), counter);
+        }
+
+        return Value::constant(counter);
+    }
+
+    fn define(&mut self, statement: &Statement, name: &str, value: Value) -> Result<(), AsmError> {
+        if self.symbols.insert(name.to_string(), value).is_some() {
+            return Err(statement.error(AsmErrorKind::DuplicateSymbol(name.to_string())));
+        }
+
+        return Ok(());
+    }
+
+    fn evaluate(&self, expr: &Expr, current: &Value) -> Result<Value, EvalError> {
+        return expr.evaluate_relocatable(&|name| self.symbols.get(name).cloned(), current);
+    }
+
+    /// Evaluates in the second pass, when every symbol has to be known.
+    fn resolve(&self, statement: &Statement, expr: &Expr) -> Result<Value, AsmError> {
+        return self
+            .evaluate(expr, &self.here())
+            .map_err(|err| statement.error(eval_error_kind(err)));
+    }
+
+    /// Evaluates a number which decides the layout, so it must be known in the first pass.
+    fn resolve_now(&self, statement: &Statement, expr: &Expr) -> Result<i64, AsmError> {
+        let value = self.evaluate(expr, &self.here()).map_err(|err| {
+            statement.error(match err {
+                EvalError::Undefined(name) => AsmErrorKind::ForwardReference(name),
+                err => eval_error_kind(err),
+            })
+        })?;
+
+        return match value.base {
+            None => Ok(value.offset),
+            Some(_) => Err(statement.error(AsmErrorKind::NotRelocatable)),
+        };
+    }
+
+    fn fits_zero_page(&self, value: &Value) -> bool {
+        return match (&value.base, value.part) {
+            (None, _) => (0..=0xFF).contains(&value.offset),
+            (Some(_), Part::Low | Part::High) => true,
+            (Some(Base::Segment(segment)), Part::Whole) => *segment == SegmentId::ZeroPage,
+            (Some(Base::Import(name)), Part::Whole) => self.zero_page_imports.contains(name),
+        };
+    }
+
+    fn select_mode(&self, statement: &Statement) -> Result<Mode, AsmError> {
+        let (mnemonic, operand) = match &statement.item {
+            Item::Instruction(mnemonic, operand) => (*mnemonic, operand),
+            _ => unreachable!("only instructions have an addressing mode"),
+        };
+        let supports = |mode| opcodes::find(mnemonic, mode).is_some();
+        let invalid = || statement.error(AsmErrorKind::InvalidAddressingMode(mnemonic.to_string()));
+
+        let mode = match operand {
+            Operand::None if supports(Mode::Accumulator) => Mode::Accumulator,
+            Operand::None => Mode::Implied,
+            Operand::Accumulator => Mode::Accumulator,
+            Operand::Immediate(_) => Mode::Immediate,
+            Operand::Indirect(_) => Mode::Indirect,
+            Operand::IndexedIndirectX(_) => Mode::IndexedIndirectX,
+            Operand::IndirectIndexedY(_) => Mode::IndirectIndexedY,
+            Operand::Address(_, None) if supports(Mode::Relative) => Mode::Relative,
+            Operand::Address(expr, index) => {
+                let (zero_page, absolute) = match index {
+                    None => (Mode::ZeroPage, Mode::Absolute),
+                    Some('X') => (Mode::ZeroPageX, Mode::AbsoluteX),
+                    _ => (Mode::ZeroPageY, Mode::AbsoluteY),
+                };
+                let fits_zero_page = self
+                    .evaluate(expr, &self.here())
+                    .is_ok_and(|value| self.fits_zero_page(&value));
+                if supports(zero_page) && (fits_zero_page || !supports(absolute)) {
+                    zero_page
+                } else {
+                    absolute
+                }
+            }
+        };
+        if !supports(mode) {
+            return Err(invalid());
+        }
+
+        return Ok(mode);
+    }
+
+    fn run(mut self, statements: &[Statement]) -> Result<Assembly, AsmError> {
+        let modes = self.first_pass(statements)?;
+        self.second_pass(statements, &modes)?;
+
+        return Ok(Assembly {
+            image: self.image,
+            labels: self.labels,
+            constants: self.constants,
+        });
+    }
+
+    fn run_object(mut self, statements: &[Statement]) -> Result<Object, AsmError> {
+        let modes = self.first_pass(statements)?;
+        self.second_pass(statements, &modes)?;
+
+        for id in SegmentId::ALL {
+            self.object.segment_mut(id).len = self.counters[id.index()] as Word;
+        }
+        for statement in statements {
+            if let Item::Export(names) = &statement.item {
+                for name in names {
+                    let export = self.export(statement, name)?;
+                    self.object.exports.push(export);
+                }
+            }
+        }
+        self.object.imports = self.imports;
+
+        return Ok(self.object);
+    }
+
+    fn export(&self, statement: &Statement, name: &str) -> Result<Export, AsmError> {
+        let value = self
+            .symbols
+            .get(name)
+            .ok_or_else(|| statement.error(AsmErrorKind::UndefinedSymbol(name.to_string())))?;
+        let segment = match (&value.base, value.part) {
+            (None, _) => None,
+            (Some(Base::Segment(segment)), Part::Whole) => Some(*segment),
+            _ => return Err(statement.error(AsmErrorKind::NotRelocatable)),
+        };
+
+        return Ok(Export {
+            name: name.to_string(),
+            segment,
+            value: checked_word(statement, value.offset)?,
+        });
+    }
+
+    fn require_relocatable(&self, statement: &Statement, directive: &str) -> Result<(), AsmError> {
+        if !self.relocatable {
+            return Err(statement.error(AsmErrorKind::RelocatableOnly(directive.to_string())));
+        }
+
+        return Ok(());
+    }
+
+    /// Bytes can only be emitted where the object stores them.
+    fn require_initialized(&self, statement: &Statement) -> Result<(), AsmError> {
+        if matches!(self.segment, SegmentId::Bss | SegmentId::ZeroPage) {
+            return Err(statement.error(AsmErrorKind::UninitializedSegment));
+        }
+
+        return Ok(());
+    }
+
+    /// Assigns addresses to labels and picks addressing modes.
+    fn first_pass(&mut self, statements: &[Statement]) -> Result<Vec<Option<Mode>>, AsmError> {
+        let mut modes = vec![None; statements.len()];
+        let mut pending = vec![];
+
+        for (idx, statement) in statements.iter().enumerate() {
+            let size = match &statement.item {
+                Item::Label(name) => {
+                    self.define(statement, name, self.here())?;
+                    if !self.relocatable {
+                        self.labels.insert(name.clone(), self.counter() as Word);
+                    }
+                    0
+                }
+                Item::Constant(name, expr) => {
+                    match self.evaluate(expr, &self.here()) {
+                        Ok(value) => self.define(statement, name, value)?,
+                        Err(EvalError::Undefined(_)) => pending.push((statement, self.here())),
+                        Err(err) => return Err(statement.error(eval_error_kind(err))),
+                    }
+                    0
+                }
+                Item::Org(expr) => {
+                    if self.relocatable {
+                        return Err(statement.error(AsmErrorKind::AbsoluteOnly(".org".to_string())));
+                    }
+                    let value = self.resolve_now(statement, expr)?;
+                    self.set_counter(checked_word(statement, value)?.into());
+                    0
+                }
+                Item::Segment(segment) => {
+                    self.require_relocatable(statement, ".segment")?;
+                    self.segment = *segment;
+                    0
+                }
+                Item::Import(names, zero_page) => {
+                    self.require_relocatable(statement, ".import")?;
+                    for name in names {
+                        self.define(
+                            statement,
+                            name,
+                            Value::relative(Base::Import(name.clone()), 0),
+                        )?;
+                        self.imports.push(name.clone());
+                        if *zero_page {
+                            self.zero_page_imports.insert(name.clone());
+                        }
+                    }
+                    0
+                }
+                Item::Export(_) => {
+                    self.require_relocatable(statement, ".export")?;
+                    0
+                }
+                Item::Bytes(data) => {
+                    self.require_initialized(statement)?;
+                    data.iter()
+                        .map(|data| match data {
+                            Data::Value(_) => 1,
+                            Data::Text(text) => text.len(),
+                        })
+                        .sum()
+                }
+                Item::Words(values) => {
+                    self.require_initialized(statement)?;
+                    2 * values.len()
+                }
+                Item::Reserve(count, fill) => {
+                    if fill.is_some() {
+                        self.require_initialized(statement)?;
+                    }
+                    let count = self.resolve_now(statement, count)?;
+                    usize::try_from(count)
+                        .map_err(|_| statement.error(AsmErrorKind::ValueOutOfRange(count)))?
+                }
+                Item::Instruction(..) => {
+                    self.require_initialized(statement)?;
+                    let mode = self.select_mode(statement)?;
+                    modes[idx] = Some(mode);
+                    1 + mode.operand_len()
+                }
+            };
+
+            let end = self.counter() + size as u32;
+            let limit = if self.relocatable { 0xFFFF } else { 0x10000 };
+            if end > limit {
+                return Err(statement.error(AsmErrorKind::AddressOverflow));
+            }
+            self.set_counter(end);
+        }
+
+        self.resolve_pending(pending)?;
+
+        return Ok(modes);
+    }
+
+    /// Defines constants referring to symbols defined after them.
+    fn resolve_pending(&mut self, mut pending: Vec<(&Statement, Value)>) -> Result<(), AsmError> {
+        while !pending.is_empty() {
+            let count = pending.len();
+            let mut unresolved = vec![];
+            for (statement, current) in pending {
+                let Item::Constant(name, expr) = &statement.item else {
+                    unreachable!("only constants are deferred");
+                };
+                match self.evaluate(expr, &current) {
+                    Ok(value) => self.define(statement, name, value)?,
+                    Err(EvalError::Undefined(_)) => unresolved.push((statement, current)),
+                    Err(err) => return Err(statement.error(eval_error_kind(err))),
+                }
+            }
+
+            if unresolved.len() == count {
+                let (statement, current) = &unresolved[0];
+                let Item::Constant(_, expr) = &statement.item else {
+                    unreachable!("only constants are deferred");
+                };
+                return match self.evaluate(expr, current) {
+                    Err(err) => Err(statement.error(eval_error_kind(err))),
+                    Ok(_) => Ok(()),
+                };
+            }
+            pending = unresolved;
+        }
+
+        return Ok(());
+    }
+
+    fn second_pass(
+        &mut self,
+        statements: &[Statement],
+        modes: &[Option<Mode>],
+    ) -> Result<(), AsmError> {
+        self.segment = SegmentId::Text;
+        self.counters = [0; 4];
+
+        for (statement, mode) in statements.iter().zip(modes) {
+            let mut bytes = vec![];
+            let mut relocations = vec![];
+            match &statement.item {
+                Item::Label(_) | Item::Import(..) | Item::Export(_) => (),
+                Item::Constant(name, _) => {
+                    let value = &self.symbols[name];
+                    if value.base.is_none() {
+                        self.constants.insert(name.clone(), value.offset);
+                    }
+                }
+                Item::Org(expr) => {
+                    let value = self.resolve_now(statement, expr)?;
+                    self.set_counter(value as u32);
+                }
+                Item::Segment(segment) => self.segment = *segment,
+                Item::Bytes(data) => {
+                    for data in data {
+                        match data {
+                            Data::Value(expr) => {
+                                let value = self.resolve(statement, expr)?;
+                                let offset = bytes.len();
+                                self.encode_field(
+                                    statement,
+                                    value,
+                                    Field::Byte,
+                                    (&mut bytes, &mut relocations),
+                                    offset,
+                                )?;
+                            }
+                            Data::Text(text) => bytes.extend(text),
+                        }
+                    }
+                }
+                Item::Words(values) => {
+                    for expr in values {
+                        let value = self.resolve(statement, expr)?;
+                        let offset = bytes.len();
+                        self.encode_field(
+                            statement,
+                            value,
+                            Field::Word,
+                            (&mut bytes, &mut relocations),
+                            offset,
+                        )?;
+                    }
+                }
+                Item::Reserve(count, fill) => {
+                    let count = self.resolve_now(statement, count)? as usize;
+                    match fill {
+                        Some(fill) => {
+                            let fill = self.resolve_now(statement, fill)?;
+                            bytes = vec![checked_byte(statement, fill)?; count];
+                        }
+                        None if self.relocatable && self.require_initialized(statement).is_ok() => {
+                            bytes = vec![0; count];
+                        }
+                        None => self.set_counter(self.counter() + count as u32),
+                    }
+                }
+                Item::Instruction(mnemonic, operand) => {
+                    let mode = mode.expect("addressing mode picked in the first pass");
+                    (bytes, relocations) = self.encode(statement, mnemonic, mode, operand)?;
+                }
+            }
+
+            self.emit(bytes, relocations);
+        }
+
+        return Ok(());
+    }
+
+    fn emit(&mut self, bytes: Vec<Byte>, relocations: Vec<Relocation>) {
+        if bytes.is_empty() {
+            return;
+        }
+
+        let size = bytes.len() as u32;
+        if self.relocatable {
+            let segment = self.object.segment_mut(self.segment);
+            let start = segment.bytes.len();
+            segment.bytes.extend(bytes);
+            segment
+                .relocations
+                .extend(relocations.into_iter().map(|relocation| Relocation {
+                    offset: start + relocation.offset,
+                    ..relocation
+                }));
+        } else {
+            self.image.push(self.counter() as Word, bytes);
+        }
+        self.set_counter(self.counter() + size);
+    }
+
+    fn relocation_target(&self, base: &Base) -> RelocationTarget {
+        return match base {
+            Base::Segment(segment) => RelocationTarget::Segment(*segment),
+            Base::Import(name) => RelocationTarget::Import(
+                self.imports
+                    .iter()
+                    .position(|import| import == name)
+                    .expect("imports are declared in the first pass"),
+            ),
+        };
+    }
+
+    /// Appends `value` to `bytes`, recording a relocation at `offset` when it is relocatable.
+    fn encode_field(
+        &self,
+        statement: &Statement,
+        value: Value,
+        field: Field,
+        (bytes, relocations): (&mut Vec<Byte>, &mut Vec<Relocation>),
+        offset: usize,
+    ) -> Result<(), AsmError> {
+        let base = match &value.base {
+            Some(base) => base,
+            None => {
+                match field {
+                    Field::Byte => bytes.push(checked_byte(statement, value.offset)?),
+                    Field::ZeroPage => bytes.push(checked_zero_page(statement, value.offset)?),
+                    Field::Word => {
+                        bytes.extend(checked_word(statement, value.offset)?.to_le_bytes())
+                    }
+                }
+                return Ok(());
+            }
+        };
+
+        let kind = match (value.part, field) {
+            (Part::Low, Field::Byte | Field::ZeroPage) => {
+                bytes.push(value.offset as Byte);
+                RelocationKind::Low
+            }
+            (Part::High, Field::Byte | Field::ZeroPage) => {
+                bytes.push((value.offset >> 8) as Byte);
+                RelocationKind::High {
+                    low: value.offset as Byte,
+                }
+            }
+            (Part::Whole, Field::Word) if (-0xFFFF..=0xFFFF).contains(&value.offset) => {
+                bytes.extend((value.offset as Word).to_le_bytes());
+                RelocationKind::Word
+            }
+            (Part::Whole, Field::Byte | Field::ZeroPage)
+                if self.fits_zero_page(&value) && (0..=0xFF).contains(&value.offset) =>
+            {
+                bytes.push(value.offset as Byte);
+                RelocationKind::Low
+            }
+            _ => return Err(statement.error(AsmErrorKind::NotRelocatable)),
+        };
+        relocations.push(Relocation {
+            offset,
+            kind,
+            target: self.relocation_target(base),
+        });
+
+        return Ok(());
+    }
+
+    fn encode(
+        &self,
+        statement: &Statement,
+        mnemonic: &str,
+        mode: Mode,
+        operand: &Operand,
+    ) -> Result<Encoded, AsmError> {
+        let opcode = opcodes::find(mnemonic, mode)
+            .expect("addressing mode checked in the first pass")
+            .opcode;
+        let expr = match operand {
+            Operand::None | Operand::Accumulator => return Ok((vec![opcode], vec![])),
+            Operand::Immediate(expr)
+            | Operand::Address(expr, _)
+            | Operand::Indirect(expr)
+            | Operand::IndexedIndirectX(expr)
+            | Operand::IndirectIndexedY(expr) => expr,
+        };
+        let value = self.resolve(statement, expr)?;
+        let mut bytes = vec![opcode];
+        let mut relocations = vec![];
+
+        let field = match mode {
+            Mode::Relative => {
+                let here = self.here();
+                if value.base != here.base || value.part != Part::Whole {
+                    return Err(statement.error(AsmErrorKind::NotRelocatable));
+                }
+                let offset = value.offset - (here.offset + 2);
+                if !(-128..=127).contains(&offset) {
+                    return Err(statement.error(AsmErrorKind::BranchOutOfRange(offset)));
+                }
+                bytes.push(offset as Byte);
+                return Ok((bytes, relocations));
+            }
+            Mode::Immediate => Field::Byte,
+            _ if mode.operand_len() == 1 => Field::ZeroPage,
+            _ => Field::Word,
+        };
+        self.encode_field(statement, value, field, (&mut bytes, &mut relocations), 1)?;
+
+        return Ok((bytes, relocations));
+    }
+}
+
+fn eval_error_kind(err: EvalError) -> AsmErrorKind {
+    return match err {
+        EvalError::Undefined(name) => AsmErrorKind::UndefinedSymbol(name),
+        EvalError::DivisionByZero => AsmErrorKind::DivisionByZero,
+        EvalError::NotRelocatable => AsmErrorKind::NotRelocatable,
+    };
+}
+
+/// Bytes may be given as signed or unsigned values.
+fn checked_byte(statement: &Statement, value: i64) -> Result<Byte, AsmError> {
+    if !(-0x80..=0xFF).contains(&value) {
+        return Err(statement.error(AsmErrorKind::ValueOutOfRange(value)));
+    }
+
+    return Ok(value as Byte);
+}
+
+fn checked_zero_page(statement: &Statement, value: i64) -> Result<Byte, AsmError> {
+    if !(0..=0xFF).contains(&value) {
+        return Err(statement.error(AsmErrorKind::ValueOutOfRange(value)));
+    }
+
+    return Ok(value as Byte);
+}
+
+fn checked_word(statement: &Statement, value: i64) -> Result<Word, AsmError> {
+    if !(0..=0xFFFF).contains(&value) {
+        return Err(statement.error(AsmErrorKind::ValueOutOfRange(value)));
+    }
+
+    return Ok(value as Word);
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,391 @@
+//! Expressions used as operands and directive arguments.
+//!
+//! Numbers are decimal, `$` or `0x` prefixed hexadecimal, `%` prefixed binary
+//! or a character in single quotes. `*` is the address of the current
+//! statement. Operators, from the loosest binding: `|`, `^`, `&`, `<<` `>>`,
+//! `+` `-`, `*` `/` `%`, and the unary `-` and `~`. A leading `<` or `>` takes
+//! the low or high byte of the whole expression following it.
+
+use crate::{consts::Word, loaders::o65::SegmentId};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum UnaryOp {
+    Negate,
+    Not,
+    Low,
+    High,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    And,
+    Or,
+    Xor,
+    ShiftLeft,
+    ShiftRight,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(i64),
+    Symbol(String),
+    Current,
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EvalError {
+    Undefined(String),
+    DivisionByZero,
+    /// The result is not a relocatable address plus or minus a constant.
+    NotRelocatable,
+}
+
+/// What a relocatable value is relative to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Base {
+    Segment(SegmentId),
+    Import(String),
+}
+
+/// Which part of the relocated address a value stands for.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Part {
+    Whole,
+    Low,
+    High,
+}
+
+/// Result of evaluating an expression in a relocatable object: `offset`
+/// from the start of `base`, or a plain number when there is no base.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Value {
+    pub offset: i64,
+    pub base: Option<Base>,
+    pub part: Part,
+}
+
+impl Value {
+    pub fn constant(value: i64) -> Self {
+        return Value {
+            offset: value,
+            base: None,
+            part: Part::Whole,
+        };
+    }
+
+    pub fn relative(base: Base, offset: i64) -> Self {
+        return Value {
+            offset,
+            base: Some(base),
+            part: Part::Whole,
+        };
+    }
+
+    fn constant_value(&self) -> Option<i64> {
+        return match self.base {
+            None => Some(self.offset),
+            Some(_) => None,
+        };
+    }
+}
+
+fn apply_unary(op: UnaryOp, value: i64) -> i64 {
+    return match op {
+        UnaryOp::Negate => value.wrapping_neg(),
+        UnaryOp::Not => !value,
+        UnaryOp::Low => value & 0xFF,
+        UnaryOp::High => (value >> 8) & 0xFF,
+    };
+}
+
+fn apply_binary(op: BinaryOp, lhs: i64, rhs: i64) -> Result<i64, EvalError> {
+    return match op {
+        BinaryOp::Add => Ok(lhs.wrapping_add(rhs)),
+        BinaryOp::Subtract => Ok(lhs.wrapping_sub(rhs)),
+        BinaryOp::Multiply => Ok(lhs.wrapping_mul(rhs)),
+        BinaryOp::Divide => lhs.checked_div(rhs).ok_or(EvalError::DivisionByZero),
+        BinaryOp::Remainder => lhs.checked_rem(rhs).ok_or(EvalError::DivisionByZero),
+        BinaryOp::And => Ok(lhs & rhs),
+        BinaryOp::Or => Ok(lhs | rhs),
+        BinaryOp::Xor => Ok(lhs ^ rhs),
+        BinaryOp::ShiftLeft => Ok(lhs.checked_shl(rhs as u32).unwrap_or(0)),
+        BinaryOp::ShiftRight => Ok(lhs.checked_shr(rhs as u32).unwrap_or(0)),
+    };
+}
+
+impl Expr {
+    /// Evaluates the expression, `lookup` resolves symbols and `current` is the value of `*`.
+    pub fn evaluate(
+        &self,
+        lookup: &dyn Fn(&str) -> Option<i64>,
+        current: Word,
+    ) -> Result<i64, EvalError> {
+        return match self {
+            Expr::Number(value) => Ok(*value),
+            Expr::Symbol(name) => lookup(name).ok_or_else(|| EvalError::Undefined(name.clone())),
+            Expr::Current => Ok(i64::from(current)),
+            Expr::Unary(op, operand) => Ok(apply_unary(*op, operand.evaluate(lookup, current)?)),
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = lhs.evaluate(lookup, current)?;
+                let rhs = rhs.evaluate(lookup, current)?;
+                apply_binary(*op, lhs, rhs)
+            }
+        };
+    }
+
+    /// Like [`Expr::evaluate`], but symbols and `*` may be relative to a
+    /// segment or an import. Such values can only be offset by constants,
+    /// subtracted from values with the same base or split with `<` and `>`.
+    pub fn evaluate_relocatable(
+        &self,
+        lookup: &dyn Fn(&str) -> Option<Value>,
+        current: &Value,
+    ) -> Result<Value, EvalError> {
+        return match self {
+            Expr::Number(value) => Ok(Value::constant(*value)),
+            Expr::Symbol(name) => lookup(name).ok_or_else(|| EvalError::Undefined(name.clone())),
+            Expr::Current => Ok(current.clone()),
+            Expr::Unary(op, operand) => {
+                let value = operand.evaluate_relocatable(lookup, current)?;
+                match (value.constant_value(), op) {
+                    (Some(value), _) => Ok(Value::constant(apply_unary(*op, value))),
+                    (None, UnaryOp::Low | UnaryOp::High) if value.part == Part::Whole => {
+                        Ok(Value {
+                            part: if *op == UnaryOp::Low {
+                                Part::Low
+                            } else {
+                                Part::High
+                            },
+                            ..value
+                        })
+                    }
+                    _ => Err(EvalError::NotRelocatable),
+                }
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = lhs.evaluate_relocatable(lookup, current)?;
+                let rhs = rhs.evaluate_relocatable(lookup, current)?;
+                if let (Some(lhs), Some(rhs)) = (lhs.constant_value(), rhs.constant_value()) {
+                    return Ok(Value::constant(apply_binary(*op, lhs, rhs)?));
+                }
+                if lhs.part != Part::Whole || rhs.part != Part::Whole {
+                    return Err(EvalError::NotRelocatable);
+                }
+                match (op, rhs.constant_value(), lhs.constant_value()) {
+                    (BinaryOp::Add, Some(rhs), _) => Ok(Value {
+                        offset: lhs.offset.wrapping_add(rhs),
+                        ..lhs
+                    }),
+                    (BinaryOp::Add, None, Some(lhs)) => Ok(Value {
+                        offset: rhs.offset.wrapping_add(lhs),
+                        ..rhs
+                    }),
+                    (BinaryOp::Subtract, Some(rhs), _) => Ok(Value {
+                        offset: lhs.offset.wrapping_sub(rhs),
+                        ..lhs
+                    }),
+                    (BinaryOp::Subtract, None, None) if lhs.base == rhs.base => {
+                        Ok(Value::constant(lhs.offset.wrapping_sub(rhs.offset)))
+                    }
+                    _ => Err(EvalError::NotRelocatable),
+                }
+            }
+        };
+    }
+}
+
+/// Whether `name` is a valid global symbol name.
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    return match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    };
+}
+
+/// Name a local `@label` gets inside the scope of the global label `scope`.
+pub fn local_name(scope: &str, label: &str) -> String {
+    return format!("{scope}{label}");
+}
+
+/// Parses `text`, local `@labels` are qualified with `scope`.
+pub fn parse(text: &str, scope: &str) -> Result<Expr, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        scope,
+    };
+    let expr = parser.expression()?;
+    parser.skip_whitespace();
+    if let Some(c) = parser.peek() {
+        return Err(format!(
+            "unexpected '{c}' in expression \"{}\"",
+            text.trim()
+        ));
+    }
+
+    return Ok(expr);
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    scope: &'a str,
+}
+
+const BINARY_LEVELS: [&[(&str, BinaryOp)]; 6] = [
+    &[("|", BinaryOp::Or)],
+    &[("^", BinaryOp::Xor)],
+    &[("&", BinaryOp::And)],
+    &[("<<", BinaryOp::ShiftLeft), (">>", BinaryOp::ShiftRight)],
+    &[("+", BinaryOp::Add), ("-", BinaryOp::Subtract)],
+    &[
+        ("*", BinaryOp::Multiply),
+        ("/", BinaryOp::Divide),
+        ("%", BinaryOp::Remainder),
+    ],
+];
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.pos).copied();
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        let matches = token
+            .chars()
+            .enumerate()
+            .all(|(offset, c)| self.chars.get(self.pos + offset) == Some(&c));
+        if matches {
+            self.pos += token.len();
+        }
+
+        return matches;
+    }
+
+    fn expression(&mut self) -> Result<Expr, String> {
+        for (token, op) in [("<", UnaryOp::Low), (">", UnaryOp::High)] {
+            if self.eat(token) {
+                return Ok(Expr::Unary(op, Box::new(self.binary(0)?)));
+            }
+        }
+
+        return self.binary(0);
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        if level == BINARY_LEVELS.len() {
+            return self.unary();
+        }
+
+        let mut lhs = self.binary(level + 1)?;
+        'operators: loop {
+            for (token, op) in BINARY_LEVELS[level] {
+                if self.eat(token) {
+                    let rhs = self.binary(level + 1)?;
+                    lhs = Expr::Binary(*op, Box::new(lhs), Box::new(rhs));
+                    continue 'operators;
+                }
+            }
+
+            return Ok(lhs);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("-") {
+            return Ok(Expr::Unary(UnaryOp::Negate, Box::new(self.unary()?)));
+        }
+        if self.eat("~") {
+            return Ok(Expr::Unary(UnaryOp::Not, Box::new(self.unary()?)));
+        }
+
+        return self.primary();
+    }
+
+    fn take_while(&mut self, accept: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&accept) {
+            self.pos += 1;
+        }
+
+        return self.chars[start..self.pos].iter().collect();
+    }
+
+    fn number(&mut self, radix: u32, prefix: &str) -> Result<Expr, String> {
+        let digits = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        let digits = digits.replace('_', "");
+        return i64::from_str_radix(&digits, radix)
+            .map(Expr::Number)
+            .map_err(|_| format!("invalid number \"{prefix}{digits}\""));
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        self.skip_whitespace();
+        let c = self.peek().ok_or("missing value in expression")?;
+        if c == '(' {
+            self.pos += 1;
+            let expr = self.expression()?;
+            if !self.eat(")") {
+                return Err("missing ')' in expression".to_string());
+            }
+            return Ok(expr);
+        }
+
+        self.pos += 1;
+        return match c {
+            '*' => Ok(Expr::Current),
+            '$' => self.number(16, "$"),
+            '%' => self.number(2, "%"),
+            '0' if self.peek().is_some_and(|next| next == 'x' || next == 'X') => {
+                self.pos += 1;
+                self.number(16, "0x")
+            }
+            '0'..='9' => {
+                self.pos -= 1;
+                self.number(10, "")
+            }
+            '\'' => {
+                let value = self.peek().ok_or("unterminated character")?;
+                self.pos += 1;
+                if !self.eat("'") {
+                    return Err("unterminated character".to_string());
+                }
+                Ok(Expr::Number(i64::from(u32::from(value))))
+            }
+            '@' => {
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                if name.is_empty() {
+                    return Err("missing local label name after '@'".to_string());
+                }
+                Ok(Expr::Symbol(local_name(self.scope, &format!("@{name}"))))
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                self.pos -= 1;
+                Ok(Expr::Symbol(
+                    self.take_while(|c| c.is_ascii_alphanumeric() || c == '_'),
+                ))
+            }
+            c => Err(format!("unexpected '{c}' in expression")),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests;
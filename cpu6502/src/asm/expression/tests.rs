@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod parse {
+    use crate::asm::expression::{parse, EvalError, Expr};
+
+    fn eval(text: &str) -> i64 {
+        let lookup = |name: &str| match name {
+            "screen" => Some(0x0400),
+            "main@loop" => Some(0x0210),
+            _ => None,
+        };
+        return parse(text, "main")
+            .unwrap()
+            .evaluate(&lookup, 0x0200)
+            .unwrap();
+    }
+
+    #[test]
+    fn should_parse_number_formats() {
+        assert_eq!(eval("$FF"), 0xFF);
+        assert_eq!(eval("0x1234"), 0x1234);
+        assert_eq!(eval("%1010"), 10);
+        assert_eq!(eval("42"), 42);
+        assert_eq!(eval("'A'"), 0x41);
+    }
+
+    #[test]
+    fn should_respect_operator_precedence() {
+        assert_eq!(eval("2 + 3 * 4"), 14);
+        assert_eq!(eval("(2 + 3) * 4"), 20);
+        assert_eq!(eval("1 << 4 | 1"), 17);
+        assert_eq!(eval("-2 + 5"), 3);
+        assert_eq!(eval("~0 & $FF"), 0xFF);
+        assert_eq!(eval("10 % 4"), 2);
+    }
+
+    #[test]
+    fn should_resolve_symbols_current_address_and_locals() {
+        assert_eq!(eval("screen + 40"), 0x0428);
+        assert_eq!(eval("* + 3"), 0x0203);
+        assert_eq!(eval("@loop"), 0x0210);
+    }
+
+    #[test]
+    fn should_take_bytes_of_whole_expression() {
+        assert_eq!(eval("<screen + $1FF"), 0xFF);
+        assert_eq!(eval(">screen + $1FF"), 0x05);
+    }
+
+    #[test]
+    fn should_report_undefined_symbols_and_division_by_zero() {
+        let lookup = |_: &str| None;
+
+        assert_eq!(
+            parse("later + 1", "").unwrap().evaluate(&lookup, 0),
+            Err(EvalError::Undefined("later".to_string()))
+        );
+        assert_eq!(
+            parse("1 / 0", "").unwrap().evaluate(&lookup, 0),
+            Err(EvalError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn should_reject_malformed_expressions() {
+        assert!(parse("1 +", "").is_err());
+        assert!(parse("(1", "").is_err());
+        assert!(parse("$G1", "").is_err());
+        assert!(parse("1 2", "").is_err());
+        assert_eq!(parse("7", ""), Ok(Expr::Number(7)));
+    }
+}
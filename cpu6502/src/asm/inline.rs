@@ -0,0 +1,546 @@
+//! Support for the [`asm6502!`](crate::asm6502) macro.
+//!
+//! The macro turns each statement into a [`Statement`] and assembles them with
+//! the `const fn`s below inside a `const` item, so a mistake in a program is
+//! reported by the compiler. Nothing here is meant to be called directly.
+
+use crate::{
+    consts::{Byte, Word},
+    opcodes::{Mode, OPCODES},
+};
+
+/// Operand of an instruction as written, before its addressing mode is picked.
+#[derive(Copy, Clone, Debug)]
+pub enum Operand {
+    None,
+    Accumulator,
+    Immediate(i64),
+    Address(i64),
+    AddressX(i64),
+    AddressY(i64),
+    /// `(value)`, which is only an addressing mode for `JMP`.
+    Indirect(i64),
+    IndexedIndirectX(i64),
+    IndirectIndexedY(i64),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum Statement {
+    Org(i64),
+    Bytes(&'static [i64]),
+    Words(&'static [i64]),
+    Instruction(&'static str, Operand),
+}
+
+/// Number of bytes `statements` assemble to.
+pub const fn assembled_size(statements: &[Statement]) -> usize {
+    let mut output = Output {
+        pairs: &mut [],
+        size: 0,
+        address: 0,
+    };
+    assemble_into(statements, &mut output);
+
+    return output.size;
+}
+
+/// Assembles `statements` into address/value pairs, `N` has to be their
+/// [`assembled_size`].
+pub const fn assemble<const N: usize>(statements: &[Statement]) -> [(Word, Byte); N] {
+    let mut pairs = [(0, 0); N];
+    let mut output = Output {
+        pairs: &mut pairs,
+        size: 0,
+        address: 0,
+    };
+    assemble_into(statements, &mut output);
+
+    return pairs;
+}
+
+/// Pairs written so far. Only the pairs fitting in `pairs` are stored, which
+/// lets the size of a program be computed by assembling it into nothing.
+struct Output<'a> {
+    pairs: &'a mut [(Word, Byte)],
+    size: usize,
+    address: i64,
+}
+
+impl Output<'_> {
+    const fn emit(&mut self, value: Byte) {
+        if self.address > 0xFFFF {
+            panic!("asm6502!: program runs past $FFFF");
+        }
+
+        if self.size < self.pairs.len() {
+            self.pairs[self.size] = (self.address as Word, value);
+        }
+        self.size += 1;
+        self.address += 1;
+    }
+
+    const fn emit_word(&mut self, value: Word) {
+        let [lo, hi] = value.to_le_bytes();
+        self.emit(lo);
+        self.emit(hi);
+    }
+}
+
+const fn assemble_into(statements: &[Statement], output: &mut Output) {
+    let mut idx = 0;
+    while idx < statements.len() {
+        match statements[idx] {
+            Statement::Org(address) => output.address = word("org", address) as i64,
+            Statement::Bytes(values) => {
+                let mut value = 0;
+                while value < values.len() {
+                    output.emit(byte("byte", values[value]));
+                    value += 1;
+                }
+            }
+            Statement::Words(values) => {
+                let mut value = 0;
+                while value < values.len() {
+                    output.emit_word(word("word", values[value]));
+                    value += 1;
+                }
+            }
+            Statement::Instruction(mnemonic, operand) => instruction(output, mnemonic, operand),
+        }
+        idx += 1;
+    }
+}
+
+const fn instruction(output: &mut Output, mnemonic: &str, operand: Operand) {
+    match operand {
+        Operand::None if find(mnemonic, Mode::Accumulator).is_some() => {
+            output.emit(opcode(mnemonic, Mode::Accumulator));
+        }
+        Operand::None => output.emit(opcode(mnemonic, Mode::Implied)),
+        Operand::Accumulator => output.emit(opcode(mnemonic, Mode::Accumulator)),
+        Operand::Immediate(value) => {
+            output.emit(opcode(mnemonic, Mode::Immediate));
+            output.emit(byte(mnemonic, value));
+        }
+        Operand::Address(value) if find(mnemonic, Mode::Relative).is_some() => {
+            let offset = value - (output.address + 2);
+            if offset < -128 || offset > 127 {
+                Message::new()
+                    .mnemonic(mnemonic)
+                    .text(" branch target is ")
+                    .number(offset)
+                    .text(" bytes away")
+                    .panic();
+            }
+            output.emit(opcode(mnemonic, Mode::Relative));
+            output.emit(offset as Byte);
+        }
+        Operand::Address(value) => address(output, mnemonic, value, Mode::ZeroPage, Mode::Absolute),
+        Operand::AddressX(value) => {
+            address(output, mnemonic, value, Mode::ZeroPageX, Mode::AbsoluteX);
+        }
+        Operand::AddressY(value) => {
+            address(output, mnemonic, value, Mode::ZeroPageY, Mode::AbsoluteY);
+        }
+        Operand::Indirect(value) if find(mnemonic, Mode::Indirect).is_none() => {
+            // the parentheses only group the value
+            address(output, mnemonic, value, Mode::ZeroPage, Mode::Absolute);
+        }
+        Operand::Indirect(value) => {
+            output.emit(opcode(mnemonic, Mode::Indirect));
+            output.emit_word(word(mnemonic, value));
+        }
+        Operand::IndexedIndirectX(value) => {
+            output.emit(opcode(mnemonic, Mode::IndexedIndirectX));
+            output.emit(zero_page(mnemonic, value));
+        }
+        Operand::IndirectIndexedY(value) => {
+            output.emit(opcode(mnemonic, Mode::IndirectIndexedY));
+            output.emit(zero_page(mnemonic, value));
+        }
+    }
+}
+
+/// Zero page form when `value` fits in a byte, absolute otherwise.
+const fn address(output: &mut Output, mnemonic: &str, value: i64, zero_page: Mode, absolute: Mode) {
+    let has_absolute = find(mnemonic, absolute).is_some();
+    if find(mnemonic, zero_page).is_some() && ((value >= 0 && value <= 0xFF) || !has_absolute) {
+        output.emit(opcode(mnemonic, zero_page));
+        output.emit(byte(mnemonic, value));
+        return;
+    }
+
+    output.emit(opcode(mnemonic, absolute));
+    output.emit_word(word(mnemonic, value));
+}
+
+const fn find(mnemonic: &str, mode: Mode) -> Option<Byte> {
+    let mut idx = 0;
+    while idx < OPCODES.len() {
+        let entry = OPCODES[idx];
+        if entry.mode as u8 == mode as u8 && entry.mnemonic.eq_ignore_ascii_case(mnemonic) {
+            return Some(entry.opcode);
+        }
+        idx += 1;
+    }
+
+    return None;
+}
+
+const fn is_mnemonic(mnemonic: &str) -> bool {
+    let mut idx = 0;
+    while idx < OPCODES.len() {
+        if OPCODES[idx].mnemonic.eq_ignore_ascii_case(mnemonic) {
+            return true;
+        }
+        idx += 1;
+    }
+
+    return false;
+}
+
+const fn opcode(mnemonic: &str, mode: Mode) -> Byte {
+    if let Some(opcode) = find(mnemonic, mode) {
+        return opcode;
+    }
+
+    if is_mnemonic(mnemonic) {
+        Message::new()
+            .mnemonic(mnemonic)
+            .text(" does not support ")
+            .text(mode_name(mode))
+            .text(" addressing")
+            .panic();
+    }
+    Message::new()
+        .text("unknown instruction ")
+        .text(mnemonic)
+        .panic();
+}
+
+const fn byte(context: &str, value: i64) -> Byte {
+    if value < -0x80 || value > 0xFF {
+        Message::new()
+            .mnemonic(context)
+            .text(" operand ")
+            .number(value)
+            .text(" does not fit in a byte")
+            .panic();
+    }
+
+    return value as Byte;
+}
+
+const fn zero_page(context: &str, value: i64) -> Byte {
+    if value < 0 || value > 0xFF {
+        Message::new()
+            .mnemonic(context)
+            .text(" operand ")
+            .number(value)
+            .text(" is not a zero page address")
+            .panic();
+    }
+
+    return value as Byte;
+}
+
+const fn word(context: &str, value: i64) -> Word {
+    if value < 0 || value > 0xFFFF {
+        Message::new()
+            .mnemonic(context)
+            .text(" operand ")
+            .number(value)
+            .text(" is not an address")
+            .panic();
+    }
+
+    return value as Word;
+}
+
+const fn mode_name(mode: Mode) -> &'static str {
+    return match mode {
+        Mode::Implied => "Implied",
+        Mode::Accumulator => "Accumulator",
+        Mode::Immediate => "Immediate",
+        Mode::ZeroPage => "ZeroPage",
+        Mode::ZeroPageX => "ZeroPageX",
+        Mode::ZeroPageY => "ZeroPageY",
+        Mode::Absolute => "Absolute",
+        Mode::AbsoluteX => "AbsoluteX",
+        Mode::AbsoluteY => "AbsoluteY",
+        Mode::Indirect => "Indirect",
+        Mode::IndexedIndirectX => "IndexedIndirectX",
+        Mode::IndirectIndexedY => "IndirectIndexedY",
+        Mode::Relative => "Relative",
+    };
+}
+
+/// Error message put together at compile time, where `format!` is not available.
+struct Message {
+    text: [u8; 96],
+    len: usize,
+}
+
+impl Message {
+    const fn new() -> Self {
+        return Message {
+            text: [0; 96],
+            len: 0,
+        }
+        .text("asm6502!: ");
+    }
+
+    const fn text(mut self, text: &str) -> Self {
+        let bytes = text.as_bytes();
+        let mut idx = 0;
+        while idx < bytes.len() && self.len < self.text.len() {
+            self.text[self.len] = bytes[idx];
+            self.len += 1;
+            idx += 1;
+        }
+
+        return self;
+    }
+
+    /// Mnemonics are written in lower case in programs and upper case in messages.
+    const fn mnemonic(mut self, mnemonic: &str) -> Self {
+        let start = self.len;
+        self = self.text(mnemonic);
+        let mut idx = start;
+        while idx < self.len {
+            self.text[idx] = self.text[idx].to_ascii_uppercase();
+            idx += 1;
+        }
+
+        return self;
+    }
+
+    const fn number(mut self, value: i64) -> Self {
+        if value < 0 {
+            self = self.text("-");
+        }
+
+        let mut digits = [0; 20];
+        let mut count = 0;
+        let mut rest = value.unsigned_abs();
+        loop {
+            digits[count] = b'0' + (rest % 10) as u8;
+            count += 1;
+            rest /= 10;
+            if rest == 0 {
+                break;
+            }
+        }
+        while count > 0 {
+            count -= 1;
+            if self.len < self.text.len() {
+                self.text[self.len] = digits[count];
+                self.len += 1;
+            }
+        }
+
+        return self;
+    }
+
+    const fn panic(self) -> ! {
+        let (text, _) = self.text.split_at(self.len);
+        match core::str::from_utf8(text) {
+            Ok(message) => panic!("{}", message),
+            Err(_) => panic!("asm6502!: invalid program"),
+        }
+    }
+}
+
+/// Assembles 6502 code written as Rust tokens into the address/value pairs
+/// accepted by `VecMemory::from`.
+///
+/// The program is assembled at compile time. Statements end with `;`. Operands
+/// are constant expressions, so constants can be used, and the index registers
+/// are written in lower case:
+///
+/// ```
+/// use cpu6502::{asm6502, memory::VecMemory};
+///
+/// const COUNT: u8 = 3;
+/// let pairs = asm6502! {
+///     org 0x0200;
+///     ldx #COUNT;
+///     lda 0x0300, x;
+///     sta (0x10), y;
+///     jmp (0xFFFC);
+///     byte 0x01, 0x02;
+///     word 0x1234;
+/// };
+/// let memory = VecMemory::from(&pairs[..]);
+///
+/// assert_eq!(memory[0x0200], 0xA2);
+/// assert_eq!(memory[0x0201], COUNT);
+/// ```
+///
+/// Zero page forms are picked for values fitting in a byte, branches take the
+/// absolute address of their target and `asl a` or `asl` address the
+/// accumulator. Unknown mnemonics, unsupported addressing modes and operands
+/// out of range fail to compile:
+///
+/// ```compile_fail,E0080
+/// let pairs = cpu6502::asm6502! { sta #0x01; };
+/// ```
+///
+/// ```compile_fail,E0080
+/// let pairs = cpu6502::asm6502! { lda #0x100; };
+/// ```
+///
+/// Every statement takes one level of macro recursion, so programs longer than
+/// about 120 statements need a higher `#![recursion_limit]` in the crate using
+/// the macro.
+#[macro_export]
+macro_rules! asm6502 {
+    (@statements [$($done:tt)*]) => {
+        [$($done)*]
+    };
+    (@statements [$($done:tt)*] ; $($rest:tt)*) => {
+        $crate::asm6502!(@statements [$($done)*] $($rest)*)
+    };
+    (@statements [$($done:tt)*] org $address:expr ; $($rest:tt)*) => {
+        $crate::asm6502!(
+            @statements [$($done)* $crate::asm::inline::Statement::Org(($address) as i64),]
+            $($rest)*
+        )
+    };
+    (@statements [$($done:tt)*] byte $($value:expr),+ ; $($rest:tt)*) => {
+        $crate::asm6502!(
+            @statements [
+                $($done)* $crate::asm::inline::Statement::Bytes(&[$(($value) as i64),+]),
+            ]
+            $($rest)*
+        )
+    };
+    (@statements [$($done:tt)*] word $($value:expr),+ ; $($rest:tt)*) => {
+        $crate::asm6502!(
+            @statements [
+                $($done)* $crate::asm::inline::Statement::Words(&[$(($value) as i64),+]),
+            ]
+            $($rest)*
+        )
+    };
+    (@statements [$($done:tt)*] $op:ident ; $($rest:tt)*) => {
+        $crate::asm6502!(
+            @statements [
+                $($done)*
+                $crate::asm::inline::Statement::Instruction(
+                    stringify!($op),
+                    $crate::asm::inline::Operand::None,
+                ),
+            ]
+            $($rest)*
+        )
+    };
+    (@statements [$($done:tt)*] $op:ident a ; $($rest:tt)*) => {
+        $crate::asm6502!(
+            @statements [
+                $($done)*
+                $crate::asm::inline::Statement::Instruction(
+                    stringify!($op),
+                    $crate::asm::inline::Operand::Accumulator,
+                ),
+            ]
+            $($rest)*
+        )
+    };
+    (@statements [$($done:tt)*] $op:ident # $value:expr ; $($rest:tt)*) => {
+        $crate::asm6502!(
+            @statements [
+                $($done)*
+                $crate::asm::inline::Statement::Instruction(
+                    stringify!($op),
+                    $crate::asm::inline::Operand::Immediate(($value) as i64),
+                ),
+            ]
+            $($rest)*
+        )
+    };
+    (@statements [$($done:tt)*] $op:ident ($value:expr, x) ; $($rest:tt)*) => {
+        $crate::asm6502!(
+            @statements [
+                $($done)*
+                $crate::asm::inline::Statement::Instruction(
+                    stringify!($op),
+                    $crate::asm::inline::Operand::IndexedIndirectX(($value) as i64),
+                ),
+            ]
+            $($rest)*
+        )
+    };
+    (@statements [$($done:tt)*] $op:ident ($value:expr), y ; $($rest:tt)*) => {
+        $crate::asm6502!(
+            @statements [
+                $($done)*
+                $crate::asm::inline::Statement::Instruction(
+                    stringify!($op),
+                    $crate::asm::inline::Operand::IndirectIndexedY(($value) as i64),
+                ),
+            ]
+            $($rest)*
+        )
+    };
+    (@statements [$($done:tt)*] $op:ident ($value:expr) ; $($rest:tt)*) => {
+        $crate::asm6502!(
+            @statements [
+                $($done)*
+                $crate::asm::inline::Statement::Instruction(
+                    stringify!($op),
+                    $crate::asm::inline::Operand::Indirect(($value) as i64),
+                ),
+            ]
+            $($rest)*
+        )
+    };
+    (@statements [$($done:tt)*] $op:ident $value:expr, x ; $($rest:tt)*) => {
+        $crate::asm6502!(
+            @statements [
+                $($done)*
+                $crate::asm::inline::Statement::Instruction(
+                    stringify!($op),
+                    $crate::asm::inline::Operand::AddressX(($value) as i64),
+                ),
+            ]
+            $($rest)*
+        )
+    };
+    (@statements [$($done:tt)*] $op:ident $value:expr, y ; $($rest:tt)*) => {
+        $crate::asm6502!(
+            @statements [
+                $($done)*
+                $crate::asm::inline::Statement::Instruction(
+                    stringify!($op),
+                    $crate::asm::inline::Operand::AddressY(($value) as i64),
+                ),
+            ]
+            $($rest)*
+        )
+    };
+    (@statements [$($done:tt)*] $op:ident $value:expr ; $($rest:tt)*) => {
+        $crate::asm6502!(
+            @statements [
+                $($done)*
+                $crate::asm::inline::Statement::Instruction(
+                    stringify!($op),
+                    $crate::asm::inline::Operand::Address(($value) as i64),
+                ),
+            ]
+            $($rest)*
+        )
+    };
+    ($($body:tt)*) => {{
+        const STATEMENTS: &[$crate::asm::inline::Statement] =
+            &$crate::asm6502!(@statements [] $($body)* ;);
+        const PAIRS: [
+            ($crate::consts::Word, $crate::consts::Byte);
+            $crate::asm::inline::assembled_size(STATEMENTS)
+        ] = $crate::asm::inline::assemble(STATEMENTS);
+        PAIRS.to_vec()
+    }};
+}
+
+#[cfg(test)]
+mod tests;
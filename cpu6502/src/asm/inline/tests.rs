@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod asm6502 {
+    use crate::{consts::Byte, disasm::disassemble_bytes, memory::VecMemory};
+
+    fn listing(pairs: &[(u16, u8)]) -> Vec<String> {
+        let bytes: Vec<Byte> = pairs.iter().map(|(_, value)| *value).collect();
+        return disassemble_bytes(&bytes, pairs[0].0)
+            .iter()
+            .map(|instruction| instruction.to_string())
+            .collect();
+    }
+
+    #[test]
+    fn should_expand_to_address_value_pairs() {
+        let pairs = asm6502! {
+            org 0x1234;
+            lda #0x42;
+            jsr 0x0300;
+        };
+
+        assert_eq!(
+            pairs,
+            vec![
+                (0x1234, 0xA9),
+                (0x1235, 0x42),
+                (0x1236, 0x20),
+                (0x1237, 0x00),
+                (0x1238, 0x03),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_assemble_every_addressing_mode() {
+        let pairs = asm6502! {
+            org 0x0200;
+            nop;
+            asl;
+            rol a;
+            lda #0x42;
+            lda 0x12;
+            lda 0x12, x;
+            ldx 0x12, y;
+            lda 0x1234;
+            lda 0x1234, x;
+            lda 0x1234, y;
+            jmp (0x1234);
+            lda (0x12, x);
+            lda (0x12), y;
+            lda (0x10 + 2) * 0x100
+        };
+
+        assert_eq!(
+            listing(&pairs),
+            vec![
+                "NOP",
+                "ASL A",
+                "ROL A",
+                "LDA #$42",
+                "LDA $12",
+                "LDA $12,X",
+                "LDX $12,Y",
+                "LDA $1234",
+                "LDA $1234,X",
+                "LDA $1234,Y",
+                "JMP ($1234)",
+                "LDA ($12,X)",
+                "LDA ($12),Y",
+                "LDA $1200",
+            ]
+        );
+    }
+
+    #[test]
+    fn should_accept_constants_branch_targets_and_data() {
+        const START: u16 = 0x0300;
+        const VALUE: Byte = 0x07;
+
+        let pairs = asm6502! {
+            org START;
+            ldx #VALUE;
+            bne START + 6;
+            byte 0x01, -1;
+            word 0xBEEF;
+        };
+        let memory = VecMemory::from(&pairs[..]);
+
+        assert_eq!(memory[0x0301], 0x07);
+        assert_eq!(memory[0x0303], 0x02);
+        assert_eq!(memory[0x0305], 0xFF);
+        assert_eq!(memory[0x0306], 0xEF);
+        assert_eq!(memory[0x0307], 0xBE);
+    }
+
+    #[test]
+    fn should_assemble_programs_longer_than_default_recursion_limit() {
+        let pairs = asm6502! {
+            org 0x0200;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            nop; nop; nop; nop; nop; nop; nop; nop; nop; nop;
+            brk;
+        };
+
+        assert_eq!(pairs.len(), 151);
+        assert_eq!(pairs[150], (0x0296, 0x00));
+    }
+}
@@ -0,0 +1,488 @@
+#[cfg(test)]
+mod assemble {
+    use crate::{
+        asm::{assemble, AsmError, AsmErrorKind, Assembly},
+        disasm::disassemble_bytes,
+    };
+    use std::path::Path;
+
+    fn program(source: &str) -> Assembly {
+        return assemble(source, Path::new("")).unwrap();
+    }
+
+    fn error(source: &str) -> AsmError {
+        return assemble(source, Path::new("")).unwrap_err();
+    }
+
+    #[test]
+    fn should_assemble_every_addressing_mode() {
+        let source = "
+            .org $0200
+            nop
+            asl
+            rol a
+            lda #$42
+            lda $12
+            lda $12,x
+            ldx $12,y
+            lda $1234
+            lda $1234,x
+            lda $1234,y
+            jmp ($1234)
+            lda ($12,x)
+            lda ($12),y
+        ";
+
+        let lines: Vec<String> = disassemble_bytes(&program(source).to_bytes(), 0x0200)
+            .iter()
+            .map(|instruction| instruction.to_string())
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "NOP",
+                "ASL A",
+                "ROL A",
+                "LDA #$42",
+                "LDA $12",
+                "LDA $12,X",
+                "LDX $12,Y",
+                "LDA $1234",
+                "LDA $1234,X",
+                "LDA $1234,Y",
+                "JMP ($1234)",
+                "LDA ($12,X)",
+                "LDA ($12),Y",
+            ]
+        );
+    }
+
+    #[test]
+    fn should_resolve_labels_and_branches() {
+        let assembly = program(
+            "
+            .org $0200
+    start:  ldx #3
+    loop:   dex
+            bne loop
+            beq done
+            nop
+    done:   jmp start
+        ",
+        );
+
+        assert_eq!(
+            assembly.to_pairs(),
+            vec![
+                (0x0200, 0xA2),
+                (0x0201, 0x03),
+                (0x0202, 0xCA),
+                (0x0203, 0xD0),
+                (0x0204, 0xFD),
+                (0x0205, 0xF0),
+                (0x0206, 0x01),
+                (0x0207, 0xEA),
+                (0x0208, 0x4C),
+                (0x0209, 0x00),
+                (0x020A, 0x02),
+            ]
+        );
+        assert_eq!(assembly.label("done"), Some(0x0208));
+    }
+
+    #[test]
+    fn should_scope_local_labels_to_previous_global_label() {
+        let assembly = program(
+            "
+            .org $0300
+    first:  dex
+    @loop:  bne @loop
+    second: dey
+    @loop:  bne @loop
+        ",
+        );
+
+        assert_eq!(assembly.label("first@loop"), Some(0x0301));
+        assert_eq!(assembly.label("second@loop"), Some(0x0304));
+        assert_eq!(assembly.to_bytes()[5], 0xFE);
+    }
+
+    #[test]
+    fn should_select_zero_page_only_for_known_values() {
+        let assembly = program(
+            "
+    ptr = $FB
+            .org $0200
+            lda ptr
+            lda late
+            sta ptr+1,x
+            stx ptr,y
+    late = $10
+        ",
+        );
+
+        assert_eq!(
+            assembly.to_bytes(),
+            vec![0xA5, 0xFB, 0xAD, 0x10, 0x00, 0x95, 0xFC, 0x96, 0xFB]
+        );
+        assert_eq!(assembly.constants.get("late"), Some(&0x10));
+    }
+
+    #[test]
+    fn should_evaluate_expressions_and_byte_operators() {
+        let assembly = program(
+            "
+    table = $1234
+            .org $C000
+            lda #<table
+            ldx #>table
+            lda #(2 + 3) * 4
+            lda table + 2 * $10
+            .word * + 1, table - 1
+        ",
+        );
+
+        assert_eq!(
+            assembly.to_bytes(),
+            vec![0xA9, 0x34, 0xA2, 0x12, 0xA9, 0x14, 0xAD, 0x54, 0x12, 0x0A, 0xC0, 0x33, 0x12,]
+        );
+    }
+
+    #[test]
+    fn should_emit_data_directives() {
+        let assembly = program(
+            "
+            .org $1000
+            .byte 1, -1, 'A', \"Hi; there\", 0 ; comment
+            .res 2
+            .res 2, $EA
+            .word $BEEF
+        ",
+        );
+
+        assert_eq!(
+            assembly.to_bytes(),
+            vec![
+                0x01, 0xFF, 0x41, b'H', b'i', b';', b' ', b't', b'h', b'e', b'r', b'e', 0x00, 0x00,
+                0x00, 0xEA, 0xEA, 0xEF, 0xBE,
+            ]
+        );
+        assert_eq!(assembly.image.segments.len(), 2);
+        assert_eq!(assembly.origin(), Some(0x1000));
+    }
+
+    #[test]
+    fn should_report_errors_with_line_numbers() {
+        let err = error("nop\n  lda missing\n");
+
+        assert_eq!(err.line, 2);
+        assert_eq!(
+            err.kind,
+            AsmErrorKind::UndefinedSymbol("missing".to_string())
+        );
+        assert_eq!(err.to_string(), "line 2: undefined symbol \"missing\"");
+    }
+
+    #[test]
+    fn should_reject_invalid_programs() {
+        assert_eq!(
+            error("foo #1").kind,
+            AsmErrorKind::UnknownInstruction("foo".to_string())
+        );
+        assert_eq!(
+            error(".fill 1").kind,
+            AsmErrorKind::UnknownDirective(".fill".to_string())
+        );
+        assert_eq!(
+            error("sta #1").kind,
+            AsmErrorKind::InvalidAddressingMode("STA".to_string())
+        );
+        assert_eq!(
+            error("lda ($12)").kind,
+            AsmErrorKind::InvalidAddressingMode("LDA".to_string())
+        );
+        assert_eq!(
+            error("a: nop\na: nop").kind,
+            AsmErrorKind::DuplicateSymbol("a".to_string())
+        );
+        assert_eq!(error("lda #256").kind, AsmErrorKind::ValueOutOfRange(256));
+        assert_eq!(
+            error(".org later\nlater: nop").kind,
+            AsmErrorKind::ForwardReference("later".to_string())
+        );
+        assert_eq!(
+            error("start: .res 130\nbne start").kind,
+            AsmErrorKind::BranchOutOfRange(-132)
+        );
+        assert_eq!(
+            error(".org $FFFF\nlda $1234").kind,
+            AsmErrorKind::AddressOverflow
+        );
+        assert!(matches!(error("lda (1").kind, AsmErrorKind::Syntax(_)));
+    }
+}
+
+#[cfg(test)]
+mod object {
+    use crate::{
+        asm::{assemble, assemble_object, AsmErrorKind},
+        loaders::o65::{Export, Object, Relocation, RelocationKind, RelocationTarget, SegmentId},
+    };
+    use std::path::Path;
+
+    fn object(source: &str) -> Object {
+        return assemble_object(source, Path::new("")).unwrap();
+    }
+
+    fn error(source: &str) -> AsmErrorKind {
+        return assemble_object(source, Path::new("")).unwrap_err().kind;
+    }
+
+    fn relocation(offset: usize, kind: RelocationKind, target: RelocationTarget) -> Relocation {
+        return Relocation {
+            offset,
+            kind,
+            target,
+        };
+    }
+
+    #[test]
+    fn should_assemble_segments_from_offset_zero() {
+        let object = object(
+            "
+            .segment \"ZEROPAGE\"
+            pointer: .res 2
+            .segment \"BSS\"
+            buffer: .res 16
+            .segment \"DATA\"
+            table: .word main, buffer
+            .segment \"CODE\"
+            main: lda table
+                  sta pointer
+                  rts
+            ",
+        );
+
+        assert_eq!(object.text.bytes, vec![0xAD, 0x00, 0x00, 0x85, 0x00, 0x60]);
+        assert_eq!(object.data.bytes, vec![0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(object.bss.len, 16);
+        assert!(object.bss.bytes.is_empty());
+        assert_eq!(object.zero_page.len, 2);
+        assert_eq!(
+            object.text.relocations,
+            vec![
+                relocation(
+                    1,
+                    RelocationKind::Word,
+                    RelocationTarget::Segment(SegmentId::Data)
+                ),
+                relocation(
+                    4,
+                    RelocationKind::Low,
+                    RelocationTarget::Segment(SegmentId::ZeroPage)
+                ),
+            ]
+        );
+        assert_eq!(
+            object.data.relocations,
+            vec![
+                relocation(
+                    0,
+                    RelocationKind::Word,
+                    RelocationTarget::Segment(SegmentId::Text)
+                ),
+                relocation(
+                    2,
+                    RelocationKind::Word,
+                    RelocationTarget::Segment(SegmentId::Bss)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_record_imports_and_exports() {
+        let object = object(
+            "
+            .import chrout
+            .importzp ptr
+            .export main, answer, message
+            answer = 42
+            main: ldy #<(message + 1)
+                  lda #>message
+                  jsr chrout
+                  sta ptr
+                  rts
+            message: .byte \"HI\", 0
+            ",
+        );
+
+        assert_eq!(
+            object.imports,
+            vec!["chrout".to_string(), "ptr".to_string()]
+        );
+        assert_eq!(
+            object.text.bytes[..9],
+            [0xA0, 0x0B, 0xA9, 0x00, 0x20, 0x00, 0x00, 0x85, 0x00]
+        );
+        assert_eq!(
+            object.text.relocations,
+            vec![
+                relocation(
+                    1,
+                    RelocationKind::Low,
+                    RelocationTarget::Segment(SegmentId::Text)
+                ),
+                relocation(
+                    3,
+                    RelocationKind::High { low: 0x0A },
+                    RelocationTarget::Segment(SegmentId::Text)
+                ),
+                relocation(5, RelocationKind::Word, RelocationTarget::Import(0)),
+                relocation(8, RelocationKind::Low, RelocationTarget::Import(1)),
+            ]
+        );
+        assert_eq!(
+            object.exports,
+            vec![
+                Export {
+                    name: "main".to_string(),
+                    segment: Some(SegmentId::Text),
+                    value: 0,
+                },
+                Export {
+                    name: "answer".to_string(),
+                    segment: None,
+                    value: 42,
+                },
+                Export {
+                    name: "message".to_string(),
+                    segment: Some(SegmentId::Text),
+                    value: 0x0A,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_keep_differences_of_labels_constant() {
+        let object = object("start: .byte end - start\nend: beq start");
+
+        assert_eq!(object.text.bytes, vec![0x01, 0xF0, 0xFD]);
+        assert!(object.text.relocations.is_empty());
+    }
+
+    #[test]
+    fn should_reject_values_which_cannot_be_relocated() {
+        assert_eq!(error("a: .byte a"), AsmErrorKind::NotRelocatable);
+        assert_eq!(error("a: .word a * 2"), AsmErrorKind::NotRelocatable);
+        assert_eq!(error(".import far\nbeq far"), AsmErrorKind::NotRelocatable);
+        assert_eq!(
+            error(".segment \"DATA\"\nd: nop\n.segment \"CODE\"\nbeq d"),
+            AsmErrorKind::NotRelocatable
+        );
+        assert_eq!(error(".import x\n.export x"), AsmErrorKind::NotRelocatable);
+    }
+
+    #[test]
+    fn should_reject_directives_of_the_other_mode() {
+        assert_eq!(
+            error(".org $0200"),
+            AsmErrorKind::AbsoluteOnly(".org".to_string())
+        );
+        assert_eq!(
+            assemble(".import x", Path::new("")).unwrap_err().kind,
+            AsmErrorKind::RelocatableOnly(".import".to_string())
+        );
+        assert_eq!(
+            error(".segment \"HEAP\""),
+            AsmErrorKind::UnknownSegment("HEAP".to_string())
+        );
+        assert_eq!(
+            error(".export missing"),
+            AsmErrorKind::UndefinedSymbol("missing".to_string())
+        );
+    }
+
+    #[test]
+    fn should_reject_data_in_uninitialized_segments() {
+        assert_eq!(
+            error(".segment \"BSS\"\nnop"),
+            AsmErrorKind::UninitializedSegment
+        );
+        assert_eq!(
+            error(".segment \"ZEROPAGE\"\n.byte 1"),
+            AsmErrorKind::UninitializedSegment
+        );
+        assert_eq!(
+            error(".segment \"BSS\"\n.res 4, $FF"),
+            AsmErrorKind::UninitializedSegment
+        );
+    }
+}
+
+#[cfg(test)]
+mod include {
+    use crate::asm::{assemble, assemble_file, AsmErrorKind};
+    use std::{env, fs, path::Path};
+
+    #[test]
+    fn should_include_files_relative_to_including_file() {
+        let dir = env::temp_dir().join("cpu6502_asm_include_test");
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        fs::write(
+            dir.join("main.s"),
+            ".org $0200\njsr print\n.include \"lib/print.s\"\n",
+        )
+        .unwrap();
+        fs::write(dir.join("lib/print.s"), "print: .include \"body.s\"\n").unwrap();
+        fs::write(dir.join("lib/body.s"), "rts\n").unwrap();
+
+        let assembly = assemble_file(dir.join("main.s")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(assembly.to_bytes(), vec![0x20, 0x03, 0x02, 0x60]);
+    }
+
+    #[test]
+    fn should_report_missing_include() {
+        let err = assemble("nop\n.include \"missing.s\"", Path::new("/nonexistent")).unwrap_err();
+
+        assert_eq!(err.line, 2);
+        assert!(matches!(err.kind, AsmErrorKind::Include { .. }));
+    }
+}
+
+#[cfg(test)]
+mod execution {
+    use crate::{asm::assemble, machine::builder::MachineBuilder};
+    use std::path::Path;
+
+    #[test]
+    fn should_run_assembled_program() {
+        let assembly = assemble(
+            "
+    result = $10
+            .org $0400
+            ldx #2
+            inc result
+            dex
+            beq @skip
+            inc result
+    @skip:  lda result
+            sta result + 1
+        ",
+            Path::new(""),
+        )
+        .unwrap();
+        let mut machine = MachineBuilder::new().build().unwrap();
+        machine.load_image(&assembly.image);
+        machine.cpu_mut().set_program_counter(0x0400);
+
+        machine.run_instructions(7);
+
+        assert_eq!(machine.registers().accumulator, 2);
+        assert_eq!(machine.memory().peek(0x0011), 2);
+    }
+}
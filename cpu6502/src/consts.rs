@@ -2,3 +2,5 @@ pub type Byte = u8;
 pub type Word = u16;
 
 pub const STACK_PAGE_HI: Word = 0x0100;
+pub const NMI_VECTOR: Word = 0xFFFA;
+pub const IRQ_VECTOR: Word = 0xFFFE;
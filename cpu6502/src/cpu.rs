@@ -1,10 +1,26 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
 
 use self::instructions::*;
 use super::consts::{Byte, Word};
-use crate::{consts::STACK_PAGE_HI, memory::Memory};
-
+use crate::{
+    consts::{IRQ_VECTOR, NMI_VECTOR, STACK_PAGE_HI},
+    disasm::decode,
+    memory::{AccessKind, Bus, InterruptLine},
+};
+
+pub mod access_log;
+pub mod breakpoints;
 mod instructions;
+pub mod trace;
+pub mod watchpoints;
+
+use access_log::{AccessRecorder, BusAccess};
+use breakpoints::{Breakpoint, BreakpointHit};
+use trace::{InstructionTracer, TraceEntry};
+use watchpoints::{Watchpoint, WatchpointHit};
 
 type Instruction = Byte;
 
@@ -30,6 +46,9 @@ const INSTRUCTION_JMP_A: Byte = 0x4C;
 const INSTRUCTION_JMP_IN: Byte = 0x6C;
 const INSTRUCTION_JSR_A: Byte = 0x20;
 const INSTRUCTION_RTS: Byte = 0x60;
+const INSTRUCTION_RTI: Byte = 0x40;
+const INSTRUCTION_SEI: Byte = 0x78;
+const INSTRUCTION_CLI: Byte = 0x58;
 const INSTRUCTION_BEQ: Byte = 0xF0;
 const INSTRUCTION_BCC: Byte = 0x90;
 const INSTRUCTION_BCS: Byte = 0xB0;
@@ -74,10 +93,17 @@ const INSTRUCTION_STY_ZP: Byte = 0x84;
 const INSTRUCTION_STY_ZPX: Byte = 0x94;
 const INSTRUCTION_STY_A: Byte = 0x8C;
 
+/// Servicing an interrupt takes 7 cycles: 2 internal cycles reading the
+/// program counter without using it, 3 pushing the return address and status
+/// and 2 reading the vector. The pushes and vector reads count their own cycles.
+const INTERRUPT_INTERNAL_CYCLES: u64 = 2;
+
 enum Flags {
     Carry = 0,
     Zero = 1,
+    InterruptDisable = 2,
     DecimalMode = 3,
+    Break = 4,
     Negative = 7,
 }
 
@@ -130,6 +156,18 @@ impl ProcessorStatus {
         self.set_flag(Flags::DecimalMode, value_set);
     }
 
+    pub fn set_interrupt_disable_flag(&mut self, value_set: bool) {
+        self.set_flag(Flags::InterruptDisable, value_set);
+    }
+
+    pub fn get_interrupt_disable_flag(&self) -> bool {
+        return self.get_flag(Flags::InterruptDisable);
+    }
+
+    pub fn set_break_flag(&mut self, value_set: bool) {
+        self.set_flag(Flags::Break, value_set);
+    }
+
     pub fn set_zero_flag(&mut self, value_set: bool) {
         self.set_flag(Flags::Zero, value_set);
     }
@@ -171,6 +209,70 @@ impl ProcessorStatus {
 
 type OpcodeHandler = fn(&mut CPU) -> ();
 
+/// CPU model being emulated. Only behaviour of instructions implemented by
+/// the emulator differs between variants: the 65C02 fixes the `JMP ($xxFF)`
+/// page wrap bug at the cost of one extra cycle.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CpuVariant {
+    Nmos6502,
+    Cmos65C02,
+}
+
+impl CpuVariant {
+    /// Accepts `6502`/`nmos6502` and `65c02`/`cmos65c02`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        return match name.to_ascii_lowercase().as_str() {
+            "6502" | "nmos6502" => Some(CpuVariant::Nmos6502),
+            "65c02" | "cmos65c02" => Some(CpuVariant::Cmos65C02),
+            _ => None,
+        };
+    }
+}
+
+/// Snapshot of the programmer visible registers and the cycle counter.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CpuState {
+    pub cycle: u64,
+    pub program_counter: Word,
+    pub stack_pointer: Byte,
+    pub accumulator: Byte,
+    pub index_register_x: Byte,
+    pub index_register_y: Byte,
+    pub processor_status: Byte,
+}
+
+/// Reason for `execute` returning before running the requested number of cycles.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StopReason {
+    Breakpoint(BreakpointHit),
+    Watchpoint(WatchpointHit),
+    /// The opcode at `address` has no handler; the program counter is left pointing at it.
+    IllegalOpcode {
+        address: Word,
+        opcode: Byte,
+    },
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            StopReason::Breakpoint(hit) => write!(
+                f,
+                "breakpoint {} at ${:04X}, hit count {}",
+                hit.id, hit.program_counter, hit.hit_count
+            ),
+            StopReason::Watchpoint(hit) => write!(
+                f,
+                "watchpoint hit by instruction at ${:04X}: {:?} of ${:04X}",
+                hit.program_counter, hit.kind, hit.address
+            ),
+            StopReason::IllegalOpcode { address, opcode } => {
+                write!(f, "illegal opcode ${opcode:02X} at ${address:04X}")
+            }
+        };
+    }
+}
+
 pub struct CPU {
     cycle: u64,
     program_counter: Word,
@@ -179,12 +281,22 @@ pub struct CPU {
     index_register_x: Byte,
     index_register_y: Byte,
     processor_status: ProcessorStatus,
-    memory: Box<dyn Memory>,
+    variant: CpuVariant,
+    memory: Box<dyn Bus>,
     opcode_handlers: HashMap<Byte, OpcodeHandler>,
+    instruction_address: Word,
+    watchpoints: Vec<Watchpoint>,
+    watchpoint_hit: Option<WatchpointHit>,
+    breakpoints: BTreeMap<usize, Breakpoint>,
+    next_breakpoint_id: usize,
+    stop_reason: Option<StopReason>,
+    access_recorder: Option<Box<dyn AccessRecorder>>,
+    tracer: Option<Box<dyn InstructionTracer>>,
+    nmi_line: bool,
 }
 
 impl CPU {
-    pub fn new(memory: Box<dyn Memory>) -> Self {
+    pub fn new(memory: Box<dyn Bus>) -> Self {
         let opcode_handlers: HashMap<Byte, OpcodeHandler> = HashMap::from([
             (INSTRUCTION_LDA_IM, lda_im as OpcodeHandler),
             (INSTRUCTION_LDA_ZP, lda_zp as OpcodeHandler),
@@ -208,6 +320,9 @@ impl CPU {
             (INSTRUCTION_JMP_IN, jmp_in as OpcodeHandler),
             (INSTRUCTION_JSR_A, jsr_a as OpcodeHandler),
             (INSTRUCTION_RTS, rts as OpcodeHandler),
+            (INSTRUCTION_RTI, rti as OpcodeHandler),
+            (INSTRUCTION_SEI, sei as OpcodeHandler),
+            (INSTRUCTION_CLI, cli as OpcodeHandler),
             (INSTRUCTION_BCC, bcc as OpcodeHandler),
             (INSTRUCTION_BCS, bcs as OpcodeHandler),
             (INSTRUCTION_BEQ, beq as OpcodeHandler),
@@ -261,8 +376,18 @@ impl CPU {
             index_register_x: 0,
             index_register_y: 0,
             processor_status: ProcessorStatus { flags: 0 },
+            variant: CpuVariant::Nmos6502,
             memory: memory,
             opcode_handlers,
+            instruction_address: 0xFFFC,
+            watchpoints: vec![],
+            watchpoint_hit: None,
+            breakpoints: BTreeMap::new(),
+            next_breakpoint_id: 1,
+            stop_reason: None,
+            access_recorder: None,
+            tracer: None,
+            nmi_line: false,
         };
     }
 
@@ -277,11 +402,168 @@ impl CPU {
     }
 
     fn access_memory(&mut self, addr: Word) -> Byte {
-        return self.memory[addr];
+        return self.read_bus(addr, AccessKind::Read);
     }
 
     fn put_into_memory(&mut self, addr: Word, value: Byte) {
-        self.memory[addr] = value;
+        self.record_access(addr, AccessKind::Write, value);
+        if self.watchpoints.is_empty() {
+            self.memory.write(addr, value);
+            return;
+        }
+
+        let old_value = self.memory.peek(addr);
+        self.memory.write(addr, value);
+        self.check_watchpoints(addr, AccessKind::Write, old_value, value);
+    }
+
+    fn read_bus(&mut self, addr: Word, kind: AccessKind) -> Byte {
+        let value = self.memory.read(addr);
+        self.record_access(addr, kind, value);
+        if !self.watchpoints.is_empty() {
+            self.check_watchpoints(addr, kind, value, value);
+        }
+
+        return value;
+    }
+
+    fn record_access(&mut self, addr: Word, kind: AccessKind, value: Byte) {
+        if let Some(recorder) = self.access_recorder.as_mut() {
+            recorder.record(BusAccess {
+                cycle: self.cycle,
+                address: addr,
+                value,
+                kind,
+            });
+        }
+    }
+
+    /// Records every subsequent bus access made by the CPU, `None` stops recording.
+    pub fn set_access_recorder(&mut self, recorder: Option<Box<dyn AccessRecorder>>) {
+        self.access_recorder = recorder;
+    }
+
+    /// Hands every subsequent instruction to `tracer` before executing it, `None` stops tracing.
+    pub fn set_tracer(&mut self, tracer: Option<Box<dyn InstructionTracer>>) {
+        self.tracer = tracer;
+    }
+
+    fn trace_instruction(&mut self) {
+        let state = self.state();
+        if let Some(tracer) = self.tracer.as_mut() {
+            let entry = TraceEntry {
+                state,
+                instruction: decode(&*self.memory, self.program_counter),
+            };
+            tracer.trace(&entry, &*self.memory);
+        }
+    }
+
+    fn check_watchpoints(
+        &mut self,
+        addr: Word,
+        kind: AccessKind,
+        old_value: Byte,
+        new_value: Byte,
+    ) {
+        if self.watchpoint_hit.is_some() {
+            return;
+        }
+
+        let triggered = self
+            .watchpoints
+            .iter()
+            .any(|watchpoint| watchpoint.matches(addr, kind, new_value));
+        if !triggered {
+            return;
+        }
+
+        self.watchpoint_hit = Some(WatchpointHit {
+            program_counter: self.instruction_address,
+            address: addr,
+            kind,
+            old_value,
+            new_value,
+        });
+    }
+
+    /// Execution stops once the instruction performing a matching access completes.
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+    }
+
+    /// Removes all watchpoints equal to `watchpoint`, returns whether any were found.
+    pub fn remove_watchpoint(&mut self, watchpoint: &Watchpoint) -> bool {
+        let count = self.watchpoints.len();
+        self.watchpoints.retain(|existing| existing != watchpoint);
+
+        return self.watchpoints.len() != count;
+    }
+
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        return &self.watchpoints;
+    }
+
+    /// Execution stops before an instruction when the CPU is in a state the
+    /// breakpoint matches, including before the first instruction of a run.
+    /// Continuing from a breakpoint stop runs that instruction without checking
+    /// again. Returns the id identifying the breakpoint, ids start at 1.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        let id = self.next_breakpoint_id;
+        self.next_breakpoint_id += 1;
+        self.breakpoints.insert(id, breakpoint);
+
+        return id;
+    }
+
+    pub fn remove_breakpoint(&mut self, id: usize) -> Option<Breakpoint> {
+        return self.breakpoints.remove(&id);
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    pub fn breakpoints(&self) -> &BTreeMap<usize, Breakpoint> {
+        return &self.breakpoints;
+    }
+
+    /// Gives access to hit and ignore counts of a breakpoint.
+    pub fn breakpoint_mut(&mut self, id: usize) -> Option<&mut Breakpoint> {
+        return self.breakpoints.get_mut(&id);
+    }
+
+    /// Whether execution continues at the address a breakpoint stopped it at.
+    fn resuming_from_breakpoint(&self) -> bool {
+        return matches!(
+            self.stop_reason,
+            Some(StopReason::Breakpoint(hit)) if hit.program_counter == self.program_counter
+        );
+    }
+
+    /// Counts hits of every matching breakpoint, stopping at the first one
+    /// past its ignore count.
+    fn check_breakpoints(&mut self) {
+        let state = self.state();
+        let memory = self.memory.as_ref();
+        for (id, breakpoint) in self.breakpoints.iter_mut() {
+            if breakpoint.check(&state, memory) && self.stop_reason.is_none() {
+                self.stop_reason = Some(StopReason::Breakpoint(BreakpointHit {
+                    id: *id,
+                    program_counter: state.program_counter,
+                    hit_count: breakpoint.hit_count,
+                }));
+            }
+        }
+    }
+
+    /// Reason for which the last `execute` call stopped early, if it did.
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        return self.stop_reason;
     }
 
     fn increment_program_counter(&mut self) {
@@ -340,7 +622,7 @@ impl CPU {
     }
 
     fn fetch_instruction(&mut self) -> Instruction {
-        let opcode = self.access_memory(self.program_counter);
+        let opcode = self.read_bus(self.program_counter, AccessKind::Fetch);
         self.increment_program_counter();
 
         return opcode;
@@ -425,7 +707,7 @@ impl CPU {
 
     fn push_byte_to_stack(&mut self, val: Byte) {
         let stack_addr: Word = STACK_PAGE_HI | (self.stack_pointer as u16);
-        self.memory[stack_addr] = val;
+        self.put_into_memory(stack_addr, val);
         self.decrement_register(Registers::StackPointer);
     }
 
@@ -438,7 +720,7 @@ impl CPU {
     fn pop_byte_from_stack(&mut self) -> Byte {
         self.increment_register(Registers::StackPointer);
         let stack_addr: Word = STACK_PAGE_HI | (self.stack_pointer as u16);
-        let val = self.memory[stack_addr];
+        let val = self.access_memory(stack_addr);
 
         return val;
     }
@@ -450,10 +732,52 @@ impl CPU {
         return Word::from_le_bytes([lo, hi]);
     }
 
-    pub fn set_memory(&mut self, memory: Box<dyn Memory>) {
+    pub fn set_memory(&mut self, memory: Box<dyn Bus>) {
         self.memory = memory;
     }
 
+    pub fn state(&self) -> CpuState {
+        return CpuState {
+            cycle: self.cycle,
+            program_counter: self.program_counter,
+            stack_pointer: self.stack_pointer,
+            accumulator: self.accumulator,
+            index_register_x: self.index_register_x,
+            index_register_y: self.index_register_y,
+            processor_status: self.processor_status.flags,
+        };
+    }
+
+    pub fn set_state(&mut self, state: CpuState) {
+        self.cycle = state.cycle;
+        self.program_counter = state.program_counter;
+        self.stack_pointer = state.stack_pointer;
+        self.accumulator = state.accumulator;
+        self.index_register_x = state.index_register_x;
+        self.index_register_y = state.index_register_y;
+        self.processor_status.flags = state.processor_status;
+    }
+
+    pub fn variant(&self) -> CpuVariant {
+        return self.variant;
+    }
+
+    pub fn set_variant(&mut self, variant: CpuVariant) {
+        self.variant = variant;
+    }
+
+    pub fn set_program_counter(&mut self, address: Word) {
+        self.program_counter = address;
+    }
+
+    pub fn memory(&self) -> &dyn Bus {
+        return self.memory.as_ref();
+    }
+
+    pub fn memory_mut(&mut self) -> &mut dyn Bus {
+        return self.memory.as_mut();
+    }
+
     pub fn offset_program_counter(&mut self, offset: u8) {
         let [program_counter_lo, program_counter_hi] = self.program_counter.to_le_bytes();
         let negative_offset_direction = 0b10000000 & offset > 0;
@@ -491,6 +815,10 @@ impl CPU {
         };
 
         let value = self.access_memory(address);
+        if addr_mode == AddressingMode::Immediate {
+            // operand is consumed by the read itself, no extra cycle
+            self.program_counter = self.program_counter.wrapping_add(1);
+        }
         if !addressing_takes_extra_cycle_to_fix(addr_mode) {
             self.cycle += 1;
         }
@@ -584,9 +912,14 @@ impl CPU {
             }
             AddressingMode::Indirect => {
                 let address = self.fetch_address();
-                let should_incorrectly_jump = address & 0x00FF == 0x00FF;
+                let should_incorrectly_jump =
+                    self.variant == CpuVariant::Nmos6502 && address & 0x00FF == 0x00FF;
                 if !should_incorrectly_jump {
-                    return Some(self.fetch_address_from(address));
+                    let effective_address = self.fetch_address_from(address);
+                    if self.variant == CpuVariant::Cmos65C02 {
+                        self.cycle += 1;
+                    }
+                    return Some(effective_address);
                 };
 
                 let hi = self.access_memory(address);
@@ -605,18 +938,103 @@ impl CPU {
     pub fn execute(&mut self, cycles: u64) -> u64 {
         let cycles_before_execution = self.cycle;
         let stop_cycle = cycles_before_execution + cycles;
+        let mut resuming = self.resuming_from_breakpoint();
+        self.stop_reason = None;
 
         while self.cycle < stop_cycle {
-            let opcode = self.fetch_instruction();
-            let handler = self.opcode_handlers.get(&opcode);
-            match handler {
-                Some(cb) => cb(self),
-                None => panic!("illegal opcode found: {opcode}"),
+            self.execute_instruction(!resuming);
+            resuming = false;
+            if self.stop_reason.is_some() {
+                return self.cycle;
             }
         }
 
         return stop_cycle;
     }
+
+    /// Executes a single instruction and returns the number of cycles it took.
+    pub fn step(&mut self) -> u64 {
+        let cycles_before_execution = self.cycle;
+        let resuming = self.resuming_from_breakpoint();
+        self.stop_reason = None;
+        self.execute_instruction(!resuming);
+
+        return self.cycle - cycles_before_execution;
+    }
+
+    /// Services a pending interrupt instead of executing an instruction.
+    /// NMI is edge triggered, IRQ is level triggered and masked by the I flag.
+    fn service_interrupt(&mut self) -> bool {
+        let nmi_line = self.memory.line_asserted(InterruptLine::Nmi);
+        let nmi_edge = nmi_line && !self.nmi_line;
+        self.nmi_line = nmi_line;
+
+        let vector = if nmi_edge {
+            NMI_VECTOR
+        } else if !self.processor_status.get_interrupt_disable_flag()
+            && self.memory.line_asserted(InterruptLine::Irq)
+        {
+            IRQ_VECTOR
+        } else {
+            return false;
+        };
+
+        if self.tracer.is_some() {
+            let state = self.state();
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.interrupt(&state);
+            }
+        }
+        self.cycle += INTERRUPT_INTERNAL_CYCLES;
+        let [lo, hi] = self.program_counter.to_le_bytes();
+        self.push_byte_to_stack(hi);
+        self.push_byte_to_stack(lo);
+        let mut status = ProcessorStatus {
+            flags: self.processor_status.flags,
+        };
+        status.set_break_flag(false);
+        self.push_byte_to_stack(status.flags);
+        self.processor_status.set_interrupt_disable_flag(true);
+        self.program_counter = self.fetch_address_from(vector);
+
+        return true;
+    }
+
+    fn execute_instruction(&mut self, check_breakpoints: bool) {
+        self.instruction_address = self.program_counter;
+        if check_breakpoints && !self.breakpoints.is_empty() {
+            self.check_breakpoints();
+            if self.stop_reason.is_some() {
+                return;
+            }
+        }
+
+        let cycles_before_execution = self.cycle;
+        if !self.service_interrupt() {
+            if self.tracer.is_some() {
+                self.trace_instruction();
+            }
+            // peeked first, so stopping at an illegal opcode makes no bus access
+            let opcode = self.memory.peek(self.program_counter);
+            match self.opcode_handlers.get(&opcode).copied() {
+                Some(cb) => {
+                    self.fetch_instruction();
+                    cb(self);
+                }
+                None => {
+                    self.stop_reason = Some(StopReason::IllegalOpcode {
+                        address: self.instruction_address,
+                        opcode,
+                    });
+                }
+            }
+        }
+        self.memory.tick(self.cycle - cycles_before_execution);
+
+        if let Some(hit) = self.watchpoint_hit.take() {
+            self.stop_reason.get_or_insert(StopReason::Watchpoint(hit));
+        }
+    }
 }
 
 fn addressing_takes_extra_cycle_to_fix(addr_mode: AddressingMode) -> bool {
@@ -0,0 +1,126 @@
+use std::{cell::RefCell, collections::VecDeque, fmt, io, rc::Rc};
+
+use crate::{
+    consts::{Byte, Word},
+    memory::AccessKind,
+};
+
+/// Single bus access performed by the CPU, `cycle` is the value of the CPU
+/// cycle counter at the moment the access was made.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BusAccess {
+    pub cycle: u64,
+    pub address: Word,
+    pub value: Byte,
+    pub kind: AccessKind,
+}
+
+impl fmt::Display for BusAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            AccessKind::Read => 'R',
+            AccessKind::Write => 'W',
+            AccessKind::Fetch => 'F',
+        };
+
+        return write!(
+            f,
+            "{} {} ${:04X} ${:02X}",
+            self.cycle, kind, self.address, self.value
+        );
+    }
+}
+
+pub trait AccessRecorder {
+    fn record(&mut self, access: BusAccess);
+}
+
+/// Shared handle to a recorder, so the log can be inspected while the CPU owns it.
+impl<T: AccessRecorder> AccessRecorder for Rc<RefCell<T>> {
+    fn record(&mut self, access: BusAccess) {
+        self.borrow_mut().record(access);
+    }
+}
+
+/// Keeps the most recent `capacity` accesses, dropping the oldest ones.
+pub struct BoundedAccessLog {
+    capacity: usize,
+    accesses: VecDeque<BusAccess>,
+}
+
+impl BoundedAccessLog {
+    pub fn new(capacity: usize) -> Self {
+        return BoundedAccessLog {
+            capacity,
+            accesses: VecDeque::with_capacity(capacity),
+        };
+    }
+
+    pub fn accesses(&self) -> impl Iterator<Item = &BusAccess> {
+        return self.accesses.iter();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.accesses.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.accesses.is_empty();
+    }
+
+    pub fn clear(&mut self) {
+        self.accesses.clear();
+    }
+}
+
+impl AccessRecorder for BoundedAccessLog {
+    fn record(&mut self, access: BusAccess) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.accesses.len() == self.capacity {
+            self.accesses.pop_front();
+        }
+        self.accesses.push_back(access);
+    }
+}
+
+/// Streams every access as a line of text to a writer.
+/// After the first failed write the recorder stops writing and keeps the error.
+pub struct AccessLogWriter<W: io::Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> AccessLogWriter<W> {
+    pub fn new(writer: W) -> Self {
+        return AccessLogWriter {
+            writer,
+            error: None,
+        };
+    }
+
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        return self.error.take();
+    }
+
+    pub fn into_inner(self) -> W {
+        return self.writer;
+    }
+}
+
+impl<W: io::Write> AccessRecorder for AccessLogWriter<W> {
+    fn record(&mut self, access: BusAccess) {
+        if self.error.is_some() {
+            return;
+        }
+
+        if let Err(err) = writeln!(self.writer, "{access}") {
+            self.error = Some(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod bounded_access_log {
+    use crate::cpu::access_log::{AccessRecorder, BoundedAccessLog, BusAccess};
+    use crate::memory::AccessKind;
+
+    fn access(cycle: u64) -> BusAccess {
+        return BusAccess {
+            cycle,
+            address: 0x0200,
+            value: 0x42,
+            kind: AccessKind::Read,
+        };
+    }
+
+    #[test]
+    fn should_keep_only_most_recent_accesses() {
+        let mut uut = BoundedAccessLog::new(2);
+
+        uut.record(access(1));
+        uut.record(access(2));
+        uut.record(access(3));
+
+        let cycles: Vec<u64> = uut.accesses().map(|access| access.cycle).collect();
+        assert_eq!(cycles, vec![2, 3]);
+    }
+
+    #[test]
+    fn should_not_keep_anything_with_zero_capacity() {
+        let mut uut = BoundedAccessLog::new(0);
+
+        uut.record(access(1));
+
+        assert!(uut.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod access_log_writer {
+    use crate::cpu::access_log::{AccessLogWriter, AccessRecorder, BusAccess};
+    use crate::memory::AccessKind;
+
+    #[test]
+    fn should_write_every_access_as_a_line() {
+        let mut uut = AccessLogWriter::new(vec![]);
+
+        uut.record(BusAccess {
+            cycle: 0,
+            address: 0xFFFC,
+            value: 0x4C,
+            kind: AccessKind::Fetch,
+        });
+        uut.record(BusAccess {
+            cycle: 7,
+            address: 0x01FF,
+            value: 0x12,
+            kind: AccessKind::Write,
+        });
+
+        let output = String::from_utf8(uut.into_inner()).unwrap();
+        assert_eq!(output, "0 F $FFFC $4C\n7 W $01FF $12\n");
+    }
+}
@@ -0,0 +1,440 @@
+//! Breakpoints checked before every instruction, optionally guarded by a condition.
+//!
+//! Conditions are expressions over the CPU state:
+//!
+//! ```text
+//! pc == $C000 && a > $10 && [$00FE] == 0
+//! ```
+//!
+//! - registers `a`, `x`, `y`, `sp`, `p` and `pc`, the cycle counter `cycles`,
+//! - flags `n`, `v`, `b`, `d`, `i`, `z` and `c`, each 0 or 1,
+//! - `[ADDRESS]` for the byte and `{ADDRESS}` for the little-endian word at an address,
+//! - numbers in decimal, hexadecimal with `$` or `0x`, or binary with `%`,
+//! - operators `||`, `&&`, comparisons `==` `!=` `<` `<=` `>` `>=`, `|`, `^`,
+//!   `&`, `+` `-`, and the unary `!` `-` `~`, from the loosest to the tightest binding.
+//!
+//! Memory is read without side effects, like a debugger would.
+
+use std::fmt;
+
+use crate::{
+    consts::{Byte, Word},
+    cpu::CpuState,
+    memory::Bus,
+};
+
+#[derive(Debug, PartialEq)]
+pub struct ConditionError(pub String);
+
+impl fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.0);
+    }
+}
+
+impl std::error::Error for ConditionError {}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Operand {
+    Accumulator,
+    IndexX,
+    IndexY,
+    StackPointer,
+    ProcessorStatus,
+    ProgramCounter,
+    Cycles,
+    /// Bit of the processor status.
+    Flag(Byte),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum BinaryOp {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Add,
+    Subtract,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum UnaryOp {
+    Not,
+    Negate,
+    Complement,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Number(i64),
+    Operand(Operand),
+    Byte(Box<Expr>),
+    Word(Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+/// Operators of each precedence level, loosest first.
+const LEVELS: [&[(&str, BinaryOp)]; 7] = [
+    &[("||", BinaryOp::Or)],
+    &[("&&", BinaryOp::And)],
+    &[
+        ("==", BinaryOp::Equal),
+        ("!=", BinaryOp::NotEqual),
+        ("<=", BinaryOp::LessOrEqual),
+        (">=", BinaryOp::GreaterOrEqual),
+        ("<", BinaryOp::Less),
+        (">", BinaryOp::Greater),
+    ],
+    &[("|", BinaryOp::BitOr)],
+    &[("^", BinaryOp::BitXor)],
+    &[("&", BinaryOp::BitAnd)],
+    &[("+", BinaryOp::Add), ("-", BinaryOp::Subtract)],
+];
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        return &self.text[self.position..];
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn error(&self, message: &str) -> ConditionError {
+        return ConditionError(format!("{message} at column {}", self.position + 1));
+    }
+
+    /// Consumes `token` if the input continues with it.
+    fn accept(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if !self.rest().starts_with(token) {
+            return false;
+        }
+        // `|` and `&` must not split `||` and `&&`
+        if token.len() == 1 && self.rest()[1..].starts_with(token) && "|&".contains(token) {
+            return false;
+        }
+
+        self.position += token.len();
+        return true;
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ConditionError> {
+        if !self.accept(token) {
+            return Err(self.error(&format!("expected \"{token}\"")));
+        }
+
+        return Ok(());
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, ConditionError> {
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+
+        let mut lhs = self.binary(level + 1)?;
+        'operators: loop {
+            for (token, op) in LEVELS[level] {
+                if self.accept(token) {
+                    let rhs = self.binary(level + 1)?;
+                    lhs = Expr::Binary(*op, Box::new(lhs), Box::new(rhs));
+                    continue 'operators;
+                }
+            }
+
+            return Ok(lhs);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ConditionError> {
+        for (token, op) in [
+            ("!", UnaryOp::Not),
+            ("-", UnaryOp::Negate),
+            ("~", UnaryOp::Complement),
+        ] {
+            if self.rest().trim_start().starts_with("!=") {
+                break;
+            }
+            if self.accept(token) {
+                return Ok(Expr::Unary(op, Box::new(self.unary()?)));
+            }
+        }
+
+        return self.primary();
+    }
+
+    fn primary(&mut self) -> Result<Expr, ConditionError> {
+        for (open, close, memory) in [
+            ("(", ")", None),
+            ("[", "]", Some(false)),
+            ("{", "}", Some(true)),
+        ] {
+            if self.accept(open) {
+                let inner = self.binary(0)?;
+                self.expect(close)?;
+                return Ok(match memory {
+                    None => inner,
+                    Some(false) => Expr::Byte(Box::new(inner)),
+                    Some(true) => Expr::Word(Box::new(inner)),
+                });
+            }
+        }
+
+        self.skip_whitespace();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '$' && c != '%' && c != '_')
+            .unwrap_or(rest.len());
+        let word = &rest[..len];
+        if word.is_empty() {
+            return Err(self.error("expected a value"));
+        }
+
+        let expr = match parse_number(word) {
+            Some(value) => Expr::Number(value),
+            None => Expr::Operand(
+                operand(word).ok_or_else(|| self.error(&format!("unknown name \"{word}\"")))?,
+            ),
+        };
+        self.position += len;
+
+        return Ok(expr);
+    }
+}
+
+fn parse_number(word: &str) -> Option<i64> {
+    if let Some(hex) = word.strip_prefix('$') {
+        return i64::from_str_radix(hex, 16).ok();
+    }
+    if let Some(hex) = word.strip_prefix("0x").or(word.strip_prefix("0X")) {
+        return i64::from_str_radix(hex, 16).ok();
+    }
+    if let Some(binary) = word.strip_prefix('%') {
+        return i64::from_str_radix(binary, 2).ok();
+    }
+    if word.starts_with(|c: char| c.is_ascii_digit()) {
+        return word.parse().ok();
+    }
+
+    return None;
+}
+
+fn operand(name: &str) -> Option<Operand> {
+    return match name.to_ascii_lowercase().as_str() {
+        "a" => Some(Operand::Accumulator),
+        "x" => Some(Operand::IndexX),
+        "y" => Some(Operand::IndexY),
+        "sp" => Some(Operand::StackPointer),
+        "p" => Some(Operand::ProcessorStatus),
+        "pc" => Some(Operand::ProgramCounter),
+        "cycles" => Some(Operand::Cycles),
+        "n" => Some(Operand::Flag(7)),
+        "v" => Some(Operand::Flag(6)),
+        "b" => Some(Operand::Flag(4)),
+        "d" => Some(Operand::Flag(3)),
+        "i" => Some(Operand::Flag(2)),
+        "z" => Some(Operand::Flag(1)),
+        "c" => Some(Operand::Flag(0)),
+        _ => None,
+    };
+}
+
+impl Expr {
+    fn evaluate(&self, state: &CpuState, memory: &dyn Bus) -> i64 {
+        let peek = |address: i64| i64::from(memory.peek(address as Word));
+
+        return match self {
+            Expr::Number(value) => *value,
+            Expr::Operand(operand) => match operand {
+                Operand::Accumulator => i64::from(state.accumulator),
+                Operand::IndexX => i64::from(state.index_register_x),
+                Operand::IndexY => i64::from(state.index_register_y),
+                Operand::StackPointer => i64::from(state.stack_pointer),
+                Operand::ProcessorStatus => i64::from(state.processor_status),
+                Operand::ProgramCounter => i64::from(state.program_counter),
+                Operand::Cycles => state.cycle as i64,
+                Operand::Flag(bit) => i64::from((state.processor_status >> bit) & 1),
+            },
+            Expr::Byte(address) => peek(address.evaluate(state, memory)),
+            Expr::Word(address) => {
+                let address = address.evaluate(state, memory);
+                peek(address) | peek(address.wrapping_add(1)) << 8
+            }
+            Expr::Unary(op, operand) => {
+                let value = operand.evaluate(state, memory);
+                match op {
+                    UnaryOp::Not => i64::from(value == 0),
+                    UnaryOp::Negate => value.wrapping_neg(),
+                    UnaryOp::Complement => !value,
+                }
+            }
+            Expr::Binary(BinaryOp::Or, lhs, rhs) => {
+                i64::from(lhs.evaluate(state, memory) != 0 || rhs.evaluate(state, memory) != 0)
+            }
+            Expr::Binary(BinaryOp::And, lhs, rhs) => {
+                i64::from(lhs.evaluate(state, memory) != 0 && rhs.evaluate(state, memory) != 0)
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.evaluate(state, memory), rhs.evaluate(state, memory));
+                match op {
+                    BinaryOp::Equal => i64::from(lhs == rhs),
+                    BinaryOp::NotEqual => i64::from(lhs != rhs),
+                    BinaryOp::Less => i64::from(lhs < rhs),
+                    BinaryOp::LessOrEqual => i64::from(lhs <= rhs),
+                    BinaryOp::Greater => i64::from(lhs > rhs),
+                    BinaryOp::GreaterOrEqual => i64::from(lhs >= rhs),
+                    BinaryOp::BitOr => lhs | rhs,
+                    BinaryOp::BitXor => lhs ^ rhs,
+                    BinaryOp::BitAnd => lhs & rhs,
+                    BinaryOp::Add => lhs.wrapping_add(rhs),
+                    BinaryOp::Subtract => lhs.wrapping_sub(rhs),
+                    BinaryOp::Or | BinaryOp::And => unreachable!("short-circuited above"),
+                }
+            }
+        };
+    }
+}
+
+/// Parsed condition, displayed as the text it was parsed from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+impl Condition {
+    pub fn parse(text: &str) -> Result<Self, ConditionError> {
+        let mut parser = Parser { text, position: 0 };
+        let expr = parser.binary(0)?;
+        parser.skip_whitespace();
+        if !parser.rest().is_empty() {
+            return Err(parser.error("unexpected input"));
+        }
+
+        return Ok(Condition {
+            source: text.trim().to_string(),
+            expr,
+        });
+    }
+
+    pub fn evaluate(&self, state: &CpuState, memory: &dyn Bus) -> i64 {
+        return self.expr.evaluate(state, memory);
+    }
+
+    pub fn is_true(&self, state: &CpuState, memory: &dyn Bus) -> bool {
+        return self.evaluate(state, memory) != 0;
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.source);
+    }
+}
+
+/// Stops execution once the program counter reaches `address` and
+/// `condition` holds, both being optional. The first `ignore_count` hits
+/// only increment `hit_count`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Breakpoint {
+    pub address: Option<Word>,
+    pub condition: Option<Condition>,
+    pub ignore_count: u64,
+    pub hit_count: u64,
+}
+
+impl Breakpoint {
+    pub fn at(address: Word) -> Self {
+        return Breakpoint {
+            address: Some(address),
+            condition: None,
+            ignore_count: 0,
+            hit_count: 0,
+        };
+    }
+
+    pub fn when(condition: Condition) -> Self {
+        return Breakpoint {
+            address: None,
+            condition: Some(condition),
+            ignore_count: 0,
+            hit_count: 0,
+        };
+    }
+
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        return self;
+    }
+
+    pub fn with_ignore_count(mut self, count: u64) -> Self {
+        self.ignore_count = count;
+        return self;
+    }
+
+    pub fn matches(&self, state: &CpuState, memory: &dyn Bus) -> bool {
+        if self
+            .address
+            .is_some_and(|address| address != state.program_counter)
+        {
+            return false;
+        }
+
+        return match &self.condition {
+            Some(condition) => condition.is_true(state, memory),
+            None => true,
+        };
+    }
+
+    /// Counts a hit if the breakpoint matches, returns whether execution should stop.
+    pub fn check(&mut self, state: &CpuState, memory: &dyn Bus) -> bool {
+        if !self.matches(state, memory) {
+            return false;
+        }
+
+        self.hit_count += 1;
+        return self.hit_count > self.ignore_count;
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.address, &self.condition) {
+            (Some(address), Some(condition)) => write!(f, "${address:04X} if {condition}")?,
+            (Some(address), None) => write!(f, "${address:04X}")?,
+            (None, Some(condition)) => write!(f, "if {condition}")?,
+            (None, None) => write!(f, "always")?,
+        }
+        write!(f, ", {} hits", self.hit_count)?;
+        if self.ignore_count > 0 {
+            write!(f, ", ignoring {}", self.ignore_count)?;
+        }
+
+        return Ok(());
+    }
+}
+
+/// Breakpoint which stopped execution, `program_counter` is the address of the next instruction.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BreakpointHit {
+    pub id: usize,
+    pub program_counter: Word,
+    pub hit_count: u64,
+}
+
+#[cfg(test)]
+mod tests;
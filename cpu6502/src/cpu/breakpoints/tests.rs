@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod condition {
+    use crate::cpu::{
+        breakpoints::{Condition, ConditionError},
+        CpuState,
+    };
+    use crate::memory::VecMemory;
+
+    fn state() -> CpuState {
+        return CpuState {
+            cycle: 1234,
+            program_counter: 0xC000,
+            stack_pointer: 0xFD,
+            accumulator: 0x20,
+            index_register_x: 0x03,
+            index_register_y: 0x00,
+            processor_status: 0b1000_0011,
+        };
+    }
+
+    fn evaluate(text: &str) -> i64 {
+        let memory = VecMemory::from(&[(0x00FE, 0x00), (0x00FF, 0x12), (0x0100, 0x34)][..]);
+        return Condition::parse(text).unwrap().evaluate(&state(), &memory);
+    }
+
+    fn error(text: &str) -> String {
+        let ConditionError(message) = Condition::parse(text).unwrap_err();
+        return message;
+    }
+
+    #[test]
+    fn should_read_registers_flags_and_cycles() {
+        assert_eq!(evaluate("pc"), 0xC000);
+        assert_eq!(evaluate("A + X + y"), 0x23);
+        assert_eq!(evaluate("sp"), 0xFD);
+        assert_eq!(evaluate("p"), 0x83);
+        assert_eq!(evaluate("n"), 1);
+        assert_eq!(evaluate("v"), 0);
+        assert_eq!(evaluate("z + c"), 2);
+        assert_eq!(evaluate("cycles"), 1234);
+    }
+
+    #[test]
+    fn should_read_memory_bytes_and_words() {
+        assert_eq!(evaluate("[$00FF]"), 0x12);
+        assert_eq!(evaluate("{$00FF}"), 0x3412);
+        assert_eq!(evaluate("[$00F0 + x + $0C]"), 0x12);
+    }
+
+    #[test]
+    fn should_parse_numbers_in_every_base() {
+        assert_eq!(evaluate("$1F"), 0x1F);
+        assert_eq!(evaluate("0x1F"), 0x1F);
+        assert_eq!(evaluate("%101"), 5);
+        assert_eq!(evaluate("31"), 31);
+    }
+
+    #[test]
+    fn should_apply_operator_precedence() {
+        assert_eq!(evaluate("pc == $C000 && a > $10 && [$00FE] == 0"), 1);
+        assert_eq!(evaluate("a == 1 || x == 3 && y == 0"), 1);
+        assert_eq!(evaluate("(a == 1 || x == 3) && y == 1"), 0);
+        assert_eq!(evaluate("p & $80 != 0"), 1);
+        assert_eq!(evaluate("1 | 2 ^ 3 & 1"), 3);
+        assert_eq!(evaluate("10 - 3 - 2"), 5);
+        assert_eq!(evaluate("!z"), 0);
+        assert_eq!(evaluate("-1 < 0"), 1);
+        assert_eq!(evaluate("~0"), -1);
+        assert_eq!(evaluate("a >= $20 && a <= $20 && a != 0"), 1);
+    }
+
+    #[test]
+    fn should_report_errors_with_column() {
+        assert_eq!(error("a == "), "expected a value at column 6");
+        assert_eq!(error("q == 1"), "unknown name \"q\" at column 1");
+        assert_eq!(error("[$10"), "expected \"]\" at column 5");
+        assert_eq!(error("a 1"), "unexpected input at column 3");
+    }
+
+    #[test]
+    fn should_display_source_text() {
+        assert_eq!(Condition::parse("  a == 1 ").unwrap().to_string(), "a == 1");
+    }
+}
+
+#[cfg(test)]
+mod breakpoint {
+    use crate::cpu::{
+        breakpoints::{Breakpoint, Condition},
+        CpuState,
+    };
+    use crate::memory::VecMemory;
+
+    fn state(program_counter: u16, accumulator: u8) -> CpuState {
+        return CpuState {
+            cycle: 0,
+            program_counter,
+            stack_pointer: 0,
+            accumulator,
+            index_register_x: 0,
+            index_register_y: 0,
+            processor_status: 0,
+        };
+    }
+
+    #[test]
+    fn should_match_address_and_condition() {
+        let memory = VecMemory::new();
+        let uut = Breakpoint::at(0x0200).with_condition(Condition::parse("a > 1").unwrap());
+
+        assert!(uut.matches(&state(0x0200, 2), &memory));
+        assert!(!uut.matches(&state(0x0200, 1), &memory));
+        assert!(!uut.matches(&state(0x0201, 2), &memory));
+    }
+
+    #[test]
+    fn should_count_hits_and_stop_after_ignore_count() {
+        let memory = VecMemory::new();
+        let mut uut = Breakpoint::when(Condition::parse("a == 0").unwrap()).with_ignore_count(2);
+
+        assert!(!uut.check(&state(0x0200, 0), &memory));
+        assert!(!uut.check(&state(0x0200, 1), &memory));
+        assert!(!uut.check(&state(0x0200, 0), &memory));
+        assert!(uut.check(&state(0x0200, 0), &memory));
+        assert_eq!(uut.hit_count, 3);
+    }
+
+    #[test]
+    fn should_describe_itself() {
+        let uut = Breakpoint::at(0xC000)
+            .with_condition(Condition::parse("a == 0").unwrap())
+            .with_ignore_count(5);
+
+        assert_eq!(uut.to_string(), "$C000 if a == 0, 0 hits, ignoring 5");
+        assert_eq!(Breakpoint::at(0x0200).to_string(), "$0200, 0 hits");
+    }
+}
@@ -102,6 +102,27 @@ pub fn rts(cpu: &mut CPU) {
     cpu.increment_program_counter();
 }
 
+pub fn rti(cpu: &mut CPU) {
+    cpu.access_memory(cpu.program_counter); // fetch and discard
+    cpu.cycle += 1;
+
+    let status = cpu.pop_byte_from_stack();
+    cpu.set_register(Registers::ProcessorStatus, status);
+    cpu.processor_status.set_break_flag(false);
+    cpu.program_counter = cpu.pop_word_from_stack();
+    cpu.cycle += 1;
+}
+
+pub fn sei(cpu: &mut CPU) {
+    cpu.processor_status.set_interrupt_disable_flag(true);
+    cpu.cycle += 1;
+}
+
+pub fn cli(cpu: &mut CPU) {
+    cpu.processor_status.set_interrupt_disable_flag(false);
+    cpu.cycle += 1;
+}
+
 fn jmp(cpu: &mut CPU, addr_mode: AddressingMode) {
     match cpu.get_address(addr_mode, super::MemoryOperation::Read) {
         Some(address) => cpu.program_counter = address,
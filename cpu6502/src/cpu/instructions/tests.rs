@@ -35,6 +35,16 @@ mod lda {
 
             assert_eq!(cpu.cycle, 1);
         }
+
+        #[test]
+        fn should_advance_program_counter_past_the_operand() {
+            let mut cpu = CPU::new(Box::new(MemoryMock::default()));
+            cpu.program_counter = 0x00;
+
+            lda_im(&mut cpu);
+
+            assert_eq!(cpu.program_counter, 0x01);
+        }
     }
 
     #[cfg(test)]
@@ -912,8 +922,8 @@ mod jsr_a {
 
         jsr_a(&mut cpu);
 
-        assert_eq!(cpu.memory[0x01FF], 0x01);
-        assert_eq!(cpu.memory[0x01FE], 0x00);
+        assert_eq!(cpu.memory.peek(0x01FF), 0x01);
+        assert_eq!(cpu.memory.peek(0x01FE), 0x00);
     }
 
     #[test]
@@ -939,6 +949,86 @@ mod jsr_a {
     }
 }
 
+#[cfg(test)]
+mod rti {
+    use super::super::*;
+    use crate::cpu::tests::MemoryMock;
+
+    #[test]
+    fn should_pull_processor_status_and_program_counter_from_stack() {
+        let mut cpu = CPU::new(Box::new(MemoryMock::new(&[0x01, 0x02, 0x03])));
+        cpu.program_counter = 0x00;
+        cpu.memory.write(0x01FF, 0x44);
+        cpu.memory.write(0x01FE, 0x51);
+        cpu.memory.write(0x01FD, 0b10000011);
+        cpu.stack_pointer = 0xFC;
+
+        rti(&mut cpu);
+
+        assert_eq!(cpu.program_counter, 0x4451);
+        assert_eq!(cpu.processor_status.flags, 0b10000011);
+        assert_eq!(cpu.stack_pointer, 0xFF);
+    }
+
+    #[test]
+    fn should_ignore_break_flag_pulled_from_stack() {
+        let mut cpu = CPU::new(Box::new(MemoryMock::new(&[0x01, 0x02, 0x03])));
+        cpu.program_counter = 0x00;
+        cpu.memory.write(0x01FD, 0b00010000);
+        cpu.stack_pointer = 0xFC;
+
+        rti(&mut cpu);
+
+        assert_eq!(cpu.processor_status.flags, 0);
+    }
+
+    #[test]
+    fn should_take_six_cycles() {
+        let mut cpu = CPU::new(Box::new(MemoryMock::new(&[0x40, 0x02, 0x03])));
+        cpu.program_counter = 0x00;
+        cpu.stack_pointer = 0xFC;
+
+        cpu.step();
+
+        assert_eq!(cpu.cycle, 6);
+    }
+}
+
+#[cfg(test)]
+mod sei {
+    use super::super::*;
+    use crate::cpu::tests::MemoryMock;
+
+    #[test]
+    fn should_set_interrupt_disable_flag_in_two_cycles() {
+        let mut cpu = CPU::new(Box::new(MemoryMock::new(&[0x78])));
+        cpu.program_counter = 0x00;
+
+        cpu.step();
+
+        assert!(cpu.processor_status.get_interrupt_disable_flag());
+        assert_eq!(cpu.cycle, 2);
+    }
+}
+
+#[cfg(test)]
+mod cli {
+    use super::super::*;
+    use crate::cpu::tests::MemoryMock;
+
+    #[test]
+    fn should_clear_interrupt_disable_flag_in_two_cycles() {
+        let mut cpu = CPU::new(Box::new(MemoryMock::new(&[0x58])));
+        cpu.program_counter = 0x00;
+        cpu.processor_status.set_interrupt_disable_flag(true);
+
+        cpu.step();
+
+        assert!(!cpu.processor_status.get_interrupt_disable_flag());
+        assert_eq!(cpu.cycle, 2);
+    }
+}
+
 #[cfg(test)]
 mod rts {
     use super::super::*;
@@ -948,8 +1038,8 @@ mod rts {
     fn should_fetch_address_from_stack_and_put_it_in_program_counter_incremented_by_one() {
         let mut cpu = CPU::new(Box::new(MemoryMock::new(&[0x01, 0x02, 0x03])));
         cpu.program_counter = 0x00;
-        cpu.memory[0x01FF] = 0x44;
-        cpu.memory[0x01FE] = 0x51;
+        cpu.memory.write(0x01FF, 0x44);
+        cpu.memory.write(0x01FE, 0x51);
         cpu.stack_pointer = 0xFD;
 
         rts(&mut cpu);
@@ -961,8 +1051,8 @@ mod rts {
     fn should_increment_stack_pointer_twice() {
         let mut cpu = CPU::new(Box::new(MemoryMock::new(&[0x01, 0x02, 0x03])));
         cpu.program_counter = 0x00;
-        cpu.memory[0x01FF] = 0x44;
-        cpu.memory[0x01FE] = 0x51;
+        cpu.memory.write(0x01FF, 0x44);
+        cpu.memory.write(0x01FE, 0x51);
         cpu.stack_pointer = 0xFD;
 
         rts(&mut cpu);
@@ -974,8 +1064,8 @@ mod rts {
     fn should_take_five_cycles() {
         let mut cpu = CPU::new(Box::new(MemoryMock::new(&[0x01, 0x02, 0x03])));
         cpu.program_counter = 0x00;
-        cpu.memory[0x01FF] = 0x44;
-        cpu.memory[0x01FE] = 0x51;
+        cpu.memory.write(0x01FF, 0x44);
+        cpu.memory.write(0x01FE, 0x51);
         cpu.stack_pointer = 0xFD;
         cpu.cycle = 0;
 
@@ -2073,7 +2163,7 @@ mod increment {
 
             inc_zp(&mut cpu);
 
-            assert_eq!(cpu.memory[ZERO_PAGE_ADDR as Word], 0x03);
+            assert_eq!(cpu.memory.peek(ZERO_PAGE_ADDR as Word), 0x03);
         }
 
         #[test]
@@ -2115,7 +2205,7 @@ mod increment {
 
             inc_zpx(&mut cpu);
 
-            assert_eq!(cpu.memory[ZERO_PAGE_ADDR_SUM_X as Word], 0x0A);
+            assert_eq!(cpu.memory.peek(ZERO_PAGE_ADDR_SUM_X as Word), 0x0A);
         }
 
         #[test]
@@ -2154,7 +2244,7 @@ mod increment {
 
             inc_a(&mut cpu);
 
-            assert_eq!(cpu.memory[ADDR as Word], 0x0A);
+            assert_eq!(cpu.memory.peek(ADDR as Word), 0x0A);
         }
 
         #[test]
@@ -2192,7 +2282,7 @@ mod increment {
 
             inc_a_x(&mut cpu);
 
-            assert_eq!(cpu.memory[ADDR_OFFSET_BY_X], 0x0A);
+            assert_eq!(cpu.memory.peek(ADDR_OFFSET_BY_X), 0x0A);
         }
 
         #[test]
@@ -2283,7 +2373,7 @@ mod decrement {
 
             dec_zp(&mut cpu);
 
-            assert_eq!(cpu.memory[ZERO_PAGE_ADDR as Word], 0x01);
+            assert_eq!(cpu.memory.peek(ZERO_PAGE_ADDR as Word), 0x01);
         }
 
         #[test]
@@ -2325,7 +2415,7 @@ mod decrement {
 
             dec_zpx(&mut cpu);
 
-            assert_eq!(cpu.memory[ZERO_PAGE_ADDR_SUM_X as Word], 0x08);
+            assert_eq!(cpu.memory.peek(ZERO_PAGE_ADDR_SUM_X as Word), 0x08);
         }
 
         #[test]
@@ -2364,7 +2454,7 @@ mod decrement {
 
             dec_a(&mut cpu);
 
-            assert_eq!(cpu.memory[ADDR as Word], 0x08);
+            assert_eq!(cpu.memory.peek(ADDR as Word), 0x08);
         }
 
         #[test]
@@ -2402,7 +2492,7 @@ mod decrement {
 
             dec_a_x(&mut cpu);
 
-            assert_eq!(cpu.memory[ADDR_OFFSET_BY_X], 0x08);
+            assert_eq!(cpu.memory.peek(ADDR_OFFSET_BY_X), 0x08);
         }
 
         #[test]
@@ -2442,7 +2532,7 @@ mod store {
 
             sta_zp(&mut cpu);
 
-            assert_eq!(cpu.memory[ZERO_PAGE_ADDR as Word], 0x02);
+            assert_eq!(cpu.memory.peek(ZERO_PAGE_ADDR as Word), 0x02);
         }
 
         #[test]
@@ -2485,7 +2575,7 @@ mod store {
 
             sta_zpx(&mut cpu);
 
-            assert_eq!(cpu.memory[ZERO_PAGE_ADDR_SUM_X], 0x05);
+            assert_eq!(cpu.memory.peek(ZERO_PAGE_ADDR_SUM_X), 0x05);
         }
 
         #[test]
@@ -2525,7 +2615,7 @@ mod store {
 
             sta_a(&mut cpu);
 
-            assert_eq!(cpu.memory[ADDR as Word], 0x0A);
+            assert_eq!(cpu.memory.peek(ADDR as Word), 0x0A);
         }
 
         #[test]
@@ -2563,7 +2653,7 @@ mod store {
 
             sta_a_x(&mut cpu);
 
-            assert_eq!(cpu.memory[ADDR_OFFSET_BY_X], 0x08);
+            assert_eq!(cpu.memory.peek(ADDR_OFFSET_BY_X), 0x08);
         }
 
         #[test]
@@ -2602,7 +2692,7 @@ mod store {
 
             sta_a_y(&mut cpu);
 
-            assert_eq!(cpu.memory[ADDR_OFFSET_BY_Y], 0x08);
+            assert_eq!(cpu.memory.peek(ADDR_OFFSET_BY_Y), 0x08);
         }
 
         #[test]
@@ -2649,7 +2739,7 @@ mod store {
 
             sta_in_x(&mut cpu);
 
-            assert_eq!(cpu.memory[EFFECTIVE_ADDRESS], 0xA9);
+            assert_eq!(cpu.memory.peek(EFFECTIVE_ADDRESS), 0xA9);
         }
 
         #[test]
@@ -2696,7 +2786,7 @@ mod store {
 
             sta_in_y(&mut cpu);
 
-            assert_eq!(cpu.memory[EFFECTIVE_ADDRESS], 0xDF);
+            assert_eq!(cpu.memory.peek(EFFECTIVE_ADDRESS), 0xDF);
         }
 
         #[test]
@@ -2734,7 +2824,7 @@ mod store {
 
             stx_zp(&mut cpu);
 
-            assert_eq!(cpu.memory[ZERO_PAGE_ADDR as Word], 0x02);
+            assert_eq!(cpu.memory.peek(ZERO_PAGE_ADDR as Word), 0x02);
         }
 
         #[test]
@@ -2777,7 +2867,7 @@ mod store {
 
             stx_zpy(&mut cpu);
 
-            assert_eq!(cpu.memory[ZERO_PAGE_ADDR_SUM_Y], 0x05);
+            assert_eq!(cpu.memory.peek(ZERO_PAGE_ADDR_SUM_Y), 0x05);
         }
 
         #[test]
@@ -2817,7 +2907,7 @@ mod store {
 
             stx_a(&mut cpu);
 
-            assert_eq!(cpu.memory[ADDR as Word], 0x0A);
+            assert_eq!(cpu.memory.peek(ADDR as Word), 0x0A);
         }
 
         #[test]
@@ -2854,7 +2944,7 @@ mod store {
 
             sty_zp(&mut cpu);
 
-            assert_eq!(cpu.memory[ZERO_PAGE_ADDR as Word], 0x02);
+            assert_eq!(cpu.memory.peek(ZERO_PAGE_ADDR as Word), 0x02);
         }
 
         #[test]
@@ -2897,7 +2987,7 @@ mod store {
 
             sty_zpx(&mut cpu);
 
-            assert_eq!(cpu.memory[ZERO_PAGE_ADDR_SUM_X], 0x05);
+            assert_eq!(cpu.memory.peek(ZERO_PAGE_ADDR_SUM_X), 0x05);
         }
 
         #[test]
@@ -2937,7 +3027,7 @@ mod store {
 
             sty_a(&mut cpu);
 
-            assert_eq!(cpu.memory[ADDR as Word], 0x0A);
+            assert_eq!(cpu.memory.peek(ADDR as Word), 0x0A);
         }
 
         #[test]
@@ -18,6 +18,17 @@ impl MemoryMock {
     }
 }
 
+impl From<&[(Word, Byte)]> for MemoryMock {
+    fn from(pairs: &[(Word, Byte)]) -> Self {
+        let mut mock = MemoryMock { data: [0; 512] };
+        for (address, value) in pairs {
+            mock.data[usize::from(*address)] = *value;
+        }
+
+        return mock;
+    }
+}
+
 impl Default for MemoryMock {
     fn default() -> Self {
         const DATA: [u8; 5] = [0x44, 0x51, 0x88, 0x42, 0x99];
@@ -91,6 +102,7 @@ mod access_memory {
     use super::MemoryMock;
     use crate::consts::Word;
     use crate::cpu::CPU;
+    use crate::memory::Bus;
 
     const ADDR: Word = 0x0003;
 
@@ -102,6 +114,36 @@ mod access_memory {
 
         assert_eq!(result, 0x42);
     }
+
+    #[test]
+    fn should_read_through_bus_allowing_side_effects() {
+        let mut uut = CPU::new(Box::new(MemoryMock::default()));
+        uut.memory = Box::new(ClearOnReadMock { value: 0x42 });
+
+        uut.access_memory(ADDR);
+
+        assert_eq!(uut.memory.peek(ADDR), 0x00);
+    }
+
+    struct ClearOnReadMock {
+        value: u8,
+    }
+
+    impl Bus for ClearOnReadMock {
+        fn read(&mut self, _addr: Word) -> u8 {
+            let value = self.value;
+            self.value = 0;
+            return value;
+        }
+
+        fn write(&mut self, _addr: Word, value: u8) {
+            self.value = value;
+        }
+
+        fn peek(&self, _addr: Word) -> u8 {
+            return self.value;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -322,7 +364,7 @@ mod push_byte_to_stack {
         let value: u8 = 0xDF;
         uut.push_byte_to_stack(value);
 
-        assert_eq!(uut.memory[0x01FF], 0xDF);
+        assert_eq!(uut.memory.peek(0x01FF), 0xDF);
     }
 
     #[test]
@@ -353,8 +395,8 @@ mod push_word_to_stack {
         let value: u16 = 0x56DF;
         uut.push_word_to_stack(value);
 
-        assert_eq!(uut.memory[0x01FF], 0xDF);
-        assert_eq!(uut.memory[0x01FE], 0x56);
+        assert_eq!(uut.memory.peek(0x01FF), 0xDF);
+        assert_eq!(uut.memory.peek(0x01FE), 0x56);
     }
 
     #[test]
@@ -379,8 +421,8 @@ mod pop_byte_from_stack {
     #[test]
     fn should_pop_byte_from_stack() {
         let mut uut = CPU::new(Box::new(MemoryMock::default()));
-        uut.memory[0x01FF] = 0xDF;
-        uut.memory[0x01FE] = 0x48;
+        uut.memory.write(0x01FF, 0xDF);
+        uut.memory.write(0x01FE, 0x48);
         uut.stack_pointer = 0xFD;
 
         let value = uut.pop_byte_from_stack();
@@ -391,8 +433,8 @@ mod pop_byte_from_stack {
     #[test]
     fn should_increment_cycle_count_and_stack_pointer_once() {
         let mut uut = CPU::new(Box::new(MemoryMock::default()));
-        uut.memory[0x01FF] = 0xDF;
-        uut.memory[0x01FE] = 0x48;
+        uut.memory.write(0x01FF, 0xDF);
+        uut.memory.write(0x01FE, 0x48);
         uut.stack_pointer = 0xFD;
 
         assert_eq!(uut.cycle, 0);
@@ -412,8 +454,8 @@ mod pop_word_from_stack {
     #[test]
     fn should_pop_word_from_stack() {
         let mut uut = CPU::new(Box::new(MemoryMock::default()));
-        uut.memory[0x01FF] = 0xDF;
-        uut.memory[0x01FE] = 0x48;
+        uut.memory.write(0x01FF, 0xDF);
+        uut.memory.write(0x01FE, 0x48);
         uut.stack_pointer = 0xFD;
 
         let val = uut.pop_word_from_stack();
@@ -424,8 +466,8 @@ mod pop_word_from_stack {
     #[test]
     fn should_increment_cycle_count_and_stack_pointer_twice() {
         let mut uut = CPU::new(Box::new(MemoryMock::default()));
-        uut.memory[0x01FF] = 0xDF;
-        uut.memory[0x01FE] = 0x48;
+        uut.memory.write(0x01FF, 0xDF);
+        uut.memory.write(0x01FE, 0x48);
         uut.stack_pointer = 0xFD;
         assert_eq!(uut.cycle, 0);
 
@@ -1082,7 +1124,7 @@ mod get_address {
     #[cfg(test)]
     mod indirect_addressing {
         use super::super::MemoryMock;
-        use crate::cpu::{AddressingMode, MemoryOperation, CPU};
+        use crate::cpu::{AddressingMode, CpuVariant, MemoryOperation, CPU};
 
         #[test]
         fn should_return_address_from_place_in_memory_stored_in_next_word_relative_to_program_counter(
@@ -1126,6 +1168,32 @@ mod get_address {
 
             assert_eq!(result.unwrap(), 0xFF00);
         }
+
+        #[test]
+        fn should_read_address_across_page_edge_on_cmos_variant() {
+            let mut memory = MemoryMock::new(&[0xFF, 0x00, 0x04, 0x00]);
+            memory.data[0x00FF] = 0x34;
+            memory.data[0x0100] = 0x12;
+            let mut uut = CPU::new(Box::new(memory));
+            uut.set_variant(CpuVariant::Cmos65C02);
+            uut.program_counter = 0x00;
+
+            let result = uut.get_address(AddressingMode::Indirect, MemoryOperation::Read);
+
+            assert_eq!(result.unwrap(), 0x1234);
+        }
+
+        #[test]
+        fn should_take_five_cycles_on_cmos_variant() {
+            let mut uut = CPU::new(Box::new(MemoryMock::new(&[0x02, 0x00, 0x01, 0x00])));
+            uut.set_variant(CpuVariant::Cmos65C02);
+            uut.program_counter = 0x02;
+            uut.cycle = 0;
+
+            uut.get_address(AddressingMode::Indirect, MemoryOperation::Read);
+
+            assert_eq!(uut.cycle, 5);
+        }
     }
 
     #[cfg(test)]
@@ -1202,3 +1270,662 @@ mod get_address {
         }
     }
 }
+
+#[cfg(test)]
+mod watchpoints {
+    use super::MemoryMock;
+    use crate::asm6502;
+    use crate::cpu::{
+        watchpoints::{Watchpoint, WatchpointHit},
+        StopReason, CPU,
+    };
+    use crate::memory::AccessKind;
+
+    fn program() -> MemoryMock {
+        return MemoryMock::from(
+            &asm6502! {
+                lda 0x10;
+                sta 0x11;
+                inc 0x11;
+                ldx 0x10;
+                org 0x10;
+                byte 0x42, 0x07;
+            }[..],
+        );
+    }
+
+    fn cpu_with_program() -> CPU {
+        let mut cpu = CPU::new(Box::new(program()));
+        cpu.program_counter = 0x0000;
+
+        return cpu;
+    }
+
+    #[test]
+    fn should_run_requested_cycles_when_no_watchpoint_triggers() {
+        let mut uut = cpu_with_program();
+        uut.add_watchpoint(Watchpoint::at(0x0100, AccessKind::Write));
+
+        uut.execute(8);
+
+        assert_eq!(uut.stop_reason(), None);
+        assert_eq!(uut.program_counter, 0x0006);
+    }
+
+    #[test]
+    fn should_stop_after_instruction_writing_to_watched_address() {
+        let mut uut = cpu_with_program();
+        uut.add_watchpoint(Watchpoint::at(0x0011, AccessKind::Write));
+
+        uut.execute(100);
+
+        assert_eq!(
+            uut.stop_reason(),
+            Some(StopReason::Watchpoint(WatchpointHit {
+                program_counter: 0x0002,
+                address: 0x0011,
+                kind: AccessKind::Write,
+                old_value: 0x07,
+                new_value: 0x42,
+            }))
+        );
+        assert_eq!(uut.program_counter, 0x0004);
+    }
+
+    #[test]
+    fn should_stop_on_read_of_address_within_watched_range() {
+        let mut uut = cpu_with_program();
+        uut.add_watchpoint(Watchpoint::new(0x0008, 0x000F, AccessKind::Read));
+        uut.add_watchpoint(Watchpoint::new(0x0010, 0x001F, AccessKind::Read));
+
+        uut.execute(100);
+
+        assert_eq!(
+            uut.stop_reason(),
+            Some(StopReason::Watchpoint(WatchpointHit {
+                program_counter: 0x0000,
+                address: 0x0010,
+                kind: AccessKind::Read,
+                old_value: 0x42,
+                new_value: 0x42,
+            }))
+        );
+    }
+
+    #[test]
+    fn should_filter_by_value() {
+        let mut uut = cpu_with_program();
+        uut.add_watchpoint(Watchpoint::at(0x0011, AccessKind::Write).with_value(0x43));
+
+        uut.execute(100);
+
+        match uut.stop_reason() {
+            Some(StopReason::Watchpoint(hit)) => {
+                assert_eq!(hit.program_counter, 0x0004);
+                assert_eq!(hit.old_value, 0x42);
+                assert_eq!(hit.new_value, 0x43);
+            }
+            reason => panic!("watchpoint not triggered: {reason:?}"),
+        }
+    }
+
+    #[test]
+    fn should_stop_on_instruction_fetch_but_not_on_operand_read() {
+        let mut uut = cpu_with_program();
+        uut.add_watchpoint(Watchpoint::at(0x0005, AccessKind::Fetch));
+        uut.add_watchpoint(Watchpoint::at(0x0006, AccessKind::Fetch));
+
+        uut.execute(100);
+
+        match uut.stop_reason() {
+            Some(StopReason::Watchpoint(hit)) => {
+                assert_eq!(hit.program_counter, 0x0006);
+                assert_eq!(hit.address, 0x0006);
+                assert_eq!(hit.new_value, 0xA6);
+            }
+            reason => panic!("watchpoint not triggered: {reason:?}"),
+        }
+    }
+
+    #[test]
+    fn should_not_stop_after_watchpoint_is_removed() {
+        let mut uut = cpu_with_program();
+        let watchpoint = Watchpoint::at(0x0011, AccessKind::Write);
+        uut.add_watchpoint(watchpoint);
+
+        assert!(uut.remove_watchpoint(&watchpoint));
+        uut.execute(8);
+
+        assert_eq!(uut.stop_reason(), None);
+        assert_eq!(uut.watchpoints().len(), 0);
+    }
+}
+
+#[cfg(test)]
+mod breakpoints {
+    use super::MemoryMock;
+    use crate::asm6502;
+    use crate::cpu::{
+        breakpoints::{Breakpoint, BreakpointHit, Condition},
+        watchpoints::Watchpoint,
+        StopReason, CPU,
+    };
+    use crate::memory::AccessKind;
+
+    /// Increments $10 three times, then loads it into X.
+    fn cpu_with_program() -> CPU {
+        let mut uut = CPU::new(Box::new(MemoryMock::from(
+            &asm6502! {
+                inc 0x10;
+                inc 0x10;
+                inc 0x10;
+                ldx 0x10;
+                lda 0x10;
+            }[..],
+        )));
+        uut.program_counter = 0x0000;
+
+        return uut;
+    }
+
+    fn condition(text: &str) -> Condition {
+        return Condition::parse(text).unwrap();
+    }
+
+    #[test]
+    fn should_stop_before_instruction_at_breakpoint_address() {
+        let mut uut = cpu_with_program();
+        let id = uut.add_breakpoint(Breakpoint::at(0x0004));
+
+        uut.execute(100);
+
+        assert_eq!(
+            uut.stop_reason(),
+            Some(StopReason::Breakpoint(BreakpointHit {
+                id,
+                program_counter: 0x0004,
+                hit_count: 1,
+            }))
+        );
+        assert_eq!(uut.program_counter, 0x0004);
+        assert_eq!(uut.memory.peek(0x0010), 2);
+    }
+
+    #[test]
+    fn should_continue_from_breakpoint() {
+        let mut uut = cpu_with_program();
+        uut.add_breakpoint(Breakpoint::at(0x0004));
+        uut.execute(100);
+
+        uut.execute(100);
+
+        assert_ne!(uut.program_counter, 0x0004);
+        assert!(!matches!(
+            uut.stop_reason(),
+            Some(StopReason::Breakpoint(_))
+        ));
+    }
+
+    #[test]
+    fn should_stop_before_first_instruction_at_breakpoint_address() {
+        let mut uut = cpu_with_program();
+        uut.add_breakpoint(Breakpoint::at(0x0000));
+
+        let cycles = uut.execute(100);
+
+        assert!(matches!(uut.stop_reason(), Some(StopReason::Breakpoint(_))));
+        assert_eq!(cycles, 0);
+        assert_eq!(uut.program_counter, 0x0000);
+        assert_eq!(uut.memory.peek(0x0010), 0);
+    }
+
+    #[test]
+    fn should_step_over_instruction_at_breakpoint_it_stopped_at() {
+        let mut uut = cpu_with_program();
+        uut.add_breakpoint(Breakpoint::at(0x0000));
+        uut.step();
+
+        uut.step();
+
+        assert_eq!(uut.stop_reason(), None);
+        assert_eq!(uut.program_counter, 0x0002);
+        assert_eq!(uut.memory.peek(0x0010), 1);
+    }
+
+    #[test]
+    fn should_stop_at_breakpoint_where_previous_stop_left_off() {
+        let mut uut = cpu_with_program();
+        uut.add_watchpoint(Watchpoint::at(0x0010, AccessKind::Write));
+        uut.add_breakpoint(Breakpoint::at(0x0002));
+        uut.execute(100);
+
+        uut.execute(100);
+
+        assert!(matches!(uut.stop_reason(), Some(StopReason::Breakpoint(_))));
+        assert_eq!(uut.program_counter, 0x0002);
+        assert_eq!(uut.memory.peek(0x0010), 1);
+    }
+
+    #[test]
+    fn should_stop_when_condition_holds() {
+        let mut uut = cpu_with_program();
+        uut.add_breakpoint(Breakpoint::when(condition("[$10] == 3 && x == 3")));
+
+        uut.execute(100);
+
+        assert!(matches!(uut.stop_reason(), Some(StopReason::Breakpoint(_))));
+        assert_eq!(uut.program_counter, 0x0008);
+    }
+
+    #[test]
+    fn should_skip_ignored_hits() {
+        let mut uut = cpu_with_program();
+        let id = uut.add_breakpoint(Breakpoint::when(condition("[$10] > 0")).with_ignore_count(2));
+
+        uut.execute(100);
+
+        assert_eq!(uut.program_counter, 0x0006);
+        assert_eq!(uut.breakpoints()[&id].hit_count, 3);
+    }
+
+    #[test]
+    fn should_not_stop_after_breakpoint_is_removed() {
+        let mut uut = cpu_with_program();
+        let id = uut.add_breakpoint(Breakpoint::at(0x0002));
+
+        assert!(uut.remove_breakpoint(id).is_some());
+        uut.execute(12);
+
+        assert_eq!(uut.stop_reason(), None);
+        assert!(uut.breakpoints().is_empty());
+    }
+
+    #[test]
+    fn should_count_hits_of_every_matching_breakpoint() {
+        let mut uut = cpu_with_program();
+        let first = uut.add_breakpoint(Breakpoint::at(0x0002));
+        let second = uut.add_breakpoint(Breakpoint::when(condition("pc == 2")));
+
+        uut.execute(100);
+
+        assert_eq!(
+            uut.stop_reason(),
+            Some(StopReason::Breakpoint(BreakpointHit {
+                id: first,
+                program_counter: 0x0002,
+                hit_count: 1,
+            }))
+        );
+        assert_eq!(uut.breakpoints()[&second].hit_count, 1);
+    }
+}
+
+#[cfg(test)]
+mod access_recorder {
+    use super::MemoryMock;
+    use crate::cpu::{
+        access_log::{BoundedAccessLog, BusAccess},
+        CPU,
+    };
+    use crate::memory::AccessKind;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn should_record_every_access_with_cycle_it_was_made_in() {
+        let log = Rc::new(RefCell::new(BoundedAccessLog::new(16)));
+        let mut uut = CPU::new(Box::new(MemoryMock::new(&[
+            0xA5, 0x04, // LDA $04
+            0x85, 0x05, // STA $05
+            0x42,
+        ])));
+        uut.program_counter = 0x0000;
+        uut.set_access_recorder(Some(Box::new(log.clone())));
+
+        uut.execute(6);
+
+        let accesses: Vec<BusAccess> = log.borrow().accesses().copied().collect();
+        let access = |cycle, address, value, kind| BusAccess {
+            cycle,
+            address,
+            value,
+            kind,
+        };
+        assert_eq!(
+            accesses,
+            vec![
+                access(0, 0x0000, 0xA5, AccessKind::Fetch),
+                access(1, 0x0001, 0x04, AccessKind::Read),
+                access(2, 0x0004, 0x42, AccessKind::Read),
+                access(3, 0x0002, 0x85, AccessKind::Fetch),
+                access(4, 0x0003, 0x05, AccessKind::Read),
+                access(5, 0x0005, 0x42, AccessKind::Write),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_stop_recording_when_recorder_is_removed() {
+        let log = Rc::new(RefCell::new(BoundedAccessLog::new(16)));
+        let mut uut = CPU::new(Box::new(MemoryMock::new(&[0xA5, 0x04])));
+        uut.program_counter = 0x0000;
+        uut.set_access_recorder(Some(Box::new(log.clone())));
+        uut.set_access_recorder(None);
+
+        uut.execute(3);
+
+        assert_eq!(log.borrow().len(), 0);
+    }
+}
+
+#[cfg(test)]
+mod trace {
+    use super::MemoryMock;
+    use crate::asm6502;
+    use crate::cpu::{
+        trace::{TraceFormat, Tracer},
+        CPU,
+    };
+    use crate::memory::VecMemory;
+    use crate::profiler::Profiler;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn should_trace_instructions_with_registers_before_execution() {
+        let tracer = Rc::new(RefCell::new(Tracer::new(vec![], TraceFormat::Plain)));
+        let program = asm6502! {
+            lda #0x42;
+            sta 0x10;
+            byte 0x02;
+        };
+        let mut uut = CPU::new(Box::new(MemoryMock::from(&program[..])));
+        uut.program_counter = 0x0000;
+        uut.set_tracer(Some(Box::new(tracer.clone())));
+
+        uut.execute(100);
+
+        let trace = String::from_utf8(tracer.borrow_mut().writer_mut().clone()).unwrap();
+        assert_eq!(
+            trace,
+            "0000  A9 42     LDA #$42      A=$00 X=$00 Y=$00 SP=$00 P=$00 [........] 0\n\
+             0002  85 10     STA $10       A=$42 X=$00 Y=$00 SP=$00 P=$00 [........] 2\n\
+             0004  02        .byte $02     A=$42 X=$00 Y=$00 SP=$00 P=$00 [........] 5\n"
+        );
+    }
+
+    #[test]
+    fn should_profile_subroutine_calls() {
+        let profiler = Rc::new(RefCell::new(Profiler::new()));
+        let memory = VecMemory::from(
+            &[
+                (0x0000, 0x20),
+                (0x0001, 0x04),
+                (0x0002, 0x00),
+                (0x0004, 0x60),
+                (0x0201, 0x02), // RTS returns here, see the push order of JSR
+            ][..],
+        );
+        let mut uut = CPU::new(Box::new(memory));
+        uut.program_counter = 0x0000;
+        uut.stack_pointer = 0xFD;
+        uut.set_tracer(Some(Box::new(profiler.clone())));
+
+        uut.execute(100);
+        profiler.borrow_mut().finish(uut.cycle);
+
+        let profiler = profiler.borrow();
+        let root = &profiler.profile().functions[&0x0000];
+        let subroutine = &profiler.profile().functions[&0x0004];
+        assert_eq!(root.callees[&(0x0000, 0x0004)].count, 1);
+        assert_eq!(subroutine.calls, 1);
+        assert_eq!(subroutine.exclusive, 6);
+        assert_eq!(root.inclusive, uut.cycle);
+    }
+}
+
+#[cfg(test)]
+mod step {
+    use super::MemoryMock;
+    use crate::asm6502;
+    use crate::cpu::CPU;
+
+    #[test]
+    fn should_execute_single_instruction_and_return_cycles_it_took() {
+        let program = asm6502! {
+            lda #0x42;
+            lda 0x00;
+        };
+        let mut uut = CPU::new(Box::new(MemoryMock::from(&program[..])));
+        uut.program_counter = 0x0000;
+
+        assert_eq!(uut.step(), 2);
+        assert_eq!(uut.accumulator, 0x42);
+        assert_eq!(uut.program_counter, 0x0002);
+
+        assert_eq!(uut.step(), 3);
+        assert_eq!(uut.accumulator, 0xA9);
+        assert_eq!(uut.cycle, 5);
+    }
+}
+
+#[cfg(test)]
+mod state {
+    use super::MemoryMock;
+    use crate::cpu::{CpuState, CPU};
+
+    #[test]
+    fn should_round_trip_register_values() {
+        let mut uut = CPU::new(Box::new(MemoryMock::default()));
+        let state = CpuState {
+            cycle: 100,
+            program_counter: 0x1234,
+            stack_pointer: 0xFD,
+            accumulator: 0x01,
+            index_register_x: 0x02,
+            index_register_y: 0x03,
+            processor_status: 0b10000011,
+        };
+
+        uut.set_state(state);
+
+        assert_eq!(uut.state(), state);
+        assert_eq!(uut.index_register_y, 0x03);
+    }
+}
+
+#[cfg(test)]
+mod interrupts {
+    use crate::consts::{Byte, Word};
+    use crate::cpu::CPU;
+    use crate::memory::{Bus, InterruptLine, VecMemory};
+    use std::{cell::RefCell, rc::Rc};
+
+    struct InterruptingMemory {
+        memory: VecMemory,
+        irq: bool,
+        nmi: bool,
+        ticked: u64,
+    }
+
+    impl Bus for InterruptingMemory {
+        fn read(&mut self, addr: Word) -> Byte {
+            return self.memory.read(addr);
+        }
+
+        fn write(&mut self, addr: Word, value: Byte) {
+            self.memory.write(addr, value);
+        }
+
+        fn peek(&self, addr: Word) -> Byte {
+            return self.memory.peek(addr);
+        }
+
+        fn tick(&mut self, cycles: u64) {
+            self.ticked += cycles;
+        }
+
+        fn line_asserted(&self, line: InterruptLine) -> bool {
+            return match line {
+                InterruptLine::Irq => self.irq,
+                InterruptLine::Nmi => self.nmi,
+            };
+        }
+    }
+
+    fn setup() -> (CPU, Rc<RefCell<InterruptingMemory>>) {
+        let mut memory = VecMemory::new();
+        memory.store(&[
+            (0x0200, 0xEA), // opcode without handler, never executed
+            (0xFFFA, 0x00), // NMI vector -> $0400
+            (0xFFFB, 0x04),
+            (0xFFFE, 0x00), // IRQ vector -> $0300
+            (0xFFFF, 0x03),
+            (0x0300, 0xA9), // LDA #$01
+            (0x0301, 0x01),
+            (0x0302, 0x40), // RTI
+            (0x0400, 0xA9), // LDA #$02
+            (0x0401, 0x02),
+        ]);
+        let memory = Rc::new(RefCell::new(InterruptingMemory {
+            memory,
+            irq: false,
+            nmi: false,
+            ticked: 0,
+        }));
+        let mut cpu = CPU::new(Box::new(memory.clone()));
+        cpu.program_counter = 0x0200;
+        cpu.stack_pointer = 0xFF;
+
+        return (cpu, memory);
+    }
+
+    #[test]
+    fn should_jump_through_irq_vector_pushing_return_address_and_status() {
+        let (mut uut, memory) = setup();
+        uut.processor_status.set_carry_flag(true);
+        memory.borrow_mut().irq = true;
+
+        let cycles = uut.step();
+
+        assert_eq!(cycles, 7);
+        assert_eq!(uut.program_counter, 0x0300);
+        assert_eq!(uut.stack_pointer, 0xFC);
+        assert_eq!(memory.borrow().peek(0x01FF), 0x02);
+        assert_eq!(memory.borrow().peek(0x01FE), 0x00);
+        assert_eq!(memory.borrow().peek(0x01FD), 0b00000001);
+        assert!(uut.processor_status.get_interrupt_disable_flag());
+    }
+
+    #[test]
+    fn should_ignore_irq_while_interrupts_are_disabled() {
+        let (mut uut, memory) = setup();
+        uut.program_counter = 0x0300;
+        uut.processor_status.set_interrupt_disable_flag(true);
+        memory.borrow_mut().irq = true;
+
+        uut.step();
+
+        assert_eq!(uut.program_counter, 0x0302);
+        assert_eq!(uut.accumulator, 0x01);
+    }
+
+    #[test]
+    fn should_return_from_interrupt_to_interrupted_instruction() {
+        let (mut uut, memory) = setup();
+        memory.borrow_mut().irq = true;
+        uut.step();
+        memory.borrow_mut().irq = false;
+
+        uut.step();
+        uut.step();
+
+        assert_eq!(uut.program_counter, 0x0200);
+        assert_eq!(uut.stack_pointer, 0xFF);
+        assert!(!uut.processor_status.get_interrupt_disable_flag());
+    }
+
+    #[test]
+    fn should_service_nmi_once_per_rising_edge_even_with_interrupts_disabled() {
+        let (mut uut, memory) = setup();
+        uut.processor_status.set_interrupt_disable_flag(true);
+        memory.borrow_mut().nmi = true;
+
+        uut.step();
+        assert_eq!(uut.program_counter, 0x0400);
+
+        uut.step();
+        assert_eq!(uut.program_counter, 0x0402);
+        assert_eq!(uut.accumulator, 0x02);
+    }
+
+    #[test]
+    fn should_prefer_nmi_over_irq() {
+        let (mut uut, memory) = setup();
+        memory.borrow_mut().irq = true;
+        memory.borrow_mut().nmi = true;
+
+        uut.step();
+
+        assert_eq!(uut.program_counter, 0x0400);
+    }
+
+    #[test]
+    fn should_tick_the_bus_with_cycles_of_every_step() {
+        let (mut uut, memory) = setup();
+        uut.program_counter = 0x0300;
+
+        uut.step();
+        memory.borrow_mut().irq = true;
+        uut.step();
+
+        assert_eq!(memory.borrow().ticked, 2 + 7);
+    }
+}
+
+#[cfg(test)]
+mod illegal_opcode {
+    use super::MemoryMock;
+    use crate::cpu::{access_log::BoundedAccessLog, watchpoints::Watchpoint, StopReason, CPU};
+    use crate::memory::AccessKind;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn should_stop_at_opcode_without_handler() {
+        let mut uut = CPU::new(Box::new(MemoryMock::new(&[
+            0xA9, 0x42, // LDA #$42
+            0x02, // no handler
+        ])));
+        uut.program_counter = 0x0000;
+
+        let cycle = uut.execute(100);
+
+        assert_eq!(cycle, 2);
+        assert_eq!(uut.program_counter, 0x0002);
+        assert_eq!(
+            uut.stop_reason(),
+            Some(StopReason::IllegalOpcode {
+                address: 0x0002,
+                opcode: 0x02
+            })
+        );
+    }
+    #[test]
+    fn should_not_access_the_bus_for_an_illegal_opcode() {
+        let log = Rc::new(RefCell::new(BoundedAccessLog::new(16)));
+        let mut uut = CPU::new(Box::new(MemoryMock::new(&[0x02])));
+        uut.program_counter = 0x0000;
+        uut.set_access_recorder(Some(Box::new(log.clone())));
+        uut.add_watchpoint(Watchpoint::at(0x0000, AccessKind::Fetch));
+
+        uut.execute(100);
+
+        assert!(log.borrow().is_empty());
+        assert_eq!(uut.cycle, 0);
+        assert_eq!(
+            uut.stop_reason(),
+            Some(StopReason::IllegalOpcode {
+                address: 0x0000,
+                opcode: 0x02
+            })
+        );
+    }
+}
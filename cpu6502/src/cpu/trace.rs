@@ -0,0 +1,336 @@
+//! Per-instruction execution trace.
+//!
+//! The CPU hands every instruction to an [`InstructionTracer`] right before executing it.
+//! [`Tracer`] writes them as text lines in one of the [`TraceFormat`]s, optionally limited
+//! by a [`TraceFilter`].
+
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+use crate::{
+    consts::{Byte, Word},
+    cpu::CpuState,
+    disasm::Instruction,
+    memory::Bus,
+    opcodes::Mode,
+};
+
+const INSTRUCTION_JSR: Byte = 0x20;
+const INSTRUCTION_RTS: Byte = 0x60;
+
+/// Instruction about to be executed, `state` holds the registers before execution.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceEntry {
+    pub state: CpuState,
+    pub instruction: Instruction,
+}
+
+pub trait InstructionTracer {
+    fn trace(&mut self, entry: &TraceEntry, memory: &dyn Bus);
+
+    /// Called when the CPU starts servicing an interrupt instead of executing an
+    /// instruction, `state` holds the registers before the interrupt sequence.
+    fn interrupt(&mut self, _state: &CpuState) {}
+}
+
+/// Shared handle to a tracer, so it can be inspected while the CPU owns it.
+impl<T: InstructionTracer> InstructionTracer for Rc<RefCell<T>> {
+    fn trace(&mut self, entry: &TraceEntry, memory: &dyn Bus) {
+        self.borrow_mut().trace(entry, memory);
+    }
+
+    fn interrupt(&mut self, state: &CpuState) {
+        self.borrow_mut().interrupt(state);
+    }
+}
+
+/// Hands every instruction to each of the tracers in turn.
+impl InstructionTracer for Vec<Box<dyn InstructionTracer>> {
+    fn trace(&mut self, entry: &TraceEntry, memory: &dyn Bus) {
+        for tracer in self.iter_mut() {
+            tracer.trace(entry, memory);
+        }
+    }
+
+    fn interrupt(&mut self, state: &CpuState) {
+        for tracer in self.iter_mut() {
+            tracer.interrupt(state);
+        }
+    }
+}
+
+/// Line layout of a trace.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TraceFormat {
+    /// `0200  A9 42     LDA #$42      A=$00 X=$00 Y=$00 SP=$FD P=$24 [..-..I..] 7`
+    Plain,
+    /// Layout of the nestest.log reference log, without the PPU columns:
+    /// `C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD CYC:7`
+    Nestest,
+    /// Layout of the VICE monitor `chis` command:
+    /// `.C:c000  4C F5 C5  JMP $C5F5      - A:00 X:00 Y:00 SP:fd ..-..I..          7`
+    Vice,
+}
+
+impl TraceFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        return match name.to_ascii_lowercase().as_str() {
+            "plain" => Some(TraceFormat::Plain),
+            "nestest" | "nes" => Some(TraceFormat::Nestest),
+            "vice" => Some(TraceFormat::Vice),
+            _ => None,
+        };
+    }
+
+    /// Formats one trace line, without the line terminator.
+    pub fn format(self, entry: &TraceEntry, memory: &dyn Bus) -> String {
+        let state = &entry.state;
+        let instruction = &entry.instruction;
+        let bytes: Vec<String> = instruction
+            .bytes
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect();
+        let bytes = bytes.join(" ");
+
+        return match self {
+            TraceFormat::Plain => format!(
+                "{:<30}A=${:02X} X=${:02X} Y=${:02X} SP=${:02X} P=${:02X} [{}] {}",
+                instruction.listing(),
+                state.accumulator,
+                state.index_register_x,
+                state.index_register_y,
+                state.stack_pointer,
+                state.processor_status,
+                flags(state.processor_status),
+                state.cycle
+            ),
+            TraceFormat::Nestest => format!(
+                "{:04X}  {:<8}  {:<32}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} CYC:{}",
+                state.program_counter,
+                bytes,
+                nestest_disassembly(instruction, state, memory),
+                state.accumulator,
+                state.index_register_x,
+                state.index_register_y,
+                state.processor_status,
+                state.stack_pointer,
+                state.cycle
+            ),
+            TraceFormat::Vice => format!(
+                ".C:{:04x}  {:<8}  {:<14} - A:{:02X} X:{:02X} Y:{:02X} SP:{:02x} {} {:>10}",
+                state.program_counter,
+                bytes,
+                instruction.to_string(),
+                state.accumulator,
+                state.index_register_x,
+                state.index_register_y,
+                state.stack_pointer,
+                flags(state.processor_status),
+                state.cycle
+            ),
+        };
+    }
+}
+
+/// Processor status as `NV-BDIZC`, clear flags shown as dots.
+fn flags(status: Byte) -> String {
+    return "NV-BDIZC"
+        .chars()
+        .enumerate()
+        .map(|(idx, name)| {
+            if status & (0x80 >> idx) != 0 {
+                name
+            } else {
+                '.'
+            }
+        })
+        .collect();
+}
+
+/// Reads a pointer the way the CPU does, the high byte wraps within the page.
+fn peek_pointer(memory: &dyn Bus, address: Word) -> Word {
+    let high_address = (address & 0xFF00) | (address.wrapping_add(1) & 0x00FF);
+
+    return Word::from_le_bytes([memory.peek(address), memory.peek(high_address)]);
+}
+
+/// Disassembly annotated with effective addresses and the values found there,
+/// e.g. `LDA ($80,X) @ 80 = 0200 = 5A`.
+fn nestest_disassembly(instruction: &Instruction, state: &CpuState, memory: &dyn Bus) -> String {
+    let text = instruction.to_string();
+    let Some(mode) = instruction.mode else {
+        return text;
+    };
+    let operand = |idx: usize| instruction.bytes.get(idx).copied().unwrap_or(0);
+    let word = Word::from_le_bytes([operand(1), operand(2)]);
+    let (x, y) = (state.index_register_x, state.index_register_y);
+
+    return match mode {
+        Mode::ZeroPage => format!("{text} = {:02X}", memory.peek(Word::from(operand(1)))),
+        Mode::ZeroPageX | Mode::ZeroPageY => {
+            let index = if mode == Mode::ZeroPageX { x } else { y };
+            let address = operand(1).wrapping_add(index);
+            format!(
+                "{text} @ {address:02X} = {:02X}",
+                memory.peek(Word::from(address))
+            )
+        }
+        Mode::Absolute if matches!(instruction.mnemonic, "JMP" | "JSR") => text,
+        Mode::Absolute => format!("{text} = {:02X}", memory.peek(word)),
+        Mode::AbsoluteX | Mode::AbsoluteY => {
+            let index = if mode == Mode::AbsoluteX { x } else { y };
+            let address = word.wrapping_add(Word::from(index));
+            format!("{text} @ {address:04X} = {:02X}", memory.peek(address))
+        }
+        Mode::Indirect => format!("{text} = {:04X}", peek_pointer(memory, word)),
+        Mode::IndexedIndirectX => {
+            let pointer = operand(1).wrapping_add(x);
+            let address = peek_pointer(memory, Word::from(pointer));
+            format!(
+                "{text} @ {pointer:02X} = {address:04X} = {:02X}",
+                memory.peek(address)
+            )
+        }
+        Mode::IndirectIndexedY => {
+            let base = peek_pointer(memory, Word::from(operand(1)));
+            let address = base.wrapping_add(Word::from(y));
+            format!(
+                "{text} = {base:04X} @ {address:04X} = {:02X}",
+                memory.peek(address)
+            )
+        }
+        Mode::Implied | Mode::Accumulator | Mode::Immediate | Mode::Relative => text,
+    };
+}
+
+/// Limits which instructions are traced, all given limits have to match.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TraceFilter {
+    /// Inclusive program counter ranges, any of them matches; empty traces everywhere.
+    pub ranges: Vec<(Word, Word)>,
+    /// Inclusive window of the cycle counter.
+    pub cycles: Option<(u64, u64)>,
+    /// Only trace from entering the subroutine at this address until its `RTS`,
+    /// subroutines it calls included.
+    pub subroutine: Option<Word>,
+}
+
+impl TraceFilter {
+    pub fn new() -> Self {
+        return TraceFilter::default();
+    }
+
+    pub fn in_range(mut self, start: Word, end: Word) -> Self {
+        self.ranges.push((start, end));
+        return self;
+    }
+
+    pub fn during(mut self, first_cycle: u64, last_cycle: u64) -> Self {
+        self.cycles = Some((first_cycle, last_cycle));
+        return self;
+    }
+
+    pub fn in_subroutine(mut self, address: Word) -> Self {
+        self.subroutine = Some(address);
+        return self;
+    }
+
+    fn matches(&self, state: &CpuState) -> bool {
+        let pc = state.program_counter;
+        let in_range = self.ranges.is_empty()
+            || self
+                .ranges
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&pc));
+        let in_window = match self.cycles {
+            Some((first, last)) => (first..=last).contains(&state.cycle),
+            None => true,
+        };
+
+        return in_range && in_window;
+    }
+}
+
+/// Writes trace lines to `writer`.
+///
+/// The first write error stops tracing and is kept for [`Tracer::take_error`],
+/// execution is not interrupted by it.
+pub struct Tracer<W: Write> {
+    writer: W,
+    format: TraceFormat,
+    filter: TraceFilter,
+    /// Call depth inside the filtered subroutine, `None` while outside of it.
+    subroutine_depth: Option<u32>,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> Tracer<W> {
+    pub fn new(writer: W, format: TraceFormat) -> Self {
+        return Tracer {
+            writer,
+            format,
+            filter: TraceFilter::new(),
+            subroutine_depth: None,
+            error: None,
+        };
+    }
+
+    pub fn with_filter(mut self, filter: TraceFilter) -> Self {
+        self.filter = filter;
+        return self;
+    }
+
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        return self.error.take();
+    }
+
+    pub fn writer_mut(&mut self) -> &mut W {
+        return &mut self.writer;
+    }
+
+    pub fn into_inner(self) -> W {
+        return self.writer;
+    }
+
+    /// Tracks entering and leaving the filtered subroutine, returns whether
+    /// `entry` is inside of it.
+    fn in_subroutine(&mut self, entry: &TraceEntry) -> bool {
+        let Some(subroutine) = self.filter.subroutine else {
+            return true;
+        };
+        if self.subroutine_depth.is_none() && entry.state.program_counter == subroutine {
+            self.subroutine_depth = Some(0);
+        }
+        let Some(depth) = self.subroutine_depth else {
+            return false;
+        };
+
+        self.subroutine_depth = match entry.instruction.opcode {
+            INSTRUCTION_JSR => Some(depth + 1),
+            INSTRUCTION_RTS => depth.checked_sub(1),
+            _ => Some(depth),
+        };
+        return true;
+    }
+}
+
+impl<W: Write> InstructionTracer for Tracer<W> {
+    fn trace(&mut self, entry: &TraceEntry, memory: &dyn Bus) {
+        let in_subroutine = self.in_subroutine(entry);
+        if self.error.is_some() || !in_subroutine || !self.filter.matches(&entry.state) {
+            return;
+        }
+
+        let line = self.format.format(entry, memory);
+        if let Err(err) = writeln!(self.writer, "{line}") {
+            self.error = Some(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,187 @@
+use crate::{
+    consts::{Byte, Word},
+    cpu::{trace::TraceEntry, CpuState},
+    disasm::decode_bytes,
+};
+
+fn entry(program_counter: Word, bytes: &[Byte], cycle: u64) -> TraceEntry {
+    return TraceEntry {
+        state: CpuState {
+            cycle,
+            program_counter,
+            stack_pointer: 0xFD,
+            accumulator: 0x00,
+            index_register_x: 0x00,
+            index_register_y: 0x00,
+            processor_status: 0x24,
+        },
+        instruction: decode_bytes(bytes, program_counter),
+    };
+}
+
+#[cfg(test)]
+mod format {
+    use super::entry;
+    use crate::cpu::trace::TraceFormat;
+    use crate::memory::VecMemory;
+
+    #[test]
+    fn should_format_plain_lines() {
+        let memory = VecMemory::new();
+
+        assert_eq!(
+            TraceFormat::Plain.format(&entry(0xC000, &[0x4C, 0xF5, 0xC5], 7), &memory),
+            "C000  4C F5 C5  JMP $C5F5     A=$00 X=$00 Y=$00 SP=$FD P=$24 [..-..I..] 7"
+        );
+    }
+
+    #[test]
+    fn should_format_nestest_lines() {
+        let memory = VecMemory::new();
+
+        assert_eq!(
+            TraceFormat::Nestest.format(&entry(0xC000, &[0x4C, 0xF5, 0xC5], 7), &memory),
+            "C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD CYC:7"
+        );
+    }
+
+    #[test]
+    fn should_annotate_nestest_operands_with_effective_addresses() {
+        let memory = VecMemory::from(
+            &[
+                (0x0010, 0x33),
+                (0x0080, 0x00),
+                (0x0081, 0x02),
+                (0x0089, 0x00),
+                (0x008A, 0x03),
+                (0x0200, 0x5A),
+                (0x0300, 0x89),
+            ][..],
+        );
+        let format = |bytes: &[u8]| TraceFormat::Nestest.format(&entry(0xC000, bytes, 0), &memory);
+
+        assert!(format(&[0xA5, 0x10]).contains("LDA $10 = 33 "));
+        assert!(format(&[0xB5, 0x10]).contains("LDA $10,X @ 10 = 33 "));
+        assert!(format(&[0xAD, 0x00, 0x03]).contains("LDA $0300 = 89 "));
+        assert!(format(&[0xBD, 0x00, 0x03]).contains("LDA $0300,X @ 0300 = 89 "));
+        assert!(format(&[0xA1, 0x80]).contains("LDA ($80,X) @ 80 = 0200 = 5A "));
+        assert!(format(&[0xB1, 0x89]).contains("LDA ($89),Y = 0300 @ 0300 = 89 "));
+        assert!(format(&[0x6C, 0x80, 0x00]).contains("JMP ($0080) = 0200 "));
+        assert!(format(&[0x20, 0x00, 0x03]).contains("JSR $0300 "));
+        assert!(format(&[0xA9, 0x10]).contains("LDA #$10 "));
+    }
+
+    #[test]
+    fn should_format_vice_lines() {
+        let memory = VecMemory::new();
+
+        assert_eq!(
+            TraceFormat::Vice.format(&entry(0xC000, &[0x4C, 0xF5, 0xC5], 7), &memory),
+            ".C:c000  4C F5 C5  JMP $C5F5      - A:00 X:00 Y:00 SP:fd ..-..I..          7"
+        );
+    }
+
+    #[test]
+    fn should_find_formats_by_name() {
+        assert_eq!(
+            TraceFormat::from_name("NESTEST"),
+            Some(TraceFormat::Nestest)
+        );
+        assert_eq!(TraceFormat::from_name("vice"), Some(TraceFormat::Vice));
+        assert_eq!(TraceFormat::from_name("plain"), Some(TraceFormat::Plain));
+        assert_eq!(TraceFormat::from_name("mame"), None);
+    }
+}
+
+#[cfg(test)]
+mod tracer {
+    use super::entry;
+    use crate::cpu::trace::{InstructionTracer, TraceEntry, TraceFilter, TraceFormat, Tracer};
+    use crate::memory::VecMemory;
+
+    const NOP: &[u8] = &[0xEA];
+
+    fn traced_addresses(filter: TraceFilter, entries: &[TraceEntry]) -> Vec<String> {
+        let memory = VecMemory::new();
+        let mut uut = Tracer::new(vec![], TraceFormat::Plain).with_filter(filter);
+        for entry in entries {
+            uut.trace(entry, &memory);
+        }
+
+        return String::from_utf8(uut.into_inner())
+            .unwrap()
+            .lines()
+            .map(|line| line[..4].to_string())
+            .collect();
+    }
+
+    #[test]
+    fn should_filter_by_address_ranges() {
+        let entries = [
+            entry(0x0200, NOP, 0),
+            entry(0x0300, NOP, 2),
+            entry(0x0400, NOP, 4),
+        ];
+
+        let traced = traced_addresses(
+            TraceFilter::new()
+                .in_range(0x0200, 0x0200)
+                .in_range(0x0400, 0x04FF),
+            &entries,
+        );
+
+        assert_eq!(traced, vec!["0200", "0400"]);
+    }
+
+    #[test]
+    fn should_filter_by_cycle_window() {
+        let entries = [
+            entry(0x0200, NOP, 0),
+            entry(0x0201, NOP, 2),
+            entry(0x0202, NOP, 4),
+            entry(0x0203, NOP, 6),
+        ];
+
+        let traced = traced_addresses(TraceFilter::new().during(2, 4), &entries);
+
+        assert_eq!(traced, vec!["0201", "0202"]);
+    }
+
+    #[test]
+    fn should_trace_subroutine_and_its_callees_only() {
+        let jsr = |target: u16| {
+            let [lo, hi] = target.to_le_bytes();
+            [0x20, lo, hi]
+        };
+        let entries = [
+            entry(0x0200, &jsr(0x0300), 0),
+            entry(0x0300, &jsr(0x0400), 6),
+            entry(0x0400, &[0x60], 12),
+            entry(0x0303, &[0x60], 18),
+            entry(0x0203, &jsr(0x0400), 24),
+            entry(0x0400, &[0x60], 30),
+            entry(0x0206, &jsr(0x0300), 36),
+            entry(0x0300, NOP, 42),
+        ];
+
+        let traced = traced_addresses(TraceFilter::new().in_subroutine(0x0300), &entries);
+
+        assert_eq!(traced, vec!["0300", "0400", "0303", "0300"]);
+    }
+
+    #[test]
+    fn should_keep_first_write_error_and_stop_writing() {
+        let memory = VecMemory::new();
+        let mut buffer = [0u8; 16];
+        let mut uut = Tracer::new(&mut buffer[..], TraceFormat::Plain);
+
+        uut.trace(&entry(0x0200, NOP, 0), &memory);
+        uut.trace(&entry(0x0201, NOP, 2), &memory);
+
+        assert_eq!(
+            uut.take_error().map(|err| err.kind()),
+            Some(std::io::ErrorKind::WriteZero)
+        );
+        assert!(uut.take_error().is_none());
+    }
+}
@@ -0,0 +1,61 @@
+use crate::{
+    consts::{Byte, Word},
+    memory::AccessKind,
+};
+
+/// Stops execution when an address in `start..=end` is accessed in the given way.
+///
+/// `Fetch` watches only opcode fetches, operand bytes are reported as `Read`.
+/// When `value` is set, only accesses transferring that value trigger the watchpoint
+/// (the value written for writes, the value read for reads and fetches).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Watchpoint {
+    pub start: Word,
+    pub end: Word,
+    pub kind: AccessKind,
+    pub value: Option<Byte>,
+}
+
+impl Watchpoint {
+    pub fn new(start: Word, end: Word, kind: AccessKind) -> Self {
+        return Watchpoint {
+            start,
+            end,
+            kind,
+            value: None,
+        };
+    }
+
+    pub fn at(address: Word, kind: AccessKind) -> Self {
+        return Watchpoint::new(address, address, kind);
+    }
+
+    pub fn with_value(mut self, value: Byte) -> Self {
+        self.value = Some(value);
+        return self;
+    }
+
+    pub fn matches(&self, address: Word, kind: AccessKind, value: Byte) -> bool {
+        if kind != self.kind || address < self.start || address > self.end {
+            return false;
+        }
+
+        return match self.value {
+            Some(expected) => expected == value,
+            None => true,
+        };
+    }
+}
+
+/// Access which triggered a watchpoint.
+///
+/// `program_counter` is the address of the instruction performing the access.
+/// For reads and fetches `old_value` and `new_value` are both the value read.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WatchpointHit {
+    pub program_counter: Word,
+    pub address: Word,
+    pub kind: AccessKind,
+    pub old_value: Byte,
+    pub new_value: Byte,
+}
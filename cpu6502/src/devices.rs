@@ -0,0 +1,4 @@
+//! Peripherals which can be mapped into a machine next to its memory.
+
+pub mod console;
+pub mod timer;
@@ -0,0 +1,60 @@
+use std::io::{self, Write};
+
+use crate::{
+    consts::{Byte, Word},
+    memory::Bus,
+};
+
+/// Output-only character device: every byte written to any of its addresses
+/// is passed to the writer. Reads return 0.
+///
+/// Errors of the writer don't stop the program, the first one is kept and
+/// can be retrieved with `take_error`.
+pub struct Console<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> Console<W> {
+    pub fn new(writer: W) -> Self {
+        return Console {
+            writer,
+            error: None,
+        };
+    }
+
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        return self.error.take();
+    }
+
+    pub fn writer(&self) -> &W {
+        return &self.writer;
+    }
+
+    pub fn into_inner(self) -> W {
+        return self.writer;
+    }
+}
+
+impl<W: Write> Bus for Console<W> {
+    fn read(&mut self, _addr: Word) -> Byte {
+        return 0;
+    }
+
+    fn write(&mut self, _addr: Word, value: Byte) {
+        let result = self
+            .writer
+            .write_all(&[value])
+            .and_then(|_| self.writer.flush());
+        if let Err(err) = result {
+            self.error.get_or_insert(err);
+        }
+    }
+
+    fn peek(&self, _addr: Word) -> Byte {
+        return 0;
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,23 @@
+#[cfg(test)]
+mod write {
+    use crate::devices::console::Console;
+    use crate::memory::Bus;
+
+    #[test]
+    fn should_pass_written_bytes_to_the_writer() {
+        let mut uut = Console::new(vec![]);
+
+        uut.write(0x0000, b'h');
+        uut.write(0x0001, b'i');
+
+        assert_eq!(uut.into_inner(), b"hi".to_vec());
+    }
+
+    #[test]
+    fn should_read_as_zero() {
+        let mut uut = Console::new(vec![]);
+        uut.write(0x0000, b'h');
+
+        assert_eq!(uut.read(0x0000), 0x00);
+    }
+}
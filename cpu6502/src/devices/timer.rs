@@ -0,0 +1,118 @@
+use crate::{
+    consts::{Byte, Word},
+    memory::Bus,
+};
+
+pub const REGISTER_RELOAD_LO: Word = 0;
+pub const REGISTER_RELOAD_HI: Word = 1;
+pub const REGISTER_CONTROL: Word = 2;
+pub const REGISTER_STATUS: Word = 3;
+
+pub const CONTROL_ENABLE: Byte = 0b0000_0001;
+pub const CONTROL_IRQ_ENABLE: Byte = 0b0000_0010;
+pub const STATUS_EXPIRED: Byte = 0b1000_0000;
+
+/// Interval timer counting CPU cycles, occupying four registers.
+///
+/// The timer expires every `reload` cycles (a reload value of 0 stands for
+/// 65536) while enabled. Expiry sets bit 7 of the status register, which stays
+/// set until the status register is read, and asserts the interrupt output
+/// for as long as the flag is set and interrupts are enabled in the control
+/// register. Writing the high byte of the reload value restarts the countdown.
+pub struct Timer {
+    reload: Word,
+    remaining: u64,
+    control: Byte,
+    expired: bool,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        return Timer {
+            reload: 0,
+            remaining: period(0),
+            control: 0,
+            expired: false,
+        };
+    }
+
+    pub fn with_reload(mut self, reload: Word) -> Self {
+        self.reload = reload;
+        self.remaining = period(reload);
+        return self;
+    }
+
+    pub fn reload(&self) -> Word {
+        return self.reload;
+    }
+
+    fn status(&self) -> Byte {
+        return if self.expired { STATUS_EXPIRED } else { 0 };
+    }
+}
+
+fn period(reload: Word) -> u64 {
+    return if reload == 0 { 0x10000 } else { reload.into() };
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        return Timer::new();
+    }
+}
+
+impl Bus for Timer {
+    fn read(&mut self, addr: Word) -> Byte {
+        let value = self.peek(addr);
+        if addr & 0x03 == REGISTER_STATUS {
+            self.expired = false;
+        }
+
+        return value;
+    }
+
+    fn write(&mut self, addr: Word, value: Byte) {
+        let [lo, hi] = self.reload.to_le_bytes();
+        match addr & 0x03 {
+            REGISTER_RELOAD_LO => self.reload = Word::from_le_bytes([value, hi]),
+            REGISTER_RELOAD_HI => {
+                self.reload = Word::from_le_bytes([lo, value]);
+                self.remaining = period(self.reload);
+            }
+            REGISTER_CONTROL => self.control = value,
+            _ => (),
+        }
+    }
+
+    fn peek(&self, addr: Word) -> Byte {
+        let [lo, hi] = self.reload.to_le_bytes();
+        return match addr & 0x03 {
+            REGISTER_RELOAD_LO => lo,
+            REGISTER_RELOAD_HI => hi,
+            REGISTER_CONTROL => self.control,
+            _ => self.status(),
+        };
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        if self.control & CONTROL_ENABLE == 0 {
+            return;
+        }
+
+        if cycles < self.remaining {
+            self.remaining -= cycles;
+            return;
+        }
+
+        let period = period(self.reload);
+        self.remaining = period - (cycles - self.remaining) % period;
+        self.expired = true;
+    }
+
+    fn interrupt_asserted(&self) -> bool {
+        return self.expired && self.control & CONTROL_IRQ_ENABLE != 0;
+    }
+}
+
+#[cfg(test)]
+mod tests;
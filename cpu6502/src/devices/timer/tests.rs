@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod registers {
+    use crate::devices::timer::{Timer, REGISTER_CONTROL, REGISTER_RELOAD_HI, REGISTER_RELOAD_LO};
+    use crate::memory::Bus;
+
+    #[test]
+    fn should_assemble_reload_value_from_both_registers() {
+        let mut uut = Timer::new();
+
+        uut.write(REGISTER_RELOAD_LO, 0x34);
+        uut.write(REGISTER_RELOAD_HI, 0x12);
+
+        assert_eq!(uut.reload(), 0x1234);
+        assert_eq!(uut.read(REGISTER_RELOAD_LO), 0x34);
+        assert_eq!(uut.read(REGISTER_CONTROL), 0x00);
+    }
+
+    #[test]
+    fn should_mirror_registers_every_four_bytes() {
+        let mut uut = Timer::new();
+
+        uut.write(0x0006, 0x03);
+
+        assert_eq!(uut.read(REGISTER_CONTROL), 0x03);
+    }
+}
+
+#[cfg(test)]
+mod countdown {
+    use crate::devices::timer::{
+        Timer, CONTROL_ENABLE, CONTROL_IRQ_ENABLE, REGISTER_CONTROL, REGISTER_STATUS,
+        STATUS_EXPIRED,
+    };
+    use crate::memory::Bus;
+
+    #[test]
+    fn should_not_count_while_disabled() {
+        let mut uut = Timer::new().with_reload(10);
+
+        uut.tick(100);
+
+        assert_eq!(uut.peek(REGISTER_STATUS), 0x00);
+    }
+
+    #[test]
+    fn should_set_status_flag_once_reload_cycles_pass() {
+        let mut uut = Timer::new().with_reload(10);
+        uut.write(REGISTER_CONTROL, CONTROL_ENABLE);
+
+        uut.tick(9);
+        assert_eq!(uut.peek(REGISTER_STATUS), 0x00);
+
+        uut.tick(1);
+        assert_eq!(uut.peek(REGISTER_STATUS), STATUS_EXPIRED);
+    }
+
+    #[test]
+    fn should_clear_status_flag_on_read_but_not_on_peek() {
+        let mut uut = Timer::new().with_reload(10);
+        uut.write(REGISTER_CONTROL, CONTROL_ENABLE);
+        uut.tick(10);
+
+        assert_eq!(uut.peek(REGISTER_STATUS), STATUS_EXPIRED);
+        assert_eq!(uut.read(REGISTER_STATUS), STATUS_EXPIRED);
+        assert_eq!(uut.read(REGISTER_STATUS), 0x00);
+    }
+
+    #[test]
+    fn should_keep_counting_periods_after_expiry() {
+        let mut uut = Timer::new().with_reload(10);
+        uut.write(REGISTER_CONTROL, CONTROL_ENABLE);
+        uut.tick(13);
+        uut.read(REGISTER_STATUS);
+
+        uut.tick(6);
+        assert_eq!(uut.peek(REGISTER_STATUS), 0x00);
+
+        uut.tick(1);
+        assert_eq!(uut.peek(REGISTER_STATUS), STATUS_EXPIRED);
+    }
+
+    #[test]
+    fn should_assert_interrupt_only_when_enabled() {
+        let mut uut = Timer::new().with_reload(10);
+        uut.write(REGISTER_CONTROL, CONTROL_ENABLE);
+        uut.tick(10);
+        assert!(!uut.interrupt_asserted());
+
+        uut.write(REGISTER_CONTROL, CONTROL_ENABLE | CONTROL_IRQ_ENABLE);
+        assert!(uut.interrupt_asserted());
+
+        uut.read(REGISTER_STATUS);
+        assert!(!uut.interrupt_asserted());
+    }
+}
@@ -0,0 +1,175 @@
+//! Decodes machine code into instructions in standard 6502 assembly syntax.
+
+use std::fmt;
+
+use crate::{
+    consts::{Byte, Word},
+    memory::Bus,
+    opcodes::{self, Mode},
+};
+
+/// Mnemonic used for bytes that do not start a documented instruction.
+pub const DATA_MNEMONIC: &str = ".byte";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instruction {
+    pub address: Word,
+    pub opcode: Byte,
+    pub mnemonic: &'static str,
+    /// `None` for illegal opcodes.
+    pub mode: Option<Mode>,
+    pub operand: String,
+    /// Raw bytes of the instruction, opcode first.
+    pub bytes: Vec<Byte>,
+    /// Absolute address a branch or jump leads to, indirect jumps excluded.
+    pub target: Option<Word>,
+}
+
+impl Instruction {
+    pub fn len(&self) -> usize {
+        return self.bytes.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.bytes.is_empty();
+    }
+
+    pub fn is_illegal(&self) -> bool {
+        return self.mode.is_none();
+    }
+
+    /// Address of the instruction following this one.
+    pub fn next_address(&self) -> Word {
+        return self.address.wrapping_add(self.len() as Word);
+    }
+
+    /// Formats the instruction as `0200  B1 AB     LDA ($AB),Y`.
+    pub fn listing(&self) -> String {
+        let bytes: Vec<String> = self
+            .bytes
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect();
+        return format!("{:04X}  {:<8}  {}", self.address, bytes.join(" "), self);
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.operand.is_empty() {
+            return write!(f, "{}", self.mnemonic);
+        }
+
+        return write!(f, "{} {}", self.mnemonic, self.operand);
+    }
+}
+
+/// Decodes the instruction at `address`. Memory is read with [`Bus::peek`],
+/// so devices are not disturbed.
+pub fn decode(memory: &dyn Bus, address: Word) -> Instruction {
+    let bytes: Vec<Byte> = (0..3)
+        .map(|offset| memory.peek(address.wrapping_add(offset)))
+        .collect();
+
+    return decode_bytes(&bytes, address);
+}
+
+/// Decodes the instruction at the start of `bytes`, which are located at `address`.
+/// An instruction cut short by the end of the slice is shown as data.
+pub fn decode_bytes(bytes: &[Byte], address: Word) -> Instruction {
+    let opcode = bytes.first().copied().unwrap_or(0);
+    let data = Instruction {
+        address,
+        opcode,
+        mnemonic: DATA_MNEMONIC,
+        mode: None,
+        operand: format!("${opcode:02X}"),
+        bytes: vec![opcode],
+        target: None,
+    };
+
+    let info = match opcodes::lookup(opcode) {
+        Some(info) if info.size() <= bytes.len() => info,
+        _ => return data,
+    };
+
+    let operand_bytes = &bytes[1..info.size()];
+    let byte = operand_bytes.first().copied().unwrap_or(0);
+    let word = match operand_bytes {
+        [lo, hi] => Word::from_le_bytes([*lo, *hi]),
+        _ => Word::from(byte),
+    };
+
+    let mut target = None;
+    let operand = match info.mode {
+        Mode::Implied => String::new(),
+        Mode::Accumulator => "A".to_string(),
+        Mode::Immediate => format!("#${byte:02X}"),
+        Mode::ZeroPage => format!("${byte:02X}"),
+        Mode::ZeroPageX => format!("${byte:02X},X"),
+        Mode::ZeroPageY => format!("${byte:02X},Y"),
+        Mode::Absolute => {
+            if matches!(info.mnemonic, "JMP" | "JSR") {
+                target = Some(word);
+            }
+            format!("${word:04X}")
+        }
+        Mode::AbsoluteX => format!("${word:04X},X"),
+        Mode::AbsoluteY => format!("${word:04X},Y"),
+        Mode::Indirect => format!("(${word:04X})"),
+        Mode::IndexedIndirectX => format!("(${byte:02X},X)"),
+        Mode::IndirectIndexedY => format!("(${byte:02X}),Y"),
+        Mode::Relative => {
+            let destination = branch_target(address, byte);
+            target = Some(destination);
+            format!("${destination:04X}")
+        }
+    };
+
+    return Instruction {
+        address,
+        opcode,
+        mnemonic: info.mnemonic,
+        mode: Some(info.mode),
+        operand,
+        bytes: bytes[..info.size()].to_vec(),
+        target,
+    };
+}
+
+/// Destination of a branch at `address` with the given signed offset.
+pub fn branch_target(address: Word, offset: Byte) -> Word {
+    return address
+        .wrapping_add(2)
+        .wrapping_add_signed(i16::from(offset as i8));
+}
+
+/// Decodes `count` consecutive instructions starting at `address`.
+pub fn disassemble(memory: &dyn Bus, address: Word, count: usize) -> Vec<Instruction> {
+    let mut instructions = Vec::with_capacity(count);
+    let mut address = address;
+    for _ in 0..count {
+        let instruction = decode(memory, address);
+        address = instruction.next_address();
+        instructions.push(instruction);
+    }
+
+    return instructions;
+}
+
+/// Decodes the whole slice, which is located at `origin`.
+pub fn disassemble_bytes(bytes: &[Byte], origin: Word) -> Vec<Instruction> {
+    let mut instructions = vec![];
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let address = origin.wrapping_add(offset as Word);
+        let instruction = decode_bytes(&bytes[offset..], address);
+        offset += instruction.len();
+        instructions.push(instruction);
+    }
+
+    return instructions;
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod decode {
+    use crate::{
+        disasm::{decode, decode_bytes},
+        memory::VecMemory,
+        opcodes::{Mode, OPCODES},
+    };
+
+    #[test]
+    fn should_format_operands_in_standard_syntax() {
+        let cases: [(&[u8], &str); 13] = [
+            (&[0xEA], "NOP"),
+            (&[0x0A], "ASL A"),
+            (&[0xA9, 0x42], "LDA #$42"),
+            (&[0xA5, 0xAB], "LDA $AB"),
+            (&[0xB5, 0xAB], "LDA $AB,X"),
+            (&[0xB6, 0xAB], "LDX $AB,Y"),
+            (&[0xAD, 0x34, 0x12], "LDA $1234"),
+            (&[0xBD, 0x34, 0x12], "LDA $1234,X"),
+            (&[0xB9, 0x34, 0x12], "LDA $1234,Y"),
+            (&[0x6C, 0x34, 0x12], "JMP ($1234)"),
+            (&[0xA1, 0xAB], "LDA ($AB,X)"),
+            (&[0xB1, 0xAB], "LDA ($AB),Y"),
+            (&[0x20, 0x00, 0x80], "JSR $8000"),
+        ];
+
+        for (bytes, expected) in cases {
+            assert_eq!(decode_bytes(bytes, 0x0200).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn should_resolve_branch_targets_to_absolute_addresses() {
+        let forward = decode_bytes(&[0xD0, 0x05], 0x0200);
+        let backward = decode_bytes(&[0xF0, 0xFC], 0x0200);
+
+        assert_eq!(forward.to_string(), "BNE $0207");
+        assert_eq!(forward.target, Some(0x0207));
+        assert_eq!(backward.to_string(), "BEQ $01FE");
+        assert_eq!(backward.mode, Some(Mode::Relative));
+    }
+
+    #[test]
+    fn should_report_length_and_raw_bytes() {
+        let instruction = decode_bytes(&[0x8D, 0x00, 0x02, 0xFF], 0x0300);
+
+        assert_eq!(instruction.len(), 3);
+        assert_eq!(instruction.bytes, vec![0x8D, 0x00, 0x02]);
+        assert_eq!(instruction.next_address(), 0x0303);
+        assert_eq!(instruction.listing(), "0300  8D 00 02  STA $0200");
+    }
+
+    #[test]
+    fn should_print_illegal_opcodes_as_data() {
+        let instruction = decode_bytes(&[0x02, 0xEA], 0x0200);
+
+        assert!(instruction.is_illegal());
+        assert_eq!(instruction.len(), 1);
+        assert_eq!(instruction.to_string(), ".byte $02");
+    }
+
+    #[test]
+    fn should_print_truncated_instructions_as_data() {
+        let instruction = decode_bytes(&[0xAD, 0x34], 0x0200);
+
+        assert_eq!(instruction.to_string(), ".byte $AD");
+        assert_eq!(instruction.len(), 1);
+    }
+
+    #[test]
+    fn should_decode_every_documented_opcode() {
+        for entry in OPCODES {
+            let instruction = decode_bytes(&[entry.opcode, 0x10, 0x20], 0x0400);
+
+            assert_eq!(instruction.mnemonic, entry.mnemonic);
+            assert_eq!(instruction.len(), entry.size());
+        }
+    }
+
+    #[test]
+    fn should_decode_from_memory() {
+        let memory = VecMemory::from(&[(0xFFFF, 0x4C), (0x0000, 0x34), (0x0001, 0x12)][..]);
+
+        let instruction = decode(&memory, 0xFFFF);
+
+        assert_eq!(instruction.to_string(), "JMP $1234");
+        assert_eq!(instruction.target, Some(0x1234));
+    }
+}
+
+#[cfg(test)]
+mod disassemble {
+    use crate::{
+        disasm::{disassemble, disassemble_bytes},
+        memory::VecMemory,
+    };
+
+    #[test]
+    fn should_disassemble_consecutive_instructions() {
+        let memory = VecMemory::from(
+            &[
+                (0x0200, 0xA9),
+                (0x0201, 0x01),
+                (0x0202, 0x8D),
+                (0x0203, 0x00),
+                (0x0204, 0x01),
+                (0x0205, 0x60),
+            ][..],
+        );
+
+        let lines: Vec<String> = disassemble(&memory, 0x0200, 3)
+            .iter()
+            .map(|instruction| instruction.to_string())
+            .collect();
+
+        assert_eq!(lines, vec!["LDA #$01", "STA $0100", "RTS"]);
+    }
+
+    #[test]
+    fn should_disassemble_whole_slice() {
+        let instructions = disassemble_bytes(&[0xE8, 0xFF, 0xA2], 0xC000);
+
+        let addresses: Vec<u16> = instructions.iter().map(|i| i.address).collect();
+
+        assert_eq!(addresses, vec![0xC000, 0xC001, 0xC002]);
+        assert_eq!(instructions[2].to_string(), ".byte $A2");
+    }
+}
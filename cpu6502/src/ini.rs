@@ -0,0 +1,137 @@
+//! INI-like text files: `key = value` pairs grouped in `[sections]`, with
+//! `#` or `;` starting a comment line or, after whitespace, a trailing comment.
+
+use crate::consts::Word;
+
+/// Error at `line`, counted from 1.
+#[derive(Debug, PartialEq)]
+pub(crate) struct SyntaxError {
+    pub line: usize,
+    pub message: String,
+}
+
+pub(crate) fn syntax_error(line: usize, message: String) -> SyntaxError {
+    return SyntaxError { line, message };
+}
+
+pub(crate) struct Section {
+    pub name: String,
+    pub line: usize,
+    pub entries: Vec<(usize, String, String)>,
+}
+
+impl Section {
+    pub fn take(&mut self, key: &str) -> Option<(usize, String)> {
+        let idx = self.entries.iter().position(|(_, k, _)| k == key)?;
+        let (line, _, value) = self.entries.remove(idx);
+
+        return Some((line, value));
+    }
+
+    pub fn take_required(&mut self, key: &str) -> Result<(usize, String), SyntaxError> {
+        let line = self.line;
+        let name = self.name.clone();
+        return self
+            .take(key)
+            .ok_or_else(|| syntax_error(line, format!("[{name}] section is missing \"{key}\"")));
+    }
+
+    pub fn take_address(&mut self, key: &str) -> Result<Word, SyntaxError> {
+        let (line, value) = self.take_required(key)?;
+        return parse_address(line, &value);
+    }
+
+    pub fn reject_remaining(&self) -> Result<(), SyntaxError> {
+        return match self.entries.first() {
+            Some((line, key, _)) => Err(syntax_error(
+                *line,
+                format!("unknown key \"{key}\" in [{}] section", self.name),
+            )),
+            None => Ok(()),
+        };
+    }
+}
+
+pub(crate) fn split_sections(text: &str) -> Result<Vec<Section>, SyntaxError> {
+    let mut sections: Vec<Section> = vec![];
+    for (idx, raw_line) in text.lines().enumerate() {
+        let line = idx + 1;
+        let content = raw_line.trim();
+        if content.is_empty() || content.starts_with('#') || content.starts_with(';') {
+            continue;
+        }
+
+        if let Some(header) = content.strip_prefix('[') {
+            let name = header
+                .strip_suffix(']')
+                .ok_or_else(|| syntax_error(line, "unterminated section header".to_string()))?;
+            sections.push(Section {
+                name: name.trim().to_ascii_lowercase(),
+                line,
+                entries: vec![],
+            });
+            continue;
+        }
+
+        let (key, value) = content
+            .split_once('=')
+            .ok_or_else(|| syntax_error(line, "expected \"key = value\"".to_string()))?;
+        let key = key.trim().to_ascii_lowercase();
+        let value = strip_comment(value).to_string();
+        let section = sections
+            .last_mut()
+            .ok_or_else(|| syntax_error(line, format!("\"{key}\" outside of a section")))?;
+        if section
+            .entries
+            .iter()
+            .any(|(_, existing, _)| *existing == key)
+        {
+            return Err(syntax_error(line, format!("\"{key}\" repeated")));
+        }
+        section.entries.push((line, key, value));
+    }
+
+    return Ok(sections);
+}
+
+/// Drops a trailing comment, which has to be separated from the value by whitespace.
+fn strip_comment(value: &str) -> &str {
+    let mut end = value.len();
+    for marker in [" #", "\t#", " ;", "\t;"] {
+        if let Some(idx) = value.find(marker) {
+            end = end.min(idx);
+        }
+    }
+
+    return value[..end].trim();
+}
+
+/// Parses a decimal number or a hexadecimal one prefixed with `$` or `0x`.
+pub fn parse_number(text: &str) -> Option<u32> {
+    let text = text.trim();
+    if let Some(hex) = text.strip_prefix('$') {
+        return u32::from_str_radix(hex, 16).ok();
+    }
+    if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        return u32::from_str_radix(hex, 16).ok();
+    }
+
+    return text.parse().ok();
+}
+
+pub(crate) fn parse_address(line: usize, text: &str) -> Result<Word, SyntaxError> {
+    return parse_number(text)
+        .and_then(|value| Word::try_from(value).ok())
+        .ok_or_else(|| syntax_error(line, format!("invalid address \"{text}\"")));
+}
+
+pub(crate) fn parse_bool(line: usize, text: &str) -> Result<bool, SyntaxError> {
+    return match text.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(syntax_error(
+            line,
+            format!("expected true or false, got \"{text}\""),
+        )),
+    };
+}
@@ -1,4 +1,16 @@
+// `asm6502!` takes one level of recursion per statement of a program.
+#![recursion_limit = "256"]
+
+pub mod asm;
 pub mod consts;
 pub mod cpu;
+pub mod devices;
+pub mod disasm;
+mod ini;
+pub mod linker;
+pub mod loaders;
 pub mod machine;
 pub mod memory;
+pub mod opcodes;
+pub mod profiler;
+pub mod trace_diff;
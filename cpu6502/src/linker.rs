@@ -0,0 +1,471 @@
+//! Combines relocatable objects built by [`assemble_object`](crate::asm::assemble_object)
+//! into one program, placing their segments as described by a [`LinkerConfig`].
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    consts::{Byte, Word},
+    ini::{parse_address, split_sections, syntax_error, SyntaxError},
+    loaders::{
+        o65::{Object, SegmentId},
+        Image,
+    },
+};
+
+#[derive(Debug)]
+pub enum LinkError {
+    Config {
+        line: usize,
+        message: String,
+    },
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// An object has bytes in a segment the config does not place.
+    MissingSegmentRule(SegmentId),
+    /// Segments placed in `area` need `size` bytes past its end.
+    Overflow {
+        area: String,
+        segment: SegmentId,
+        size: u32,
+    },
+    /// The ZEROPAGE segment is placed in `area` past `$00FF`, where one byte
+    /// addresses cannot reach it.
+    ZeroPageOutOfRange {
+        area: String,
+    },
+    /// A segment with a fixed start would overwrite the one before it.
+    Overlap {
+        area: String,
+        segment: SegmentId,
+    },
+    UndefinedSymbol {
+        object: String,
+        name: String,
+    },
+    DuplicateSymbol {
+        object: String,
+        name: String,
+    },
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            LinkError::Config { line, message } => write!(f, "line {line}: {message}"),
+            LinkError::Io { path, error } => {
+                write!(f, "could not read {}: {error}", path.display())
+            }
+            LinkError::MissingSegmentRule(segment) => {
+                write!(f, "no memory area given for segment {}", segment.name())
+            }
+            LinkError::Overflow {
+                area,
+                segment,
+                size,
+            } => write!(
+                f,
+                "memory area {area} overflows by {size} bytes placing segment {}",
+                segment.name()
+            ),
+            LinkError::ZeroPageOutOfRange { area } => write!(
+                f,
+                "segment {} in memory area {area} does not fit in $0000-$00FF",
+                SegmentId::ZeroPage.name()
+            ),
+            LinkError::Overlap { area, segment } => write!(
+                f,
+                "segment {} overlaps the segment before it in memory area {area}",
+                segment.name()
+            ),
+            LinkError::UndefinedSymbol { object, name } => {
+                write!(f, "{object}: undefined symbol \"{name}\"")
+            }
+            LinkError::DuplicateSymbol { object, name } => {
+                write!(f, "{object}: symbol \"{name}\" already exported")
+            }
+        };
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+impl From<SyntaxError> for LinkError {
+    fn from(err: SyntaxError) -> Self {
+        return LinkError::Config {
+            line: err.line,
+            message: err.message,
+        };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemoryArea {
+    pub name: String,
+    pub start: Word,
+    pub end: Word,
+    /// When set the whole area is written to the binary, padded with this value.
+    pub fill: Option<Byte>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SegmentRule {
+    pub segment: SegmentId,
+    /// Name of the [`MemoryArea`] holding the segment.
+    pub memory: String,
+    /// Fixed start address, otherwise the segment follows the previous one in the area.
+    pub start: Option<Word>,
+}
+
+/// Memory layout of the linked program.
+///
+/// Uses the INI-like format of machine descriptions. Every `[memory]`
+/// section declares an area, every `[segment]` section places a segment in
+/// one of them. Segments sharing an area follow each other in the order of
+/// their sections:
+///
+/// ```text
+/// [memory]
+/// name = ZP
+/// start = $0080
+/// end = $00FF
+///
+/// [memory]
+/// name = RAM
+/// start = $0200
+/// end = $7FFF
+/// fill = $00             # write the whole area to the binary
+///
+/// [segment]
+/// name = ZEROPAGE        # CODE, DATA, BSS or ZEROPAGE
+/// memory = ZP
+///
+/// [segment]
+/// name = CODE
+/// memory = RAM
+/// start = $0400          # optional
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinkerConfig {
+    pub areas: Vec<MemoryArea>,
+    pub segments: Vec<SegmentRule>,
+}
+
+impl LinkerConfig {
+    pub fn parse(text: &str) -> Result<Self, LinkError> {
+        let mut config = LinkerConfig::default();
+        for mut section in split_sections(text)? {
+            match section.name.as_str() {
+                "memory" => {
+                    let (line, name) = section.take_required("name")?;
+                    if config.area(&name).is_some() {
+                        return Err(
+                            syntax_error(line, format!("memory area {name} repeated")).into()
+                        );
+                    }
+                    let start = section.take_address("start")?;
+                    let end = section.take_address("end")?;
+                    if end < start {
+                        return Err(
+                            syntax_error(section.line, "end is before start".to_string()).into(),
+                        );
+                    }
+                    let fill = match section.take("fill") {
+                        Some((line, value)) => {
+                            Some(Byte::try_from(parse_address(line, &value)?).map_err(|_| {
+                                syntax_error(line, format!("invalid fill value \"{value}\""))
+                            })?)
+                        }
+                        None => None,
+                    };
+                    config.areas.push(MemoryArea {
+                        name,
+                        start,
+                        end,
+                        fill,
+                    });
+                }
+                "segment" => {
+                    let (line, name) = section.take_required("name")?;
+                    let segment = SegmentId::from_name(&name)
+                        .ok_or_else(|| syntax_error(line, format!("unknown segment \"{name}\"")))?;
+                    if config.rule(segment).is_some() {
+                        return Err(syntax_error(line, format!("segment {name} repeated")).into());
+                    }
+                    let (line, memory) = section.take_required("memory")?;
+                    let area = config.area(&memory).ok_or_else(|| {
+                        syntax_error(line, format!("unknown memory area \"{memory}\""))
+                    })?;
+                    let start = match section.take("start") {
+                        Some((line, value)) => {
+                            let start = parse_address(line, &value)?;
+                            if !(area.start..=area.end).contains(&start) {
+                                return Err(syntax_error(
+                                    line,
+                                    format!("start is outside of memory area {memory}"),
+                                )
+                                .into());
+                            }
+                            Some(start)
+                        }
+                        None => None,
+                    };
+                    config.segments.push(SegmentRule {
+                        segment,
+                        memory,
+                        start,
+                    });
+                }
+                other => {
+                    return Err(
+                        syntax_error(section.line, format!("unknown section [{other}]")).into(),
+                    )
+                }
+            }
+            section.reject_remaining()?;
+        }
+
+        return Ok(config);
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, LinkError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|error| LinkError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        return LinkerConfig::parse(&text);
+    }
+
+    pub fn area(&self, name: &str) -> Option<&MemoryArea> {
+        return self.areas.iter().find(|area| area.name == name);
+    }
+
+    pub fn rule(&self, segment: SegmentId) -> Option<&SegmentRule> {
+        return self.segments.iter().find(|rule| rule.segment == segment);
+    }
+}
+
+/// Where a segment of one object ended up.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Placement {
+    pub object: String,
+    pub segment: SegmentId,
+    pub address: Word,
+    pub len: Word,
+}
+
+/// Linked program.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Linked {
+    /// Relocated `CODE` and `DATA` bytes.
+    pub image: Image,
+    /// Addresses of all exported symbols.
+    pub symbols: BTreeMap<String, Word>,
+    /// Non-empty segments in the order they were placed.
+    pub placements: Vec<Placement>,
+    /// Memory areas with the number of bytes placed in each.
+    pub usage: Vec<(MemoryArea, u32)>,
+}
+
+impl Linked {
+    /// Contents of every area holding `CODE` or `DATA`, in config order. An
+    /// area spans from its start to its last initialized byte, or to its end
+    /// when it has a fill value; gaps are filled with that value or zeros.
+    pub fn binary(&self) -> Vec<Byte> {
+        let mut binary = vec![];
+        for (area, _) in &self.usage {
+            let segments: Vec<_> = self
+                .image
+                .segments
+                .iter()
+                .filter(|segment| (area.start..=area.end).contains(&segment.address))
+                .collect();
+            let end = match area.fill {
+                Some(_) => usize::from(area.end) + 1,
+                None => match segments
+                    .iter()
+                    .map(|segment| usize::from(segment.address) + segment.data.len())
+                    .max()
+                {
+                    Some(end) => end,
+                    None => continue,
+                },
+            };
+
+            let start = usize::from(area.start);
+            let mut bytes = vec![area.fill.unwrap_or(0); end - start];
+            for segment in segments {
+                let offset = usize::from(segment.address) - start;
+                bytes[offset..offset + segment.data.len()].copy_from_slice(&segment.data);
+            }
+            binary.extend(bytes);
+        }
+
+        return binary;
+    }
+
+    /// Writes a listing of the placed segments, memory usage and symbols.
+    pub fn write_map(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "Segments:")?;
+        for placement in &self.placements {
+            writeln!(
+                out,
+                "  {:<16} {:<8} ${:04X}-${:04X} {:>5} bytes",
+                placement.object,
+                placement.segment.name(),
+                placement.address,
+                u32::from(placement.address) + u32::from(placement.len) - 1,
+                placement.len
+            )?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "Memory areas:")?;
+        for (area, used) in &self.usage {
+            writeln!(
+                out,
+                "  {:<16} ${:04X}-${:04X} {:>5} of {} bytes used",
+                area.name,
+                area.start,
+                area.end,
+                used,
+                u32::from(area.end) - u32::from(area.start) + 1
+            )?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "Symbols:")?;
+        for (name, address) in &self.symbols {
+            writeln!(out, "  {name:<24} ${address:04X}")?;
+        }
+
+        return Ok(());
+    }
+}
+
+/// Links `objects`, given with the names used in error messages and the
+/// map. Segments of the same kind are concatenated in the order of `objects`.
+pub fn link(config: &LinkerConfig, objects: &[(String, Object)]) -> Result<Linked, LinkError> {
+    let mut bases = vec![[0; 4]; objects.len()];
+    let mut placements = vec![];
+    let mut cursors: Vec<u32> = config
+        .areas
+        .iter()
+        .map(|area| u32::from(area.start))
+        .collect();
+    let mut used = vec![0; config.areas.len()];
+
+    for segment in SegmentId::ALL {
+        let used = objects
+            .iter()
+            .any(|(_, object)| object.segment(segment).len > 0);
+        if used && config.rule(segment).is_none() {
+            return Err(LinkError::MissingSegmentRule(segment));
+        }
+    }
+
+    for rule in &config.segments {
+        let idx = config
+            .areas
+            .iter()
+            .position(|area| area.name == rule.memory)
+            .expect("areas are checked when parsing the config");
+        let area = &config.areas[idx];
+        let mut address = cursors[idx];
+        if let Some(start) = rule.start {
+            if u32::from(start) < address {
+                return Err(LinkError::Overlap {
+                    area: area.name.clone(),
+                    segment: rule.segment,
+                });
+            }
+            address = u32::from(start);
+        }
+
+        for ((name, object), bases) in objects.iter().zip(bases.iter_mut()) {
+            let len = object.segment(rule.segment).len;
+            bases[rule.segment.index()] = address as Word;
+            if len == 0 {
+                continue;
+            }
+            placements.push(Placement {
+                object: name.clone(),
+                segment: rule.segment,
+                address: address as Word,
+                len,
+            });
+            address += u32::from(len);
+            used[idx] += u32::from(len);
+            if address > u32::from(area.end) + 1 {
+                return Err(LinkError::Overflow {
+                    area: area.name.clone(),
+                    segment: rule.segment,
+                    size: address - u32::from(area.end) - 1,
+                });
+            }
+            if rule.segment == SegmentId::ZeroPage && address > 0x0100 {
+                return Err(LinkError::ZeroPageOutOfRange {
+                    area: area.name.clone(),
+                });
+            }
+        }
+        cursors[idx] = address;
+    }
+
+    let mut symbols = BTreeMap::new();
+    for ((name, object), bases) in objects.iter().zip(&bases) {
+        for (symbol, address) in object.relocated_exports(*bases) {
+            if symbols.insert(symbol.clone(), address).is_some() {
+                return Err(LinkError::DuplicateSymbol {
+                    object: name.clone(),
+                    name: symbol,
+                });
+            }
+        }
+    }
+
+    let mut image = Image::default();
+    for ((name, object), bases) in objects.iter().zip(&bases) {
+        let imports = object
+            .imports
+            .iter()
+            .map(|import| {
+                return symbols
+                    .get(import)
+                    .copied()
+                    .ok_or_else(|| LinkError::UndefinedSymbol {
+                        object: name.clone(),
+                        name: import.clone(),
+                    });
+            })
+            .collect::<Result<Vec<Word>, LinkError>>()?;
+
+        let (text, data) = object.relocate(*bases, &imports);
+        for (segment, bytes) in [(SegmentId::Text, text), (SegmentId::Data, data)] {
+            if !bytes.is_empty() {
+                image.push(bases[segment.index()], bytes);
+            }
+        }
+    }
+    image.segments.sort_by_key(|segment| segment.address);
+
+    let usage = config.areas.iter().cloned().zip(used).collect();
+
+    return Ok(Linked {
+        image,
+        symbols,
+        placements,
+        usage,
+    });
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,311 @@
+use crate::{asm::assemble_object, linker::LinkerConfig, loaders::o65::Object};
+use std::path::Path;
+
+const CONFIG: &str = "
+[memory]
+name = ZP
+start = $0080
+end = $00FF
+
+[memory]
+name = RAM
+start = $0200
+end = $02FF
+
+[segment]
+name = ZEROPAGE
+memory = ZP
+
+[segment]
+name = CODE
+memory = RAM
+
+[segment]
+name = DATA
+memory = RAM
+
+[segment]
+name = BSS
+memory = RAM
+";
+
+const MAIN: &str = "
+    .import print
+    .importzp count
+    .export main
+main:   ldx #<message
+        ldy #>message
+        jsr print
+        inc count
+        brk
+    .segment \"DATA\"
+message: .byte \"HI\", 0
+";
+
+const PRINT: &str = "
+    .importzp dummy
+    .export print, count
+    .segment \"ZEROPAGE\"
+count:  .res 1
+pointer: .res 2
+    .segment \"CODE\"
+print:  stx pointer
+        sty pointer + 1
+        rts
+    .segment \"BSS\"
+buffer: .res 8
+";
+
+fn config() -> LinkerConfig {
+    return LinkerConfig::parse(CONFIG).unwrap();
+}
+
+fn objects(sources: &[(&str, &str)]) -> Vec<(String, Object)> {
+    return sources
+        .iter()
+        .map(|(name, source)| {
+            (
+                name.to_string(),
+                assemble_object(source, Path::new("")).unwrap(),
+            )
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod config {
+    use super::{config, CONFIG};
+    use crate::{
+        linker::{LinkError, LinkerConfig, MemoryArea, SegmentRule},
+        loaders::o65::SegmentId,
+    };
+
+    fn error(text: &str) -> String {
+        return match LinkerConfig::parse(text).unwrap_err() {
+            LinkError::Config { line, message } => format!("{line}: {message}"),
+            other => panic!("unexpected error {other}"),
+        };
+    }
+
+    #[test]
+    fn should_parse_memory_areas_and_segment_rules() {
+        let config = config();
+
+        assert_eq!(
+            config.areas[1],
+            MemoryArea {
+                name: "RAM".to_string(),
+                start: 0x0200,
+                end: 0x02FF,
+                fill: None,
+            }
+        );
+        assert_eq!(
+            config.segments[1],
+            SegmentRule {
+                segment: SegmentId::Text,
+                memory: "RAM".to_string(),
+                start: None,
+            }
+        );
+        assert_eq!(config.segments.len(), 4);
+    }
+
+    #[test]
+    fn should_parse_optional_fill_and_start() {
+        let config = LinkerConfig::parse(
+            "[memory]\nname = ROM\nstart = $E000\nend = $FFFF\nfill = $FF\n\
+             [segment]\nname = CODE\nmemory = ROM\nstart = $F000",
+        )
+        .unwrap();
+
+        assert_eq!(config.areas[0].fill, Some(0xFF));
+        assert_eq!(config.segments[0].start, Some(0xF000));
+    }
+
+    #[test]
+    fn should_report_invalid_configs_with_line_numbers() {
+        assert_eq!(
+            error(&format!("{CONFIG}\n[segment]\nname = HEAP\nmemory = RAM")),
+            "29: unknown segment \"HEAP\""
+        );
+        assert_eq!(
+            error("[segment]\nname = CODE\nmemory = ROM"),
+            "3: unknown memory area \"ROM\""
+        );
+        assert_eq!(
+            error("[memory]\nname = RAM\nstart = $0200\nend = $0100"),
+            "1: end is before start"
+        );
+        assert_eq!(
+            error("[memory]\nname = RAM\nstart = 0\nend = 9\n[segment]\nname = CODE\nmemory = RAM\nstart = 10"),
+            "8: start is outside of memory area RAM"
+        );
+        assert_eq!(
+            error("[memory]\nname = RAM\nstart = 0\nend = 9\nsize = 10"),
+            "5: unknown key \"size\" in [memory] section"
+        );
+        assert_eq!(error("[files]"), "1: unknown section [files]");
+    }
+}
+
+#[cfg(test)]
+mod link {
+    use super::{config, objects, CONFIG, MAIN, PRINT};
+    use crate::{
+        linker::{link, LinkError, LinkerConfig, Placement},
+        loaders::o65::SegmentId,
+        machine::builder::MachineBuilder,
+    };
+
+    const DUMMY: &str = ".export dummy\ndummy = $42";
+
+    #[test]
+    fn should_place_segments_in_config_and_input_order() {
+        let inputs = objects(&[("main", MAIN), ("print", PRINT), ("dummy", DUMMY)]);
+
+        let linked = link(&config(), &inputs).unwrap();
+
+        let placement = |object: &str, segment, address, len| Placement {
+            object: object.to_string(),
+            segment,
+            address,
+            len,
+        };
+        assert_eq!(
+            linked.placements,
+            vec![
+                placement("print", SegmentId::ZeroPage, 0x0080, 3),
+                placement("main", SegmentId::Text, 0x0200, 10),
+                placement("print", SegmentId::Text, 0x020A, 5),
+                placement("main", SegmentId::Data, 0x020F, 3),
+                placement("print", SegmentId::Bss, 0x0212, 8),
+            ]
+        );
+        assert_eq!(linked.usage[0].1, 3);
+        assert_eq!(linked.usage[1].1, 26);
+    }
+
+    #[test]
+    fn should_resolve_symbols_across_objects() {
+        let inputs = objects(&[("main", MAIN), ("print", PRINT), ("dummy", DUMMY)]);
+
+        let linked = link(&config(), &inputs).unwrap();
+
+        assert_eq!(linked.symbols["main"], 0x0200);
+        assert_eq!(linked.symbols["print"], 0x020A);
+        assert_eq!(linked.symbols["count"], 0x0080);
+        assert_eq!(linked.symbols["dummy"], 0x0042);
+        assert_eq!(
+            linked.binary(),
+            vec![
+                0xA2, 0x0F, 0xA0, 0x02, 0x20, 0x0A, 0x02, 0xE6, 0x80, 0x00, // main
+                0x86, 0x81, 0x84, 0x82, 0x60, // print
+                b'H', b'I', 0x00, // message
+            ]
+        );
+    }
+
+    #[test]
+    fn should_run_linked_program() {
+        let inputs = objects(&[("main", MAIN), ("print", PRINT), ("dummy", DUMMY)]);
+        let linked = link(&config(), &inputs).unwrap();
+        let mut machine = MachineBuilder::new().build().unwrap();
+        machine.load_image(&linked.image);
+        machine
+            .cpu_mut()
+            .set_program_counter(linked.symbols["main"]);
+
+        machine.run_instructions(5);
+
+        assert_eq!(machine.memory().peek(0x0081), 0x0F);
+        assert_eq!(machine.memory().peek(0x0082), 0x02);
+    }
+
+    #[test]
+    fn should_fill_areas_with_fill_value() {
+        let config = LinkerConfig::parse(
+            "[memory]\nname = ROM\nstart = $FFF0\nend = $FFFF\nfill = $FF\n\
+             [segment]\nname = CODE\nmemory = ROM\nstart = $FFFC",
+        )
+        .unwrap();
+        let inputs = objects(&[("vectors", "reset: .word reset")]);
+
+        let binary = link(&config, &inputs).unwrap().binary();
+
+        let mut expected = vec![0xFF; 12];
+        expected.extend([0xFC, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(binary, expected);
+    }
+
+    #[test]
+    fn should_report_overflowing_memory_area() {
+        let inputs = objects(&[("big", ".res 200, 0"), ("bigger", ".res 100, 0")]);
+
+        let err = link(&config(), &inputs).unwrap_err();
+
+        assert!(matches!(
+            err,
+            LinkError::Overflow { ref area, segment: SegmentId::Text, size: 44 } if area == "RAM"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "memory area RAM overflows by 44 bytes placing segment CODE"
+        );
+    }
+
+    #[test]
+    fn should_report_zero_page_placed_past_its_end() {
+        let config = CONFIG.replace("name = ZP\nstart = $0080", "name = ZP\nstart = $00FE");
+        let config = LinkerConfig::parse(&config.replace("end = $00FF", "end = $01FF")).unwrap();
+        let inputs = objects(&[("main", MAIN), ("print", PRINT), ("dummy", DUMMY)]);
+
+        let err = link(&config, &inputs).unwrap_err();
+
+        assert!(matches!(err, LinkError::ZeroPageOutOfRange { ref area } if area == "ZP"));
+        assert_eq!(
+            err.to_string(),
+            "segment ZEROPAGE in memory area ZP does not fit in $0000-$00FF"
+        );
+    }
+
+    #[test]
+    fn should_report_undefined_and_duplicate_symbols() {
+        let inputs = objects(&[("main", MAIN), ("print", PRINT)]);
+        assert_eq!(
+            link(&config(), &inputs).unwrap_err().to_string(),
+            "print: undefined symbol \"dummy\""
+        );
+
+        let inputs = objects(&[("a", ".export x\nx: nop"), ("b", ".export x\nx = 1")]);
+        assert_eq!(
+            link(&config(), &inputs).unwrap_err().to_string(),
+            "b: symbol \"x\" already exported"
+        );
+    }
+
+    #[test]
+    fn should_require_rules_for_used_segments() {
+        let config = LinkerConfig::parse("[memory]\nname = RAM\nstart = 0\nend = 9").unwrap();
+        let inputs = objects(&[("main", "nop")]);
+
+        assert!(matches!(
+            link(&config, &inputs).unwrap_err(),
+            LinkError::MissingSegmentRule(SegmentId::Text)
+        ));
+    }
+
+    #[test]
+    fn should_write_map_file() {
+        let inputs = objects(&[("main", MAIN), ("print", PRINT), ("dummy", DUMMY)]);
+        let linked = link(&config(), &inputs).unwrap();
+        let mut map = vec![];
+
+        linked.write_map(&mut map).unwrap();
+
+        let map = String::from_utf8(map).unwrap();
+        assert!(map.contains("  main             CODE     $0200-$0209    10 bytes\n"));
+        assert!(map.contains("  RAM              $0200-$02FF    26 of 256 bytes used\n"));
+        assert!(map.contains("  print                    $020A\n"));
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt;
+
+use crate::{
+    consts::{Byte, Word},
+    memory::Bus,
+};
+
+pub mod intel_hex;
+pub mod o65;
+pub mod prg;
+pub mod srec;
+
+/// Contiguous bytes which should be placed at `address`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment {
+    pub address: Word,
+    pub data: Vec<Byte>,
+}
+
+/// Program produced by a loader: its segments and, when the format carried
+/// one, the address execution should start at.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Image {
+    pub segments: Vec<Segment>,
+    pub entry_point: Option<Word>,
+}
+
+impl Image {
+    /// Writes all segments through the bus, so ROM regions keep their contents.
+    pub fn load_into(&self, memory: &mut dyn Bus) {
+        for segment in &self.segments {
+            let mut address = segment.address;
+            for value in &segment.data {
+                memory.write(address, *value);
+                address = address.wrapping_add(1);
+            }
+        }
+    }
+
+    /// Flattens the image into the address/value pairs `VecMemory::from` accepts.
+    pub fn to_pairs(&self) -> Vec<(Word, Byte)> {
+        let mut pairs = vec![];
+        for segment in &self.segments {
+            let mut address = segment.address;
+            for value in &segment.data {
+                pairs.push((address, *value));
+                address = address.wrapping_add(1);
+            }
+        }
+
+        return pairs;
+    }
+
+    pub(crate) fn push(&mut self, address: Word, data: Vec<Byte>) {
+        if let Some(last) = self.segments.last_mut() {
+            let next_address = usize::from(last.address) + last.data.len();
+            if next_address == usize::from(address) {
+                last.data.extend(data);
+                return;
+            }
+        }
+
+        self.segments.push(Segment { address, data });
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RecordErrorKind {
+    MissingStartCode,
+    InvalidHexDigits,
+    LengthMismatch { declared: usize, actual: usize },
+    ChecksumMismatch { expected: Byte, actual: Byte },
+    UnsupportedRecordType(String),
+    AddressOutOfRange(u32),
+    MissingEndRecord,
+}
+
+/// Error in a text record based format, `line` is counted from 1.
+#[derive(Debug, PartialEq)]
+pub struct RecordError {
+    pub line: usize,
+    pub kind: RecordErrorKind,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        return match &self.kind {
+            RecordErrorKind::MissingStartCode => {
+                write!(f, "record does not start with a start code")
+            }
+            RecordErrorKind::InvalidHexDigits => write!(f, "record contains invalid hex digits"),
+            RecordErrorKind::LengthMismatch { declared, actual } => {
+                write!(f, "record declares {declared} bytes but contains {actual}")
+            }
+            RecordErrorKind::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch: expected ${expected:02X}, found ${actual:02X}"
+            ),
+            RecordErrorKind::UnsupportedRecordType(record_type) => {
+                write!(f, "unsupported record type {record_type}")
+            }
+            RecordErrorKind::AddressOutOfRange(address) => {
+                write!(
+                    f,
+                    "address ${address:X} is outside of the 64 KB address space"
+                )
+            }
+            RecordErrorKind::MissingEndRecord => write!(f, "missing end of file record"),
+        };
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// Error in a binary object format.
+#[derive(Debug, PartialEq)]
+pub enum ObjectError {
+    Truncated,
+    InvalidMagic,
+    Unsupported(String),
+    InvalidSegment(Byte),
+    InvalidRelocation { offset: usize },
+    UnresolvedSymbol(String),
+    AddressOutOfRange,
+}
+
+impl fmt::Display for ObjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            ObjectError::Truncated => write!(f, "file is truncated"),
+            ObjectError::InvalidMagic => write!(f, "file does not start with a valid header"),
+            ObjectError::Unsupported(feature) => write!(f, "unsupported feature: {feature}"),
+            ObjectError::InvalidSegment(id) => write!(f, "invalid segment id {id}"),
+            ObjectError::InvalidRelocation { offset } => {
+                write!(
+                    f,
+                    "relocation entry points outside of its segment at offset {offset}"
+                )
+            }
+            ObjectError::UnresolvedSymbol(name) => write!(f, "unresolved symbol {name}"),
+            ObjectError::AddressOutOfRange => {
+                write!(f, "contents do not fit in the 64 KB address space")
+            }
+        };
+    }
+}
+
+impl std::error::Error for ObjectError {}
+
+fn parse_hex_bytes(digits: &str) -> Option<Vec<Byte>> {
+    if !digits.len().is_multiple_of(2) || !digits.is_ascii() {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(digits.len() / 2);
+    for idx in (0..digits.len()).step_by(2) {
+        let byte = Byte::from_str_radix(&digits[idx..idx + 2], 16).ok()?;
+        bytes.push(byte);
+    }
+
+    return Some(bytes);
+}
+
+fn to_word(address: u32) -> Result<Word, RecordErrorKind> {
+    return Word::try_from(address).map_err(|_| RecordErrorKind::AddressOutOfRange(address));
+}
+
+/// Checks that `len` bytes starting at `address` stay within the address space.
+fn check_span(address: u32, len: usize) -> Result<Word, RecordErrorKind> {
+    let word = to_word(address)?;
+    if len > 0 {
+        to_word(address + len as u32 - 1)?;
+    }
+
+    return Ok(word);
+}
+
+#[cfg(test)]
+mod tests;
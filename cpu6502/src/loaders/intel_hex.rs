@@ -0,0 +1,134 @@
+use super::{check_span, parse_hex_bytes, to_word, Image, RecordError, RecordErrorKind};
+use crate::consts::Byte;
+
+const RECORD_DATA: Byte = 0x00;
+const RECORD_END_OF_FILE: Byte = 0x01;
+const RECORD_EXTENDED_SEGMENT_ADDRESS: Byte = 0x02;
+const RECORD_START_SEGMENT_ADDRESS: Byte = 0x03;
+const RECORD_EXTENDED_LINEAR_ADDRESS: Byte = 0x04;
+const RECORD_START_LINEAR_ADDRESS: Byte = 0x05;
+
+/// Parses an Intel HEX file. Extended address records are honoured as long
+/// as all data stays within the 64 KB address space; start address records
+/// set the entry point.
+pub fn parse(text: &str) -> Result<Image, RecordError> {
+    let mut image = Image::default();
+    let mut base_address: u32 = 0;
+
+    for (idx, line) in text.lines().enumerate() {
+        let line_number = idx + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let error = |kind| RecordError {
+            line: line_number,
+            kind,
+        };
+        let record = parse_record(line).map_err(error)?;
+        match record.record_type {
+            RECORD_DATA => {
+                let address = base_address + u32::from(record.address);
+                let address = check_span(address, record.data.len()).map_err(error)?;
+                image.push(address, record.data);
+            }
+            RECORD_END_OF_FILE => return Ok(image),
+            RECORD_EXTENDED_SEGMENT_ADDRESS => {
+                base_address = u32::from(record.word_payload().map_err(error)?) << 4;
+            }
+            RECORD_EXTENDED_LINEAR_ADDRESS => {
+                base_address = u32::from(record.word_payload().map_err(error)?) << 16;
+            }
+            RECORD_START_SEGMENT_ADDRESS => {
+                let [cs_hi, cs_lo, ip_hi, ip_lo] = record.dword_payload().map_err(error)?;
+                let segment = u32::from(u16::from_be_bytes([cs_hi, cs_lo]));
+                let offset = u32::from(u16::from_be_bytes([ip_hi, ip_lo]));
+                image.entry_point = Some(to_word((segment << 4) + offset).map_err(error)?);
+            }
+            RECORD_START_LINEAR_ADDRESS => {
+                let address = u32::from_be_bytes(record.dword_payload().map_err(error)?);
+                image.entry_point = Some(to_word(address).map_err(error)?);
+            }
+            other => {
+                return Err(error(RecordErrorKind::UnsupportedRecordType(format!(
+                    "{other:02X}"
+                ))))
+            }
+        }
+    }
+
+    return Err(RecordError {
+        line: text.lines().count(),
+        kind: RecordErrorKind::MissingEndRecord,
+    });
+}
+
+struct Record {
+    address: u16,
+    record_type: Byte,
+    data: Vec<Byte>,
+}
+
+impl Record {
+    fn word_payload(&self) -> Result<u16, RecordErrorKind> {
+        return match self.data[..] {
+            [hi, lo] => Ok(u16::from_be_bytes([hi, lo])),
+            _ => Err(RecordErrorKind::LengthMismatch {
+                declared: 2,
+                actual: self.data.len(),
+            }),
+        };
+    }
+
+    fn dword_payload(&self) -> Result<[Byte; 4], RecordErrorKind> {
+        return match self.data[..] {
+            [b0, b1, b2, b3] => Ok([b0, b1, b2, b3]),
+            _ => Err(RecordErrorKind::LengthMismatch {
+                declared: 4,
+                actual: self.data.len(),
+            }),
+        };
+    }
+}
+
+fn parse_record(line: &str) -> Result<Record, RecordErrorKind> {
+    let digits = match line.strip_prefix(':') {
+        Some(digits) => digits,
+        None => return Err(RecordErrorKind::MissingStartCode),
+    };
+    let bytes = parse_hex_bytes(digits).ok_or(RecordErrorKind::InvalidHexDigits)?;
+    if bytes.len() < 5 {
+        return Err(RecordErrorKind::LengthMismatch {
+            declared: 5,
+            actual: bytes.len(),
+        });
+    }
+
+    let declared = usize::from(bytes[0]);
+    let actual = bytes.len() - 5;
+    if declared != actual {
+        return Err(RecordErrorKind::LengthMismatch { declared, actual });
+    }
+
+    let (payload, checksum) = bytes.split_at(bytes.len() - 1);
+    let sum = payload
+        .iter()
+        .fold(0 as Byte, |sum, byte| sum.wrapping_add(*byte));
+    let expected = sum.wrapping_neg();
+    if expected != checksum[0] {
+        return Err(RecordErrorKind::ChecksumMismatch {
+            expected,
+            actual: checksum[0],
+        });
+    }
+
+    return Ok(Record {
+        address: u16::from_be_bytes([bytes[1], bytes[2]]),
+        record_type: bytes[3],
+        data: bytes[4..bytes.len() - 1].to_vec(),
+    });
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,148 @@
+#[cfg(test)]
+mod parse {
+    use crate::loaders::{intel_hex::parse, RecordError, RecordErrorKind, Segment};
+
+    #[test]
+    fn should_merge_contiguous_data_records_into_one_segment() {
+        let text = ":03123400A942606C\n:01123700EACC\n:02030000A9FF53\n:00000001FF\n";
+
+        let image = parse(text).unwrap();
+
+        assert_eq!(
+            image.segments,
+            vec![
+                Segment {
+                    address: 0x1234,
+                    data: vec![0xA9, 0x42, 0x60, 0xEA]
+                },
+                Segment {
+                    address: 0x0300,
+                    data: vec![0xA9, 0xFF]
+                },
+            ]
+        );
+        assert_eq!(image.entry_point, None);
+    }
+
+    #[test]
+    fn should_set_entry_point_from_start_linear_address_record() {
+        let text = ":03123400A942606C\n:0400000500001234B1\n:00000001FF\n";
+
+        let image = parse(text).unwrap();
+
+        assert_eq!(image.entry_point, Some(0x1234));
+    }
+
+    #[test]
+    fn should_set_entry_point_from_start_segment_address_record() {
+        let text = ":0400000301000234C2\n:00000001FF\n";
+
+        let image = parse(text).unwrap();
+
+        assert_eq!(image.entry_point, Some(0x1234));
+    }
+
+    #[test]
+    fn should_apply_extended_segment_address_to_data_records() {
+        let text = ":020000020100FB\n:0100340001CA\n:00000001FF\n";
+
+        let image = parse(text).unwrap();
+
+        assert_eq!(image.segments[0].address, 0x1034);
+    }
+
+    #[test]
+    fn should_ignore_records_after_end_of_file_record() {
+        let text = ":00000001FF\n:zz\n";
+
+        assert_eq!(parse(text).unwrap().segments.len(), 0);
+    }
+
+    #[test]
+    fn should_report_checksum_mismatch_with_line_number() {
+        let text = ":03123400A942606C\n\n:01123700EACD\n:00000001FF\n";
+
+        assert_eq!(
+            parse(text).err(),
+            Some(RecordError {
+                line: 3,
+                kind: RecordErrorKind::ChecksumMismatch {
+                    expected: 0xCC,
+                    actual: 0xCD
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn should_report_data_outside_of_address_space() {
+        let text = ":020000040001F9\n:0100340001CA\n:00000001FF\n";
+
+        assert_eq!(
+            parse(text).err(),
+            Some(RecordError {
+                line: 2,
+                kind: RecordErrorKind::AddressOutOfRange(0x10034)
+            })
+        );
+    }
+
+    #[test]
+    fn should_report_data_record_crossing_end_of_address_space() {
+        let text = ":02FFFF000102FD\n:00000001FF\n";
+
+        assert_eq!(
+            parse(text).err().map(|err| err.kind),
+            Some(RecordErrorKind::AddressOutOfRange(0x10000))
+        );
+    }
+
+    #[test]
+    fn should_report_missing_start_code() {
+        let text = "03123400A942606C\n";
+
+        assert_eq!(
+            parse(text).err(),
+            Some(RecordError {
+                line: 1,
+                kind: RecordErrorKind::MissingStartCode
+            })
+        );
+    }
+
+    #[test]
+    fn should_report_declared_length_not_matching_record() {
+        let text = ":04123400A942606C\n";
+
+        assert_eq!(
+            parse(text).err().map(|err| err.kind),
+            Some(RecordErrorKind::LengthMismatch {
+                declared: 4,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn should_report_unsupported_record_type() {
+        let text = ":00000006FA\n";
+
+        assert_eq!(
+            parse(text).err().map(|err| err.kind),
+            Some(RecordErrorKind::UnsupportedRecordType(String::from("06")))
+        );
+    }
+
+    #[test]
+    fn should_report_missing_end_of_file_record() {
+        let text = ":03123400A942606C\n";
+
+        assert_eq!(
+            parse(text).err(),
+            Some(RecordError {
+                line: 1,
+                kind: RecordErrorKind::MissingEndRecord
+            })
+        );
+    }
+}
@@ -0,0 +1,616 @@
+use std::collections::HashMap;
+
+use super::ObjectError;
+use crate::{
+    consts::{Byte, Word},
+    memory::Bus,
+};
+
+const MAGIC: [Byte; 6] = [0x01, 0x00, b'o', b'6', b'5', 0x00];
+
+const MODE_65816: u16 = 0x8000;
+const MODE_PAGE_RELOCATION: u16 = 0x4000;
+const MODE_32_BIT: u16 = 0x2000;
+const MODE_CHAIN: u16 = 0x0400;
+const MODE_BSS_ZERO: u16 = 0x0200;
+
+const SEGMENT_UNDEFINED: Byte = 0;
+const SEGMENT_ABSOLUTE: Byte = 1;
+const SEGMENT_TEXT: Byte = 2;
+const SEGMENT_DATA: Byte = 3;
+const SEGMENT_BSS: Byte = 4;
+const SEGMENT_ZERO_PAGE: Byte = 5;
+
+const RELOCATION_WORD: Byte = 0x80;
+const RELOCATION_HIGH: Byte = 0x40;
+const RELOCATION_LOW: Byte = 0x20;
+
+/// Where segments are relocated to and how undefined references are resolved.
+/// Unset data and bss bases place the segment right after the previous one,
+/// an unset zero page base keeps the one stored in the file.
+pub struct Options {
+    pub text_base: Word,
+    pub data_base: Option<Word>,
+    pub bss_base: Option<Word>,
+    pub zero_page_base: Option<Word>,
+    pub imports: HashMap<String, Word>,
+}
+
+impl Options {
+    pub fn new(text_base: Word) -> Self {
+        return Options {
+            text_base,
+            data_base: None,
+            bss_base: None,
+            zero_page_base: None,
+            imports: HashMap::new(),
+        };
+    }
+
+    pub fn with_import(mut self, name: &str, address: Word) -> Self {
+        self.imports.insert(String::from(name), address);
+        return self;
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Placement {
+    pub address: Word,
+    pub len: Word,
+}
+
+/// Final location of every segment and the relocated exported symbols.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+    pub text: Placement,
+    pub data: Placement,
+    pub bss: Placement,
+    pub zero_page: Placement,
+    pub exports: HashMap<String, Word>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SegmentId {
+    Text,
+    Data,
+    Bss,
+    ZeroPage,
+}
+
+impl SegmentId {
+    pub const ALL: [SegmentId; 4] = [
+        SegmentId::Text,
+        SegmentId::Data,
+        SegmentId::Bss,
+        SegmentId::ZeroPage,
+    ];
+
+    /// Segment named as in assembler sources and linker configs.
+    pub fn from_name(name: &str) -> Option<Self> {
+        return match name.to_ascii_uppercase().as_str() {
+            "CODE" | "TEXT" => Some(SegmentId::Text),
+            "DATA" => Some(SegmentId::Data),
+            "BSS" => Some(SegmentId::Bss),
+            "ZEROPAGE" | "ZP" => Some(SegmentId::ZeroPage),
+            _ => None,
+        };
+    }
+
+    pub fn name(self) -> &'static str {
+        return match self {
+            SegmentId::Text => "CODE",
+            SegmentId::Data => "DATA",
+            SegmentId::Bss => "BSS",
+            SegmentId::ZeroPage => "ZEROPAGE",
+        };
+    }
+
+    fn from_byte(value: Byte) -> Option<Self> {
+        return match value {
+            SEGMENT_TEXT => Some(SegmentId::Text),
+            SEGMENT_DATA => Some(SegmentId::Data),
+            SEGMENT_BSS => Some(SegmentId::Bss),
+            SEGMENT_ZERO_PAGE => Some(SegmentId::ZeroPage),
+            _ => None,
+        };
+    }
+
+    fn byte(self) -> Byte {
+        return match self {
+            SegmentId::Text => SEGMENT_TEXT,
+            SegmentId::Data => SEGMENT_DATA,
+            SegmentId::Bss => SEGMENT_BSS,
+            SegmentId::ZeroPage => SEGMENT_ZERO_PAGE,
+        };
+    }
+
+    pub(crate) fn index(self) -> usize {
+        return usize::from(self.byte() - SEGMENT_TEXT);
+    }
+}
+
+/// What the relocated value is relative to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RelocationTarget {
+    Absolute,
+    Segment(SegmentId),
+    /// Index into [`Object::imports`].
+    Import(usize),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RelocationKind {
+    Word,
+    /// High byte of an address whose low byte is `low`.
+    High {
+        low: Byte,
+    },
+    Low,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Relocation {
+    /// Offset of the relocated byte(s) within the segment.
+    pub offset: usize,
+    pub kind: RelocationKind,
+    pub target: RelocationTarget,
+}
+
+/// Segment as stored in the file, `bytes` is empty for bss and zero page.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjectSegment {
+    pub base: Word,
+    pub len: Word,
+    pub bytes: Vec<Byte>,
+    pub relocations: Vec<Relocation>,
+}
+
+/// Exported symbol, `segment` is `None` for absolute values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Export {
+    pub name: String,
+    pub segment: Option<SegmentId>,
+    pub value: Word,
+}
+
+/// Contents of an o65 file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Object {
+    pub mode: u16,
+    pub text: ObjectSegment,
+    pub data: ObjectSegment,
+    pub bss: ObjectSegment,
+    pub zero_page: ObjectSegment,
+    /// Names of the undefined references.
+    pub imports: Vec<String>,
+    pub exports: Vec<Export>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [Byte],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<Byte, ObjectError> {
+        let value = *self
+            .bytes
+            .get(self.position)
+            .ok_or(ObjectError::Truncated)?;
+        self.position += 1;
+
+        return Ok(value);
+    }
+
+    fn word(&mut self) -> Result<Word, ObjectError> {
+        let lo = self.byte()?;
+        let hi = self.byte()?;
+
+        return Ok(Word::from_le_bytes([lo, hi]));
+    }
+
+    fn slice(&mut self, len: usize) -> Result<&'a [Byte], ObjectError> {
+        let end = self.position + len;
+        let slice = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(ObjectError::Truncated)?;
+        self.position = end;
+
+        return Ok(slice);
+    }
+
+    fn name(&mut self) -> Result<String, ObjectError> {
+        let mut name = vec![];
+        loop {
+            match self.byte()? {
+                0 => return Ok(String::from_utf8_lossy(&name).into_owned()),
+                value => name.push(value),
+            }
+        }
+    }
+}
+
+struct Header {
+    mode: u16,
+    bases: [Word; 4],
+    lens: [Word; 4],
+}
+
+impl Header {
+    fn read(reader: &mut Reader) -> Result<Self, ObjectError> {
+        if reader
+            .slice(MAGIC.len())
+            .map_err(|_| ObjectError::InvalidMagic)?
+            != MAGIC
+        {
+            return Err(ObjectError::InvalidMagic);
+        }
+
+        let mode = reader.word()?;
+        if mode & MODE_65816 != 0 {
+            return Err(ObjectError::Unsupported(String::from("65816 code")));
+        }
+        if mode & MODE_32_BIT != 0 {
+            return Err(ObjectError::Unsupported(String::from("32-bit sizes")));
+        }
+        if mode & MODE_CHAIN != 0 {
+            return Err(ObjectError::Unsupported(String::from("chained files")));
+        }
+
+        let mut bases = [0; 4];
+        let mut lens = [0; 4];
+        for idx in 0..4 {
+            bases[idx] = reader.word()?;
+            lens[idx] = reader.word()?;
+        }
+        reader.word()?; // stack size, not used when loading
+
+        loop {
+            let option_len = reader.byte()?;
+            if option_len == 0 {
+                break;
+            }
+            reader.slice(usize::from(option_len.saturating_sub(1)))?;
+        }
+
+        return Ok(Header { mode, bases, lens });
+    }
+}
+
+impl Object {
+    pub fn segment(&self, id: SegmentId) -> &ObjectSegment {
+        return match id {
+            SegmentId::Text => &self.text,
+            SegmentId::Data => &self.data,
+            SegmentId::Bss => &self.bss,
+            SegmentId::ZeroPage => &self.zero_page,
+        };
+    }
+
+    pub fn segment_mut(&mut self, id: SegmentId) -> &mut ObjectSegment {
+        return match id {
+            SegmentId::Text => &mut self.text,
+            SegmentId::Data => &mut self.data,
+            SegmentId::Bss => &mut self.bss,
+            SegmentId::ZeroPage => &mut self.zero_page,
+        };
+    }
+
+    pub fn parse(bytes: &[Byte]) -> Result<Self, ObjectError> {
+        let mut reader = Reader { bytes, position: 0 };
+        let header = Header::read(&mut reader)?;
+        let mut object = Object {
+            mode: header.mode,
+            ..Object::default()
+        };
+        for id in SegmentId::ALL {
+            let segment = object.segment_mut(id);
+            segment.base = header.bases[id.index()];
+            segment.len = header.lens[id.index()];
+        }
+
+        object.text.bytes = reader.slice(usize::from(object.text.len))?.to_vec();
+        object.data.bytes = reader.slice(usize::from(object.data.len))?.to_vec();
+
+        let undefined_count = reader.word()?;
+        for _ in 0..undefined_count {
+            object.imports.push(reader.name()?);
+        }
+
+        let page_wise = header.mode & MODE_PAGE_RELOCATION != 0;
+        for id in [SegmentId::Text, SegmentId::Data] {
+            let len = object.segment(id).bytes.len();
+            let relocations = read_relocations(&mut reader, len, object.imports.len(), page_wise)?;
+            object.segment_mut(id).relocations = relocations;
+        }
+
+        let export_count = reader.word()?;
+        for _ in 0..export_count {
+            let name = reader.name()?;
+            let segment = match reader.byte()? {
+                SEGMENT_ABSOLUTE => None,
+                id => Some(SegmentId::from_byte(id).ok_or(ObjectError::InvalidSegment(id))?),
+            };
+            let value = reader.word()?;
+            object.exports.push(Export {
+                name,
+                segment,
+                value,
+            });
+        }
+
+        return Ok(object);
+    }
+
+    pub fn to_bytes(&self) -> Vec<Byte> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(self.mode.to_le_bytes());
+        for id in SegmentId::ALL {
+            let segment = self.segment(id);
+            bytes.extend(segment.base.to_le_bytes());
+            bytes.extend(segment.len.to_le_bytes());
+        }
+        bytes.extend([0x00, 0x00]); // stack size
+        bytes.push(0x00); // no header options
+
+        bytes.extend(&self.text.bytes);
+        bytes.extend(&self.data.bytes);
+
+        bytes.extend((self.imports.len() as Word).to_le_bytes());
+        for name in &self.imports {
+            bytes.extend(name.as_bytes());
+            bytes.push(0x00);
+        }
+
+        let page_wise = self.mode & MODE_PAGE_RELOCATION != 0;
+        for segment in [&self.text, &self.data] {
+            write_relocations(&mut bytes, &segment.relocations, page_wise);
+        }
+
+        bytes.extend((self.exports.len() as Word).to_le_bytes());
+        for export in &self.exports {
+            bytes.extend(export.name.as_bytes());
+            bytes.push(0x00);
+            bytes.push(export.segment.map_or(SEGMENT_ABSOLUTE, SegmentId::byte));
+            bytes.extend(export.value.to_le_bytes());
+        }
+
+        return bytes;
+    }
+
+    /// Returns the text and data segments relocated so that every segment
+    /// starts at `bases`, indexed like [`SegmentId::ALL`]. `imports` holds
+    /// the address of each of [`Object::imports`].
+    pub fn relocate(&self, bases: [Word; 4], imports: &[Word]) -> (Vec<Byte>, Vec<Byte>) {
+        let delta = |target: RelocationTarget| -> Word {
+            return match target {
+                RelocationTarget::Absolute => 0,
+                RelocationTarget::Segment(id) => {
+                    bases[id.index()].wrapping_sub(self.segment(id).base)
+                }
+                RelocationTarget::Import(idx) => imports[idx],
+            };
+        };
+
+        let relocate_segment = |segment: &ObjectSegment| -> Vec<Byte> {
+            let mut bytes = segment.bytes.clone();
+            for relocation in &segment.relocations {
+                let delta = delta(relocation.target);
+                let position = relocation.offset;
+                match relocation.kind {
+                    RelocationKind::Word => {
+                        let value = Word::from_le_bytes([bytes[position], bytes[position + 1]]);
+                        let [lo, hi] = value.wrapping_add(delta).to_le_bytes();
+                        bytes[position] = lo;
+                        bytes[position + 1] = hi;
+                    }
+                    RelocationKind::High { low } => {
+                        let [_, hi] = Word::from_le_bytes([low, bytes[position]])
+                            .wrapping_add(delta)
+                            .to_le_bytes();
+                        bytes[position] = hi;
+                    }
+                    RelocationKind::Low => {
+                        bytes[position] = bytes[position].wrapping_add(delta.to_le_bytes()[0]);
+                    }
+                }
+            }
+
+            return bytes;
+        };
+
+        return (relocate_segment(&self.text), relocate_segment(&self.data));
+    }
+
+    /// Exported symbols with the segments starting at `bases`.
+    pub fn relocated_exports(&self, bases: [Word; 4]) -> Vec<(String, Word)> {
+        return self
+            .exports
+            .iter()
+            .map(|export| {
+                let delta = match export.segment {
+                    Some(id) => bases[id.index()].wrapping_sub(self.segment(id).base),
+                    None => 0,
+                };
+                (export.name.clone(), export.value.wrapping_add(delta))
+            })
+            .collect();
+    }
+}
+
+fn read_relocations(
+    reader: &mut Reader,
+    len: usize,
+    import_count: usize,
+    page_wise: bool,
+) -> Result<Vec<Relocation>, ObjectError> {
+    let mut relocations = vec![];
+    let mut position: usize = 0;
+    let mut first = true;
+    loop {
+        let mut offset = reader.byte()?;
+        if offset == 0 {
+            return Ok(relocations);
+        }
+        while offset == 0xFF {
+            position += 0xFE;
+            offset = reader.byte()?;
+        }
+        // the first offset is counted from the byte before the segment
+        position += usize::from(offset);
+        if first {
+            position -= 1;
+            first = false;
+        }
+
+        let type_byte = reader.byte()?;
+        let kind = match type_byte & 0xE0 {
+            RELOCATION_WORD => RelocationKind::Word,
+            RELOCATION_HIGH => RelocationKind::High { low: 0 },
+            RELOCATION_LOW => RelocationKind::Low,
+            other => {
+                return Err(ObjectError::Unsupported(format!(
+                    "relocation type ${other:02X}"
+                )))
+            }
+        };
+        let target = match type_byte & 0x1F {
+            SEGMENT_UNDEFINED => {
+                let idx = usize::from(reader.word()?);
+                if idx >= import_count {
+                    return Err(ObjectError::InvalidSegment(SEGMENT_UNDEFINED));
+                }
+                RelocationTarget::Import(idx)
+            }
+            SEGMENT_ABSOLUTE => RelocationTarget::Absolute,
+            id => RelocationTarget::Segment(
+                SegmentId::from_byte(id).ok_or(ObjectError::InvalidSegment(id))?,
+            ),
+        };
+        let kind = match kind {
+            RelocationKind::High { .. } if !page_wise => RelocationKind::High {
+                low: reader.byte()?,
+            },
+            kind => kind,
+        };
+
+        let size = if kind == RelocationKind::Word { 2 } else { 1 };
+        if position + size > len {
+            return Err(ObjectError::InvalidRelocation { offset: position });
+        }
+        relocations.push(Relocation {
+            offset: position,
+            kind,
+            target,
+        });
+    }
+}
+
+fn write_relocations(bytes: &mut Vec<Byte>, relocations: &[Relocation], page_wise: bool) {
+    let mut relocations = relocations.to_vec();
+    relocations.sort_by_key(|relocation| relocation.offset);
+
+    // the first offset is counted from the byte before the segment
+    let mut previous: isize = -1;
+    for relocation in relocations {
+        let mut distance = (relocation.offset as isize - previous) as usize;
+        while distance > 0xFE {
+            bytes.push(0xFF);
+            distance -= 0xFE;
+        }
+        bytes.push(distance as Byte);
+        previous = relocation.offset as isize;
+
+        let (kind, low) = match relocation.kind {
+            RelocationKind::Word => (RELOCATION_WORD, None),
+            RelocationKind::High { low } => (RELOCATION_HIGH, (!page_wise).then_some(low)),
+            RelocationKind::Low => (RELOCATION_LOW, None),
+        };
+        match relocation.target {
+            RelocationTarget::Absolute => bytes.push(kind | SEGMENT_ABSOLUTE),
+            RelocationTarget::Segment(id) => bytes.push(kind | id.byte()),
+            RelocationTarget::Import(idx) => {
+                bytes.push(kind | SEGMENT_UNDEFINED);
+                bytes.extend((idx as Word).to_le_bytes());
+            }
+        }
+        if let Some(low) = low {
+            bytes.push(low);
+        }
+    }
+    bytes.push(0x00);
+}
+
+fn place(address: Word, len: Word, limit: usize) -> Result<Placement, ObjectError> {
+    if usize::from(address) + usize::from(len) > limit {
+        return Err(ObjectError::AddressOutOfRange);
+    }
+
+    return Ok(Placement { address, len });
+}
+
+/// Relocates an o65 file according to `options` and loads its text and data
+/// segments into memory. The bss segment is cleared when the file requests it.
+pub fn load(bytes: &[u8], options: &Options, memory: &mut dyn Bus) -> Result<Layout, ObjectError> {
+    let object = Object::parse(bytes)?;
+
+    let text = place(options.text_base, object.text.len, 0x10000)?;
+    let data_base = options
+        .data_base
+        .unwrap_or(text.address.wrapping_add(text.len));
+    let data = place(data_base, object.data.len, 0x10000)?;
+    let bss_base = options
+        .bss_base
+        .unwrap_or(data.address.wrapping_add(data.len));
+    let bss = place(bss_base, object.bss.len, 0x10000)?;
+    let zero_page_base = options.zero_page_base.unwrap_or(object.zero_page.base);
+    let zero_page = place(zero_page_base, object.zero_page.len, 0x100)?;
+
+    let bases = [text.address, data.address, bss.address, zero_page.address];
+    let page_wise = object.mode & MODE_PAGE_RELOCATION != 0;
+    let unaligned = SegmentId::ALL
+        .iter()
+        .any(|id| bases[id.index()].wrapping_sub(object.segment(*id).base) & 0x00FF != 0);
+    if page_wise && unaligned {
+        return Err(ObjectError::Unsupported(String::from(
+            "page-wise relocation to a base which is not page aligned",
+        )));
+    }
+
+    let mut imports = vec![];
+    for name in &object.imports {
+        match options.imports.get(name) {
+            Some(address) => imports.push(*address),
+            None => return Err(ObjectError::UnresolvedSymbol(name.clone())),
+        }
+    }
+
+    let (text_segment, data_segment) = object.relocate(bases, &imports);
+    let exports = object.relocated_exports(bases).into_iter().collect();
+
+    write_segment(memory, text.address, &text_segment);
+    write_segment(memory, data.address, &data_segment);
+    if object.mode & MODE_BSS_ZERO != 0 {
+        write_segment(memory, bss.address, &vec![0; usize::from(object.bss.len)]);
+    }
+
+    return Ok(Layout {
+        text,
+        data,
+        bss,
+        zero_page,
+        exports,
+    });
+}
+
+fn write_segment(memory: &mut dyn Bus, address: Word, bytes: &[Byte]) {
+    let mut address = address;
+    for value in bytes {
+        memory.write(address, *value);
+        address = address.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests;
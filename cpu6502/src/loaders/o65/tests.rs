@@ -0,0 +1,252 @@
+fn object_file(mode: [u8; 2]) -> Vec<u8> {
+    let mut bytes = vec![0x01, 0x00, b'o', b'6', b'5', 0x00];
+    bytes.extend([mode[0], mode[1]]);
+    bytes.extend([0x00, 0x10, 0x08, 0x00]); // text: $1000, 8 bytes
+    bytes.extend([0x00, 0x20, 0x02, 0x00]); // data: $2000, 2 bytes
+    bytes.extend([0x00, 0x30, 0x04, 0x00]); // bss: $3000, 4 bytes
+    bytes.extend([0x10, 0x00, 0x02, 0x00]); // zero page: $0010, 2 bytes
+    bytes.extend([0x00, 0x00]); // stack
+    bytes.extend([0x04, 0x00, b'A', b'B', 0x00]); // filename option, end of options
+    bytes.extend([0xAD, 0x05, 0x10]); // LDA $1005
+    bytes.extend([0x20, 0x00, 0x00]); // JSR chrout
+    bytes.extend([0xA9, 0x20]); // LDA #>($2001)
+    bytes.extend([0x00, 0x10]); // .word $1000
+    bytes.extend([0x01, 0x00]);
+    bytes.extend(b"chrout\0");
+    bytes.extend([0x02, 0x82]); // text +1: word, text segment
+    bytes.extend([0x03, 0x80, 0x00, 0x00]); // text +4: word, undefined reference 0
+    bytes.extend([0x03, 0x43, 0x01]); // text +7: high byte, data segment, low byte $01
+    bytes.push(0x00);
+    bytes.extend([0x01, 0x82]); // data +0: word, text segment
+    bytes.push(0x00);
+    bytes.extend([0x02, 0x00]);
+    bytes.extend(b"start\0");
+    bytes.extend([0x02, 0x00, 0x10]);
+    bytes.extend(b"buffer\0");
+    bytes.extend([0x04, 0x00, 0x30]);
+
+    return bytes;
+}
+
+#[cfg(test)]
+mod load {
+    use super::object_file;
+    use crate::loaders::{
+        o65::{load, Options, Placement},
+        ObjectError,
+    };
+    use crate::memory::{Bus, VecMemory};
+
+    fn options() -> Options {
+        return Options::new(0xC000).with_import("chrout", 0xFFD2);
+    }
+
+    #[test]
+    fn should_relocate_text_and_data_segments() {
+        let mut memory = VecMemory::new();
+
+        load(&object_file([0x00, 0x00]), &options(), &mut memory).unwrap();
+
+        let text: Vec<u8> = (0xC000..0xC008).map(|addr| memory.peek(addr)).collect();
+        assert_eq!(text, vec![0xAD, 0x05, 0xC0, 0x20, 0xD2, 0xFF, 0xA9, 0xC0]);
+        assert_eq!(memory.peek(0xC008), 0x00);
+        assert_eq!(memory.peek(0xC009), 0xC0);
+    }
+
+    #[test]
+    fn should_return_segment_layout_and_relocated_exports() {
+        let mut memory = VecMemory::new();
+
+        let layout = load(&object_file([0x00, 0x00]), &options(), &mut memory).unwrap();
+
+        assert_eq!(
+            layout.text,
+            Placement {
+                address: 0xC000,
+                len: 8
+            }
+        );
+        assert_eq!(
+            layout.data,
+            Placement {
+                address: 0xC008,
+                len: 2
+            }
+        );
+        assert_eq!(
+            layout.bss,
+            Placement {
+                address: 0xC00A,
+                len: 4
+            }
+        );
+        assert_eq!(
+            layout.zero_page,
+            Placement {
+                address: 0x0010,
+                len: 2
+            }
+        );
+        assert_eq!(layout.exports["start"], 0xC000);
+        assert_eq!(layout.exports["buffer"], 0xC00A);
+    }
+
+    #[test]
+    fn should_place_segments_at_requested_bases() {
+        let mut memory = VecMemory::new();
+        let mut options = options();
+        options.data_base = Some(0x0400);
+        options.bss_base = Some(0x0500);
+
+        load(&object_file([0x00, 0x00]), &options, &mut memory).unwrap();
+
+        assert_eq!(memory.peek(0xC007), 0x04);
+        assert_eq!(memory.peek(0x0401), 0xC0);
+    }
+
+    #[test]
+    fn should_clear_bss_when_requested_by_file() {
+        let mut memory = VecMemory::new();
+        memory.store(&[(0xC00A, 0xFF), (0xC00D, 0xFF), (0xC00E, 0xFF)]);
+
+        load(&object_file([0x00, 0x02]), &options(), &mut memory).unwrap();
+
+        assert_eq!(memory.peek(0xC00A), 0x00);
+        assert_eq!(memory.peek(0xC00D), 0x00);
+        assert_eq!(memory.peek(0xC00E), 0xFF);
+    }
+
+    #[test]
+    fn should_report_unresolved_import() {
+        let mut memory = VecMemory::new();
+
+        let result = load(
+            &object_file([0x00, 0x00]),
+            &Options::new(0xC000),
+            &mut memory,
+        );
+
+        assert_eq!(
+            result.err(),
+            Some(ObjectError::UnresolvedSymbol(String::from("chrout")))
+        );
+    }
+
+    #[test]
+    fn should_reject_page_wise_relocation_to_unaligned_base() {
+        let mut memory = VecMemory::new();
+
+        let result = load(&object_file([0x00, 0x40]), &options(), &mut memory);
+
+        assert!(matches!(result, Err(ObjectError::Unsupported(_))));
+    }
+
+    #[test]
+    fn should_reject_65816_code() {
+        let mut memory = VecMemory::new();
+
+        let result = load(&object_file([0x00, 0x80]), &options(), &mut memory);
+
+        assert!(matches!(result, Err(ObjectError::Unsupported(_))));
+    }
+
+    #[test]
+    fn should_reject_file_with_invalid_magic() {
+        let mut memory = VecMemory::new();
+        let mut bytes = object_file([0x00, 0x00]);
+        bytes[2] = b'x';
+
+        let result = load(&bytes, &options(), &mut memory);
+
+        assert_eq!(result.err(), Some(ObjectError::InvalidMagic));
+    }
+
+    #[test]
+    fn should_report_truncated_file() {
+        let mut memory = VecMemory::new();
+        let bytes = object_file([0x00, 0x00]);
+
+        let result = load(&bytes[..bytes.len() - 3], &options(), &mut memory);
+
+        assert_eq!(result.err(), Some(ObjectError::Truncated));
+    }
+
+    #[test]
+    fn should_reject_segments_past_end_of_address_space() {
+        let mut memory = VecMemory::new();
+
+        let result = load(
+            &object_file([0x00, 0x00]),
+            &Options::new(0xFFFC).with_import("chrout", 0xFFD2),
+            &mut memory,
+        );
+
+        assert_eq!(result.err(), Some(ObjectError::AddressOutOfRange));
+    }
+}
+
+#[cfg(test)]
+mod object {
+    use super::object_file;
+    use crate::loaders::o65::{Object, Relocation, RelocationKind, RelocationTarget, SegmentId};
+
+    #[test]
+    fn should_parse_segments_imports_and_exports() {
+        let object = Object::parse(&object_file([0x00, 0x00])).unwrap();
+
+        assert_eq!(object.text.base, 0x1000);
+        assert_eq!(object.text.bytes.len(), 8);
+        assert_eq!(object.bss.len, 4);
+        assert_eq!(object.imports, vec!["chrout".to_string()]);
+        assert_eq!(
+            object.text.relocations[2],
+            Relocation {
+                offset: 7,
+                kind: RelocationKind::High { low: 0x01 },
+                target: RelocationTarget::Segment(SegmentId::Data),
+            }
+        );
+        assert_eq!(object.exports[1].name, "buffer");
+        assert_eq!(object.exports[1].segment, Some(SegmentId::Bss));
+    }
+
+    #[test]
+    fn should_write_objects_it_can_parse_back() {
+        let object = Object::parse(&object_file([0x00, 0x00])).unwrap();
+
+        assert_eq!(Object::parse(&object.to_bytes()).unwrap(), object);
+    }
+
+    #[test]
+    fn should_write_relocations_far_apart() {
+        let mut object = Object::default();
+        object.text.len = 600;
+        object.text.bytes = vec![0xEA; 600];
+        for offset in [0, 300, 598] {
+            object.text.relocations.push(Relocation {
+                offset,
+                kind: RelocationKind::Word,
+                target: RelocationTarget::Segment(SegmentId::Text),
+            });
+        }
+
+        assert_eq!(Object::parse(&object.to_bytes()).unwrap(), object);
+    }
+
+    #[test]
+    fn should_relocate_against_given_bases_and_imports() {
+        let object = Object::parse(&object_file([0x00, 0x00])).unwrap();
+
+        let (text, data) = object.relocate([0xC000, 0xD000, 0xE000, 0x0080], &[0xFFD2]);
+
+        assert_eq!(text, vec![0xAD, 0x05, 0xC0, 0x20, 0xD2, 0xFF, 0xA9, 0xD0]);
+        assert_eq!(data, vec![0x00, 0xC0]);
+        assert_eq!(
+            object.relocated_exports([0xC000, 0xD000, 0xE000, 0x0080]),
+            vec![
+                ("start".to_string(), 0xC000),
+                ("buffer".to_string(), 0xE000)
+            ]
+        );
+    }
+}
@@ -0,0 +1,31 @@
+use super::{Image, ObjectError};
+use crate::{consts::Word, memory::Bus};
+
+/// Parses a Commodore PRG file: a little-endian load address followed by data.
+pub fn parse(bytes: &[u8]) -> Result<Image, ObjectError> {
+    if bytes.len() < 2 {
+        return Err(ObjectError::Truncated);
+    }
+
+    let address = Word::from_le_bytes([bytes[0], bytes[1]]);
+    let data = &bytes[2..];
+    if usize::from(address) + data.len() > 0x10000 {
+        return Err(ObjectError::AddressOutOfRange);
+    }
+
+    let mut image = Image::default();
+    image.push(address, data.to_vec());
+
+    return Ok(image);
+}
+
+/// Loads a PRG file into memory and returns the loaded image.
+pub fn load(bytes: &[u8], memory: &mut dyn Bus) -> Result<Image, ObjectError> {
+    let image = parse(bytes)?;
+    image.load_into(memory);
+
+    return Ok(image);
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod load {
+    use crate::loaders::{prg::load, ObjectError, Segment};
+    use crate::memory::{Bus, VecMemory};
+
+    #[test]
+    fn should_load_data_at_address_from_header() {
+        let mut memory = VecMemory::new();
+
+        let image = load(&[0x01, 0x08, 0x0B, 0x08, 0x0A], &mut memory).unwrap();
+
+        assert_eq!(
+            image.segments,
+            vec![Segment {
+                address: 0x0801,
+                data: vec![0x0B, 0x08, 0x0A]
+            }]
+        );
+        assert_eq!(memory.peek(0x0801), 0x0B);
+        assert_eq!(memory.peek(0x0803), 0x0A);
+    }
+
+    #[test]
+    fn should_reject_file_without_complete_header() {
+        let mut memory = VecMemory::new();
+
+        assert_eq!(
+            load(&[0x01], &mut memory).err(),
+            Some(ObjectError::Truncated)
+        );
+    }
+
+    #[test]
+    fn should_reject_data_past_end_of_address_space() {
+        let mut memory = VecMemory::new();
+
+        let result = load(&[0xFF, 0xFF, 0x01, 0x02], &mut memory);
+
+        assert_eq!(result.err(), Some(ObjectError::AddressOutOfRange));
+    }
+}
@@ -0,0 +1,96 @@
+use super::{check_span, parse_hex_bytes, to_word, Image, RecordError, RecordErrorKind};
+use crate::consts::Byte;
+
+/// Parses a Motorola S-record file. Header (S0) and count (S5, S6) records
+/// are skipped; S7, S8 and S9 records set the entry point. A missing
+/// termination record is accepted, since several tools omit it.
+pub fn parse(text: &str) -> Result<Image, RecordError> {
+    let mut image = Image::default();
+
+    for (idx, line) in text.lines().enumerate() {
+        let line_number = idx + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let error = |kind| RecordError {
+            line: line_number,
+            kind,
+        };
+        let (record_type, bytes) = parse_record(line).map_err(error)?;
+        let address_len = match record_type {
+            '0' | '1' | '5' | '9' => 2,
+            '2' | '6' | '8' => 3,
+            '3' | '7' => 4,
+            other => {
+                return Err(error(RecordErrorKind::UnsupportedRecordType(format!(
+                    "S{other}"
+                ))))
+            }
+        };
+        if bytes.len() < address_len {
+            return Err(error(RecordErrorKind::LengthMismatch {
+                declared: address_len,
+                actual: bytes.len(),
+            }));
+        }
+
+        let address = bytes[..address_len]
+            .iter()
+            .fold(0u32, |address, byte| (address << 8) | u32::from(*byte));
+        let data = &bytes[address_len..];
+        match record_type {
+            '1' | '2' | '3' => {
+                let address = check_span(address, data.len()).map_err(error)?;
+                image.push(address, data.to_vec());
+            }
+            '7' | '8' | '9' => {
+                image.entry_point = Some(to_word(address).map_err(error)?);
+                return Ok(image);
+            }
+            _ => {}
+        }
+    }
+
+    return Ok(image);
+}
+
+/// Returns record type digit and the address and data bytes of a record.
+fn parse_record(line: &str) -> Result<(char, Vec<Byte>), RecordErrorKind> {
+    let mut chars = line.chars();
+    if chars.next() != Some('S') {
+        return Err(RecordErrorKind::MissingStartCode);
+    }
+    let record_type = chars.next().ok_or(RecordErrorKind::InvalidHexDigits)?;
+    let bytes = parse_hex_bytes(chars.as_str()).ok_or(RecordErrorKind::InvalidHexDigits)?;
+    if bytes.len() < 2 {
+        return Err(RecordErrorKind::LengthMismatch {
+            declared: 2,
+            actual: bytes.len(),
+        });
+    }
+
+    let declared = usize::from(bytes[0]);
+    let actual = bytes.len() - 1;
+    if declared != actual {
+        return Err(RecordErrorKind::LengthMismatch { declared, actual });
+    }
+
+    let (payload, checksum) = bytes.split_at(bytes.len() - 1);
+    let sum = payload
+        .iter()
+        .fold(0 as Byte, |sum, byte| sum.wrapping_add(*byte));
+    let expected = !sum;
+    if expected != checksum[0] {
+        return Err(RecordErrorKind::ChecksumMismatch {
+            expected,
+            actual: checksum[0],
+        });
+    }
+
+    return Ok((record_type, payload[1..].to_vec()));
+}
+
+#[cfg(test)]
+mod tests;
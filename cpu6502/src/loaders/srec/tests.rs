@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod parse {
+    use crate::loaders::{srec::parse, RecordError, RecordErrorKind, Segment};
+
+    #[test]
+    fn should_load_data_records_with_all_address_sizes() {
+        let text =
+            "S00600004844521B\nS1061234A9426068\nS206000300A9FF4E\nS30600001000EAFF\nS5030003F9\n";
+
+        let image = parse(text).unwrap();
+
+        assert_eq!(
+            image.segments,
+            vec![
+                Segment {
+                    address: 0x1234,
+                    data: vec![0xA9, 0x42, 0x60]
+                },
+                Segment {
+                    address: 0x0300,
+                    data: vec![0xA9, 0xFF]
+                },
+                Segment {
+                    address: 0x1000,
+                    data: vec![0xEA]
+                },
+            ]
+        );
+        assert_eq!(image.entry_point, None);
+    }
+
+    #[test]
+    fn should_set_entry_point_from_termination_record() {
+        let text = "S1061234A9426068\nS9031234B6\n";
+
+        assert_eq!(parse(text).unwrap().entry_point, Some(0x1234));
+    }
+
+    #[test]
+    fn should_set_entry_point_from_24_bit_termination_record() {
+        let text = "S80400C0003B\n";
+
+        assert_eq!(parse(text).unwrap().entry_point, Some(0xC000));
+    }
+
+    #[test]
+    fn should_report_checksum_mismatch_with_line_number() {
+        let text = "S00600004844521B\nS1061234A9426069\n";
+
+        assert_eq!(
+            parse(text).err(),
+            Some(RecordError {
+                line: 2,
+                kind: RecordErrorKind::ChecksumMismatch {
+                    expected: 0x68,
+                    actual: 0x69
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn should_report_data_outside_of_address_space() {
+        let text = "S205010000EA0F\n";
+
+        assert_eq!(
+            parse(text).err(),
+            Some(RecordError {
+                line: 1,
+                kind: RecordErrorKind::AddressOutOfRange(0x10000)
+            })
+        );
+    }
+
+    #[test]
+    fn should_report_unsupported_record_type() {
+        let text = "S4031234B6\n";
+
+        assert_eq!(
+            parse(text).err().map(|err| err.kind),
+            Some(RecordErrorKind::UnsupportedRecordType(String::from("S4")))
+        );
+    }
+
+    #[test]
+    fn should_report_invalid_hex_digits() {
+        let text = "S1061234A94260GG\n";
+
+        assert_eq!(
+            parse(text).err().map(|err| err.kind),
+            Some(RecordErrorKind::InvalidHexDigits)
+        );
+    }
+}
@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod image {
+    use crate::loaders::{Image, Segment};
+    use crate::memory::{Bus, VecMemory};
+
+    fn image() -> Image {
+        return Image {
+            segments: vec![
+                Segment {
+                    address: 0x1234,
+                    data: vec![0xA9, 0x42],
+                },
+                Segment {
+                    address: 0xFFFC,
+                    data: vec![0x34, 0x12],
+                },
+            ],
+            entry_point: Some(0x1234),
+        };
+    }
+
+    #[test]
+    fn should_write_all_segments_into_memory() {
+        let mut memory = VecMemory::new();
+
+        image().load_into(&mut memory);
+
+        assert_eq!(memory.peek(0x1234), 0xA9);
+        assert_eq!(memory.peek(0x1235), 0x42);
+        assert_eq!(memory.peek(0xFFFD), 0x12);
+    }
+
+    #[test]
+    fn should_flatten_segments_into_address_value_pairs() {
+        assert_eq!(
+            image().to_pairs(),
+            vec![
+                (0x1234, 0xA9),
+                (0x1235, 0x42),
+                (0xFFFC, 0x34),
+                (0xFFFD, 0x12)
+            ]
+        );
+    }
+}
@@ -1,24 +1,178 @@
-use crate::memory::VecMemory;
+use std::{io, path::Path};
+
+use crate::consts::{Byte, Word};
+use crate::cpu::{CpuState, StopReason};
+use crate::loaders::Image;
+use crate::memory::binary::{self, BinaryError, WrapPolicy};
+use crate::memory::{Bus, VecMemory};
 
 use super::cpu::CPU;
+use pacing::{Pacer, PacingError, PacingReport};
+
+pub mod builder;
+pub mod description;
+pub mod pacing;
+
+pub const DEFAULT_CLOCK_HZ: f64 = 1_000_000.0;
+
+/// Where execution starts after a reset.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ResetBehaviour {
+    /// Start executing the instruction stored at `$FFFC`.
+    ExecuteFromFffc,
+    /// Jump to the address stored in the reset vector at `$FFFC-$FFFD`.
+    ResetVector,
+    /// Jump to a fixed address, ignoring memory contents.
+    EntryPoint(Word),
+}
 
 pub struct Machine {
     cpu: CPU,
+    clock_hz: f64,
+    reset_behaviour: ResetBehaviour,
 }
 
 impl Machine {
     pub fn new() -> Self {
         return Machine {
             cpu: CPU::new(Box::new(VecMemory::new())),
+            clock_hz: DEFAULT_CLOCK_HZ,
+            reset_behaviour: ResetBehaviour::ExecuteFromFffc,
         };
     }
 
-    pub fn execute_cycles(&mut self, program: &[(u16, u8)], cycles: u64) {
-        self.cpu.set_memory(Box::new(VecMemory::from(program)));
+    /// Writes the program into the current memory, leaving CPU state untouched.
+    pub fn load(&mut self, program: &[(Word, Byte)]) {
+        let memory = self.cpu.memory_mut();
+        for (address, value) in program {
+            memory.write(*address, *value);
+        }
+    }
+
+    /// Loads all segments of the image and jumps to its entry point, if it has one.
+    pub fn load_image(&mut self, image: &Image) {
+        image.load_into(self.cpu.memory_mut());
+        if let Some(entry_point) = image.entry_point {
+            self.cpu.set_program_counter(entry_point);
+        }
+    }
+
+    /// Continues execution from the current state for at least `cycles` cycles,
+    /// unless stopped early. Returns the cycle counter after execution, which is
+    /// past the requested cycles when the last instruction overshoots them.
+    pub fn run_cycles(&mut self, cycles: u64) -> u64 {
         self.cpu.execute(cycles);
+        return self.cpu.state().cycle;
+    }
+
+    /// Executes a single instruction and returns the number of cycles it took.
+    pub fn step(&mut self) -> u64 {
+        return self.cpu.step();
+    }
+
+    /// Like `run_cycles`, but paced against the host clock at the machine clock
+    /// rate multiplied by `speed`. See [`Pacer`] for finer control.
+    pub fn run_paced(&mut self, cycles: u64, speed: f64) -> Result<PacingReport, PacingError> {
+        let mut pacer = Pacer::new().with_speed(speed)?;
+        return Ok(pacer.run(self, cycles));
+    }
+
+    /// Executes up to `count` instructions, unless stopped early.
+    /// Returns the number of instructions executed.
+    pub fn run_instructions(&mut self, count: u64) -> u64 {
+        for executed in 0..count {
+            self.cpu.step();
+            match self.cpu.stop_reason() {
+                Some(StopReason::IllegalOpcode { .. } | StopReason::Breakpoint(_)) => {
+                    return executed;
+                }
+                Some(_) => return executed + 1,
+                None => (),
+            }
+        }
+
+        return count;
+    }
+
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        return self.cpu.stop_reason();
     }
 
     pub fn reset(&mut self) {
         self.cpu.reset();
+        match self.reset_behaviour {
+            ResetBehaviour::ExecuteFromFffc => (),
+            ResetBehaviour::ResetVector => {
+                let memory = self.cpu.memory();
+                let address = Word::from_le_bytes([memory.peek(0xFFFC), memory.peek(0xFFFD)]);
+                self.cpu.set_program_counter(address);
+            }
+            ResetBehaviour::EntryPoint(address) => self.cpu.set_program_counter(address),
+        }
+    }
+
+    pub fn clock_hz(&self) -> f64 {
+        return self.clock_hz;
+    }
+
+    pub fn reset_behaviour(&self) -> ResetBehaviour {
+        return self.reset_behaviour;
+    }
+
+    pub fn registers(&self) -> CpuState {
+        return self.cpu.state();
+    }
+
+    pub fn set_registers(&mut self, state: CpuState) {
+        self.cpu.set_state(state);
+    }
+
+    pub fn cpu(&self) -> &CPU {
+        return &self.cpu;
+    }
+
+    pub fn cpu_mut(&mut self) -> &mut CPU {
+        return &mut self.cpu;
+    }
+
+    pub fn memory(&self) -> &dyn Bus {
+        return self.cpu.memory();
+    }
+
+    pub fn memory_mut(&mut self) -> &mut dyn Bus {
+        return self.cpu.memory_mut();
+    }
+
+    pub fn load_binary(
+        &mut self,
+        origin: Word,
+        data: &[Byte],
+        wrap: WrapPolicy,
+    ) -> Result<usize, BinaryError> {
+        return binary::load(self.cpu.memory_mut(), origin, data, wrap);
+    }
+
+    pub fn load_binary_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        origin: Word,
+        wrap: WrapPolicy,
+    ) -> Result<usize, BinaryError> {
+        return binary::load_file(self.cpu.memory_mut(), path, origin, wrap);
+    }
+
+    pub fn dump(&self, start: Word, end: Word) -> Vec<Byte> {
+        return binary::dump(self.cpu.memory(), start, end);
+    }
+
+    pub fn dump_to_file<P: AsRef<Path>>(&self, start: Word, end: Word, path: P) -> io::Result<()> {
+        return binary::dump_to_file(self.cpu.memory(), start, end, path);
+    }
+
+    pub fn hexdump(&self, start: Word, end: Word, writer: &mut dyn io::Write) -> io::Result<()> {
+        return binary::hexdump(self.cpu.memory(), start, end, writer);
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,176 @@
+use std::fmt;
+
+use super::{Machine, ResetBehaviour, DEFAULT_CLOCK_HZ};
+use crate::{
+    consts::Word,
+    cpu::{CpuVariant, CPU},
+    memory::{
+        decoder::{AddressDecoderBuilder, DecoderError},
+        rom::Rom,
+        Bus, InterruptLine, VecMemory,
+    },
+};
+
+const RESET_VECTOR: [Word; 2] = [0xFFFC, 0xFFFD];
+
+#[derive(Debug, PartialEq)]
+pub enum MachineError {
+    InvalidClock(f64),
+    Memory(DecoderError),
+    ResetVectorUnmapped,
+    /// Nothing is mapped at the address execution starts from.
+    StartUnmapped(Word),
+}
+
+impl fmt::Display for MachineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            MachineError::InvalidClock(clock_hz) => {
+                write!(f, "clock frequency of {clock_hz} Hz is not valid")
+            }
+            MachineError::Memory(err) => write!(f, "invalid memory map: {err}"),
+            MachineError::ResetVectorUnmapped => {
+                write!(f, "reset vector at $FFFC-$FFFD is not mapped")
+            }
+            MachineError::StartUnmapped(address) => {
+                write!(f, "start address ${address:04X} is not mapped")
+            }
+        };
+    }
+}
+
+impl std::error::Error for MachineError {}
+
+impl From<DecoderError> for MachineError {
+    fn from(err: DecoderError) -> Self {
+        return MachineError::Memory(err);
+    }
+}
+
+/// Configures and validates a [`Machine`].
+///
+/// Without any mapped region the machine gets a flat 64 KB of RAM, otherwise
+/// only the mapped regions respond. `build` resets the machine, so with
+/// [`ResetBehaviour::ResetVector`] the vector has to be present by then.
+pub struct MachineBuilder {
+    variant: CpuVariant,
+    clock_hz: f64,
+    reset_behaviour: ResetBehaviour,
+    memory: AddressDecoderBuilder,
+}
+
+impl MachineBuilder {
+    pub fn new() -> Self {
+        return MachineBuilder {
+            variant: CpuVariant::Nmos6502,
+            clock_hz: DEFAULT_CLOCK_HZ,
+            reset_behaviour: ResetBehaviour::ExecuteFromFffc,
+            memory: AddressDecoderBuilder::new(),
+        };
+    }
+
+    pub fn cpu(mut self, variant: CpuVariant) -> Self {
+        self.variant = variant;
+        return self;
+    }
+
+    pub fn clock_hz(mut self, clock_hz: f64) -> Self {
+        self.clock_hz = clock_hz;
+        return self;
+    }
+
+    pub fn reset_behaviour(mut self, reset_behaviour: ResetBehaviour) -> Self {
+        self.reset_behaviour = reset_behaviour;
+        return self;
+    }
+
+    pub fn ram(self, start: Word, end: Word) -> Self {
+        let size = usize::from(end.wrapping_sub(start)) + 1;
+        return self.map(start, end, Box::new(VecMemory::with_size(size)));
+    }
+
+    pub fn rom(self, start: Word, end: Word, rom: Rom) -> Self {
+        return self.map(start, end, Box::new(rom));
+    }
+
+    /// Attaches a device or memory to `start..=end`, see [`AddressDecoderBuilder::map`].
+    pub fn map(self, start: Word, end: Word, handler: Box<dyn Bus>) -> Self {
+        return self.map_mirrored(start, end, 0xFFFF, handler);
+    }
+
+    pub fn map_mirrored(
+        mut self,
+        start: Word,
+        end: Word,
+        mask: Word,
+        handler: Box<dyn Bus>,
+    ) -> Self {
+        self.memory = self.memory.map_mirrored(start, end, mask, handler);
+        return self;
+    }
+
+    /// Attaches a device whose interrupt output drives `line` of the CPU.
+    pub fn map_interrupting(
+        mut self,
+        start: Word,
+        end: Word,
+        line: InterruptLine,
+        handler: Box<dyn Bus>,
+    ) -> Self {
+        self.memory = self.memory.map_interrupting(start, end, line, handler);
+        return self;
+    }
+
+    pub fn build(self) -> Result<Machine, MachineError> {
+        if !self.clock_hz.is_finite() || self.clock_hz <= 0.0 {
+            return Err(MachineError::InvalidClock(self.clock_hz));
+        }
+
+        let memory: Box<dyn Bus> = if self.memory.is_empty() {
+            Box::new(VecMemory::new())
+        } else {
+            let decoder = self.memory.build()?;
+
+            let ranges = decoder.mapped_ranges();
+            let mapped = |addr: Word| {
+                ranges
+                    .iter()
+                    .any(|(start, end)| addr >= *start && addr <= *end)
+            };
+            match self.reset_behaviour {
+                ResetBehaviour::ExecuteFromFffc if !mapped(RESET_VECTOR[0]) => {
+                    return Err(MachineError::StartUnmapped(RESET_VECTOR[0]));
+                }
+                ResetBehaviour::ResetVector if !RESET_VECTOR.iter().all(|addr| mapped(*addr)) => {
+                    return Err(MachineError::ResetVectorUnmapped);
+                }
+                ResetBehaviour::EntryPoint(address) if !mapped(address) => {
+                    return Err(MachineError::StartUnmapped(address));
+                }
+                _ => (),
+            }
+
+            Box::new(decoder)
+        };
+
+        let mut cpu = CPU::new(memory);
+        cpu.set_variant(self.variant);
+        let mut machine = Machine {
+            cpu,
+            clock_hz: self.clock_hz,
+            reset_behaviour: self.reset_behaviour,
+        };
+        machine.reset();
+
+        return Ok(machine);
+    }
+}
+
+impl Default for MachineBuilder {
+    fn default() -> Self {
+        return MachineBuilder::new();
+    }
+}
+
+#[cfg(test)]
+mod tests;
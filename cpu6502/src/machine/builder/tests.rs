@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod build {
+    use crate::cpu::CpuVariant;
+    use crate::machine::{
+        builder::{MachineBuilder, MachineError},
+        ResetBehaviour, DEFAULT_CLOCK_HZ,
+    };
+    use crate::memory::{decoder::DecoderError, rom::Rom};
+
+    #[test]
+    fn should_default_to_nmos_cpu_with_flat_ram() {
+        let uut = MachineBuilder::new().build().unwrap();
+
+        assert_eq!(uut.cpu().variant(), CpuVariant::Nmos6502);
+        assert_eq!(uut.clock_hz(), DEFAULT_CLOCK_HZ);
+        assert_eq!(uut.registers().program_counter, 0xFFFC);
+    }
+
+    #[test]
+    fn should_use_selected_cpu_variant_and_clock() {
+        let uut = MachineBuilder::new()
+            .cpu(CpuVariant::Cmos65C02)
+            .clock_hz(1_789_773.0)
+            .build()
+            .unwrap();
+
+        assert_eq!(uut.cpu().variant(), CpuVariant::Cmos65C02);
+        assert_eq!(uut.clock_hz(), 1_789_773.0);
+    }
+
+    #[test]
+    fn should_reject_clock_which_is_not_positive() {
+        let result = MachineBuilder::new().clock_hz(0.0).build();
+
+        assert_eq!(result.err(), Some(MachineError::InvalidClock(0.0)));
+    }
+
+    #[test]
+    fn should_reject_overlapping_regions() {
+        let result = MachineBuilder::new()
+            .ram(0x0000, 0x7FFF)
+            .rom(0x7000, 0xFFFF, Rom::new(&[0x00; 0x9000]))
+            .build();
+
+        assert_eq!(
+            result.err(),
+            Some(MachineError::Memory(DecoderError::Overlap {
+                first: (0x0000, 0x7FFF),
+                second: (0x7000, 0xFFFF)
+            }))
+        );
+    }
+
+    #[test]
+    fn should_require_reset_vector_to_be_mapped_when_jumping_through_it() {
+        let result = MachineBuilder::new()
+            .ram(0x0000, 0x7FFF)
+            .reset_behaviour(ResetBehaviour::ResetVector)
+            .build();
+
+        assert_eq!(result.err(), Some(MachineError::ResetVectorUnmapped));
+    }
+
+    #[test]
+    fn should_require_start_address_to_be_mapped() {
+        let execute_from_fffc = MachineBuilder::new().ram(0x0000, 0x7FFF).build();
+        let entry_point = MachineBuilder::new()
+            .ram(0x0000, 0x7FFF)
+            .reset_behaviour(ResetBehaviour::EntryPoint(0x8000))
+            .build();
+
+        assert_eq!(
+            execute_from_fffc.err(),
+            Some(MachineError::StartUnmapped(0xFFFC))
+        );
+        assert_eq!(entry_point.err(), Some(MachineError::StartUnmapped(0x8000)));
+    }
+
+    #[test]
+    fn should_start_at_address_from_reset_vector() {
+        let mut rom = vec![0xEA; 0x1000];
+        rom[0x0FFC] = 0x00;
+        rom[0x0FFD] = 0xF8;
+
+        let uut = MachineBuilder::new()
+            .ram(0x0000, 0x7FFF)
+            .rom(0xF000, 0xFFFF, Rom::new(&rom))
+            .reset_behaviour(ResetBehaviour::ResetVector)
+            .build()
+            .unwrap();
+
+        assert_eq!(uut.registers().program_counter, 0xF800);
+    }
+
+    #[test]
+    fn should_start_at_fixed_entry_point() {
+        let uut = MachineBuilder::new()
+            .reset_behaviour(ResetBehaviour::EntryPoint(0x0400))
+            .build()
+            .unwrap();
+
+        assert_eq!(uut.registers().program_counter, 0x0400);
+    }
+}
+
+#[cfg(test)]
+mod memory_map {
+    use crate::machine::builder::MachineBuilder;
+    use crate::memory::{decoder::OPEN_BUS_VALUE, rom::Rom};
+
+    #[test]
+    fn should_route_accesses_to_attached_regions() {
+        let mut uut = MachineBuilder::new()
+            .ram(0x0000, 0x07FF)
+            .rom(0xFF00, 0xFFFF, Rom::new(&[0x42; 0x0100]))
+            .build()
+            .unwrap();
+
+        uut.memory_mut().write(0x0100, 0x11);
+        uut.memory_mut().write(0xFF00, 0x11);
+
+        assert_eq!(uut.memory().peek(0x0100), 0x11);
+        assert_eq!(uut.memory().peek(0xFF00), 0x42);
+        assert_eq!(uut.memory().peek(0x4000), OPEN_BUS_VALUE);
+    }
+}
+
+#[cfg(test)]
+mod interrupts {
+    use crate::cpu::CpuState;
+    use crate::devices::timer::{Timer, CONTROL_ENABLE, CONTROL_IRQ_ENABLE, REGISTER_CONTROL};
+    use crate::machine::{builder::MachineBuilder, ResetBehaviour};
+    use crate::memory::{rom::Rom, Bus, InterruptLine};
+
+    #[test]
+    fn should_run_irq_handler_when_wired_device_interrupts() {
+        let mut rom = vec![0x00; 0x0100];
+        rom[0x00] = 0x58; // CLI
+        rom[0x01] = 0x4C; // JMP $FF01
+        rom[0x02] = 0x01;
+        rom[0x03] = 0xFF;
+        rom[0x10] = 0xA9; // LDA #$42
+        rom[0x11] = 0x42;
+        rom[0x12] = 0x4C; // JMP $FF12
+        rom[0x13] = 0x12;
+        rom[0x14] = 0xFF;
+        rom[0xFE] = 0x10; // IRQ vector -> $FF10
+        rom[0xFF] = 0xFF;
+        let mut timer = Timer::new().with_reload(20);
+        timer.write(REGISTER_CONTROL, CONTROL_ENABLE | CONTROL_IRQ_ENABLE);
+
+        let mut uut = MachineBuilder::new()
+            .ram(0x0000, 0x07FF)
+            .map_interrupting(0xD000, 0xD003, InterruptLine::Irq, Box::new(timer))
+            .rom(0xFF00, 0xFFFF, Rom::new(&rom))
+            .reset_behaviour(ResetBehaviour::EntryPoint(0xFF00))
+            .build()
+            .unwrap();
+        uut.set_registers(CpuState {
+            stack_pointer: 0xFF,
+            ..uut.registers()
+        });
+
+        uut.run_cycles(40);
+
+        assert_eq!(uut.registers().accumulator, 0x42);
+    }
+}
@@ -0,0 +1,425 @@
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    builder::{MachineBuilder, MachineError},
+    Machine, ResetBehaviour, DEFAULT_CLOCK_HZ,
+};
+pub use crate::ini::parse_number;
+
+use crate::{
+    consts::Word,
+    cpu::CpuVariant,
+    devices::{console::Console, timer::Timer},
+    ini::{parse_address, parse_bool, split_sections, Section, SyntaxError},
+    memory::{rom::Rom, Bus, InterruptLine, VecMemory},
+};
+
+/// Options of a `[device]` section other than `type`, `start`, `end` and `irq`.
+pub type DeviceOptions = HashMap<String, String>;
+
+pub type DeviceFactory = Box<dyn Fn(&DeviceOptions) -> Result<Box<dyn Bus>, String>>;
+
+#[derive(Debug)]
+pub enum DescriptionError {
+    Syntax { line: usize, message: String },
+    Io { path: PathBuf, error: io::Error },
+    Device { line: usize, message: String },
+    Machine(MachineError),
+}
+
+impl fmt::Display for DescriptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            DescriptionError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            DescriptionError::Io { path, error } => {
+                write!(f, "could not read {}: {error}", path.display())
+            }
+            DescriptionError::Device { line, message } => {
+                write!(f, "line {line}: invalid device: {message}")
+            }
+            DescriptionError::Machine(err) => write!(f, "{err}"),
+        };
+    }
+}
+
+impl std::error::Error for DescriptionError {}
+
+impl From<SyntaxError> for DescriptionError {
+    fn from(err: SyntaxError) -> Self {
+        return DescriptionError::Syntax {
+            line: err.line,
+            message: err.message,
+        };
+    }
+}
+
+impl From<MachineError> for DescriptionError {
+    fn from(err: MachineError) -> Self {
+        return DescriptionError::Machine(err);
+    }
+}
+
+/// What is attached to a region of the address space.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RegionKind {
+    Ram,
+    Rom {
+        image: PathBuf,
+        mirrored: bool,
+    },
+    Device {
+        device_type: String,
+        interrupt: Option<InterruptLine>,
+        options: DeviceOptions,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegionDescription {
+    /// Line of the section header, used when reporting errors found while building.
+    pub line: usize,
+    pub start: Word,
+    pub end: Word,
+    pub mask: Word,
+    pub kind: RegionKind,
+}
+
+/// Board described in a text file, to be turned into a [`Machine`].
+///
+/// The format is INI-like: `key = value` pairs grouped in sections, with
+/// `#` or `;` starting a comment line. Numbers are decimal, or hexadecimal
+/// with a `$` or `0x` prefix. `[machine]` may appear once, every `[ram]`,
+/// `[rom]` and `[device]` section maps one region:
+///
+/// ```text
+/// [machine]
+/// cpu = 65c02            # 6502 or 65c02
+/// clock = 1.79 MHz       # Hz, kHz or MHz
+/// reset = vector         # fffc, vector or an entry point address
+///
+/// [ram]
+/// start = $0000
+/// end = $1FFF
+/// mask = $07FF           # 2 KB mirrored over the region
+///
+/// [rom]
+/// start = $F000
+/// end = $FFFF
+/// image = monitor.bin    # relative to the description file
+/// mirrored = true
+///
+/// [device]
+/// type = timer
+/// start = $D000
+/// end = $D003
+/// irq = irq              # irq or nmi, not wired when omitted
+/// reload = 1000          # remaining keys are passed to the device
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct MachineDescription {
+    pub variant: CpuVariant,
+    pub clock_hz: f64,
+    pub reset_behaviour: ResetBehaviour,
+    pub regions: Vec<RegionDescription>,
+}
+
+fn syntax_error(line: usize, message: String) -> DescriptionError {
+    return DescriptionError::Syntax { line, message };
+}
+
+impl MachineDescription {
+    /// Parses a description, resolving relative image paths against `base_dir`.
+    pub fn parse(text: &str, base_dir: &Path) -> Result<Self, DescriptionError> {
+        let mut description = MachineDescription {
+            variant: CpuVariant::Nmos6502,
+            clock_hz: DEFAULT_CLOCK_HZ,
+            reset_behaviour: ResetBehaviour::ExecuteFromFffc,
+            regions: vec![],
+        };
+
+        let mut machine_section_seen = false;
+        for mut section in split_sections(text)? {
+            match section.name.as_str() {
+                "machine" => {
+                    if machine_section_seen {
+                        return Err(syntax_error(
+                            section.line,
+                            "[machine] section repeated".to_string(),
+                        ));
+                    }
+                    machine_section_seen = true;
+                    description.parse_machine_section(&mut section)?;
+                }
+                "ram" | "rom" | "device" => {
+                    let region = parse_region_section(&mut section, base_dir)?;
+                    description.regions.push(region);
+                }
+                name => {
+                    return Err(syntax_error(
+                        section.line,
+                        format!("unknown section [{name}]"),
+                    ))
+                }
+            }
+            section.reject_remaining()?;
+        }
+
+        return Ok(description);
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, DescriptionError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|error| DescriptionError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+
+        return MachineDescription::parse(&text, base_dir);
+    }
+
+    fn parse_machine_section(&mut self, section: &mut Section) -> Result<(), DescriptionError> {
+        if let Some((line, value)) = section.take("cpu") {
+            self.variant = CpuVariant::from_name(&value)
+                .ok_or_else(|| syntax_error(line, format!("unknown cpu \"{value}\"")))?;
+        }
+        if let Some((line, value)) = section.take("clock") {
+            self.clock_hz = parse_clock(&value)
+                .ok_or_else(|| syntax_error(line, format!("invalid clock \"{value}\"")))?;
+        }
+        if let Some((line, value)) = section.take("reset") {
+            self.reset_behaviour = match value.to_ascii_lowercase().as_str() {
+                "fffc" => ResetBehaviour::ExecuteFromFffc,
+                "vector" => ResetBehaviour::ResetVector,
+                _ => ResetBehaviour::EntryPoint(parse_address(line, &value)?),
+            };
+        }
+
+        return Ok(());
+    }
+
+    /// Instantiates the machine, reading ROM images and creating devices through `registry`.
+    pub fn build(&self, registry: &DeviceRegistry) -> Result<Machine, DescriptionError> {
+        let mut builder = MachineBuilder::new()
+            .cpu(self.variant)
+            .clock_hz(self.clock_hz)
+            .reset_behaviour(self.reset_behaviour);
+
+        for region in &self.regions {
+            builder = match &region.kind {
+                RegionKind::Ram => {
+                    let size = usize::from(region.mask.min(region.end - region.start)) + 1;
+                    builder.map_mirrored(
+                        region.start,
+                        region.end,
+                        region.mask,
+                        Box::new(VecMemory::with_size(size)),
+                    )
+                }
+                RegionKind::Rom { image, mirrored } => {
+                    let mut rom = Rom::from_file(image).map_err(|error| DescriptionError::Io {
+                        path: image.clone(),
+                        error,
+                    })?;
+                    if *mirrored {
+                        rom = rom.mirrored();
+                    }
+                    builder.rom(region.start, region.end, rom)
+                }
+                RegionKind::Device {
+                    device_type,
+                    interrupt,
+                    options,
+                } => {
+                    let device = registry.create(device_type, options).map_err(|message| {
+                        DescriptionError::Device {
+                            line: region.line,
+                            message,
+                        }
+                    })?;
+                    match interrupt {
+                        Some(line) => {
+                            builder.map_interrupting(region.start, region.end, *line, device)
+                        }
+                        None => builder.map(region.start, region.end, device),
+                    }
+                }
+            };
+        }
+
+        return Ok(builder.build()?);
+    }
+}
+
+fn parse_region_section(
+    section: &mut Section,
+    base_dir: &Path,
+) -> Result<RegionDescription, DescriptionError> {
+    let start = section.take_address("start")?;
+    let end = section.take_address("end")?;
+    if start > end {
+        return Err(syntax_error(
+            section.line,
+            format!("region ${start:04X}-${end:04X} starts after its end"),
+        ));
+    }
+
+    let mut mask = 0xFFFF;
+    let kind = match section.name.as_str() {
+        "ram" => {
+            if let Some((line, value)) = section.take("mask") {
+                mask = parse_address(line, &value)?;
+            }
+            RegionKind::Ram
+        }
+        "rom" => {
+            let (_, image) = section.take_required("image")?;
+            let mirrored = match section.take("mirrored") {
+                Some((line, value)) => parse_bool(line, &value)?,
+                None => false,
+            };
+            RegionKind::Rom {
+                image: base_dir.join(image),
+                mirrored,
+            }
+        }
+        _ => {
+            let (_, device_type) = section.take_required("type")?;
+            let interrupt = match section.take("irq") {
+                Some((line, value)) => Some(parse_interrupt_line(line, &value)?),
+                None => None,
+            };
+            let options = section
+                .entries
+                .drain(..)
+                .map(|(_, key, value)| (key, value))
+                .collect();
+            RegionKind::Device {
+                device_type: device_type.to_ascii_lowercase(),
+                interrupt,
+                options,
+            }
+        }
+    };
+
+    return Ok(RegionDescription {
+        line: section.line,
+        start,
+        end,
+        mask,
+        kind,
+    });
+}
+
+fn parse_interrupt_line(line: usize, text: &str) -> Result<InterruptLine, DescriptionError> {
+    return match text.to_ascii_lowercase().as_str() {
+        "irq" => Ok(InterruptLine::Irq),
+        "nmi" => Ok(InterruptLine::Nmi),
+        _ => Err(syntax_error(
+            line,
+            format!("unknown interrupt line \"{text}\""),
+        )),
+    };
+}
+
+/// Parses a frequency in Hz, optionally followed by a `Hz`, `kHz` or `MHz` unit.
+fn parse_clock(text: &str) -> Option<f64> {
+    let lowercase = text.trim().to_ascii_lowercase();
+    let (number, multiplier) = if let Some(number) = lowercase.strip_suffix("mhz") {
+        (number, 1_000_000.0)
+    } else if let Some(number) = lowercase.strip_suffix("khz") {
+        (number, 1_000.0)
+    } else if let Some(number) = lowercase.strip_suffix("hz") {
+        (number, 1.0)
+    } else {
+        (lowercase.as_str(), 1.0)
+    };
+
+    return number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .map(|value| value * multiplier);
+}
+
+/// Creates devices named by the `type` key of `[device]` sections.
+pub struct DeviceRegistry {
+    factories: HashMap<String, DeviceFactory>,
+}
+
+impl DeviceRegistry {
+    pub fn new() -> Self {
+        return DeviceRegistry {
+            factories: HashMap::new(),
+        };
+    }
+
+    /// Registry knowing the devices of this crate: `timer` (option `reload`)
+    /// and `console`, which prints to the standard output.
+    pub fn with_builtin_devices() -> Self {
+        return DeviceRegistry::new()
+            .register("timer", Box::new(create_timer))
+            .register("console", Box::new(create_console));
+    }
+
+    /// Makes `console` devices print to the standard error instead, keeping the
+    /// standard output free for machine-readable reports.
+    pub fn with_console_on_stderr(self) -> Self {
+        return self.register("console", Box::new(create_stderr_console));
+    }
+
+    pub fn register(mut self, name: &str, factory: DeviceFactory) -> Self {
+        self.factories.insert(name.to_ascii_lowercase(), factory);
+        return self;
+    }
+
+    pub fn create(&self, name: &str, options: &DeviceOptions) -> Result<Box<dyn Bus>, String> {
+        return match self.factories.get(name) {
+            Some(factory) => factory(options),
+            None => Err(format!("unknown device type \"{name}\"")),
+        };
+    }
+}
+
+impl Default for DeviceRegistry {
+    fn default() -> Self {
+        return DeviceRegistry::new();
+    }
+}
+
+fn reject_unknown_options(options: &DeviceOptions, known: &[&str]) -> Result<(), String> {
+    return match options.keys().find(|key| !known.contains(&key.as_str())) {
+        Some(key) => Err(format!("unknown option \"{key}\"")),
+        None => Ok(()),
+    };
+}
+
+fn create_timer(options: &DeviceOptions) -> Result<Box<dyn Bus>, String> {
+    reject_unknown_options(options, &["reload"])?;
+    let mut timer = Timer::new();
+    if let Some(value) = options.get("reload") {
+        let reload = parse_number(value)
+            .and_then(|value| Word::try_from(value).ok())
+            .ok_or_else(|| format!("invalid reload value \"{value}\""))?;
+        timer = timer.with_reload(reload);
+    }
+
+    return Ok(Box::new(timer));
+}
+
+fn create_console(options: &DeviceOptions) -> Result<Box<dyn Bus>, String> {
+    reject_unknown_options(options, &[])?;
+    return Ok(Box::new(Console::new(io::stdout())));
+}
+
+fn create_stderr_console(options: &DeviceOptions) -> Result<Box<dyn Bus>, String> {
+    reject_unknown_options(options, &[])?;
+    return Ok(Box::new(Console::new(io::stderr())));
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,276 @@
+#[cfg(test)]
+mod parse {
+    use crate::cpu::CpuVariant;
+    use crate::machine::description::{DescriptionError, MachineDescription, RegionKind};
+    use crate::machine::{ResetBehaviour, DEFAULT_CLOCK_HZ};
+    use crate::memory::InterruptLine;
+    use std::path::Path;
+
+    const BOARD: &str = "
+# test board
+[machine]
+cpu = 65C02
+clock = 1.79 MHz   ; NTSC-ish
+reset = vector
+
+[ram]
+start = $0000
+end = 0x1FFF
+mask = $07FF
+
+[rom]
+start = $F000
+end = 65535
+image = roms/monitor.bin
+mirrored = yes
+
+[device]
+type = timer
+start = $D000
+end = $D003
+irq = nmi
+reload = 1000
+";
+
+    #[test]
+    fn should_read_machine_section() {
+        let uut = MachineDescription::parse(BOARD, Path::new("boards")).unwrap();
+
+        assert_eq!(uut.variant, CpuVariant::Cmos65C02);
+        assert_eq!(uut.clock_hz, 1_790_000.0);
+        assert_eq!(uut.reset_behaviour, ResetBehaviour::ResetVector);
+    }
+
+    #[test]
+    fn should_read_regions_in_order() {
+        let uut = MachineDescription::parse(BOARD, Path::new("boards")).unwrap();
+
+        assert_eq!(uut.regions.len(), 3);
+        assert_eq!(
+            (
+                uut.regions[0].start,
+                uut.regions[0].end,
+                uut.regions[0].mask
+            ),
+            (0x0000, 0x1FFF, 0x07FF)
+        );
+        assert_eq!(uut.regions[0].kind, RegionKind::Ram);
+        assert_eq!(
+            uut.regions[1].kind,
+            RegionKind::Rom {
+                image: Path::new("boards").join("roms/monitor.bin"),
+                mirrored: true
+            }
+        );
+        assert_eq!(uut.regions[1].end, 0xFFFF);
+    }
+
+    #[test]
+    fn should_pass_remaining_device_keys_as_options() {
+        let uut = MachineDescription::parse(BOARD, Path::new("")).unwrap();
+
+        match &uut.regions[2].kind {
+            RegionKind::Device {
+                device_type,
+                interrupt,
+                options,
+            } => {
+                assert_eq!(device_type, "timer");
+                assert_eq!(*interrupt, Some(InterruptLine::Nmi));
+                assert_eq!(options.len(), 1);
+                assert_eq!(options["reload"], "1000");
+            }
+            kind => panic!("unexpected region {kind:?}"),
+        }
+        assert_eq!(uut.regions[2].line, 19);
+    }
+
+    #[test]
+    fn should_use_builder_defaults_for_missing_machine_section() {
+        let uut =
+            MachineDescription::parse("[ram]\nstart = 0\nend = $FFFF\n", Path::new("")).unwrap();
+
+        assert_eq!(uut.variant, CpuVariant::Nmos6502);
+        assert_eq!(uut.clock_hz, DEFAULT_CLOCK_HZ);
+        assert_eq!(uut.reset_behaviour, ResetBehaviour::ExecuteFromFffc);
+    }
+
+    #[test]
+    fn should_accept_entry_point_as_reset_behaviour_and_plain_clock() {
+        let uut =
+            MachineDescription::parse("[machine]\nreset = $C000\nclock = 2000000\n", Path::new(""))
+                .unwrap();
+
+        assert_eq!(uut.reset_behaviour, ResetBehaviour::EntryPoint(0xC000));
+        assert_eq!(uut.clock_hz, 2_000_000.0);
+    }
+
+    fn syntax_error_line(text: &str) -> usize {
+        return match MachineDescription::parse(text, Path::new("")) {
+            Err(DescriptionError::Syntax { line, .. }) => line,
+            result => panic!("expected syntax error, got {result:?}"),
+        };
+    }
+
+    #[test]
+    fn should_report_line_of_unknown_key() {
+        assert_eq!(syntax_error_line("[machine]\ncpu = 6502\nspeed = 1\n"), 3);
+    }
+
+    #[test]
+    fn should_report_unknown_section() {
+        assert_eq!(syntax_error_line("\n[cartridge]\n"), 2);
+    }
+
+    #[test]
+    fn should_report_missing_required_key_at_section_header() {
+        assert_eq!(syntax_error_line("[ram]\nstart = $0000\n"), 1);
+    }
+
+    #[test]
+    fn should_report_invalid_values() {
+        assert_eq!(syntax_error_line("[machine]\ncpu = z80\n"), 2);
+        assert_eq!(syntax_error_line("[machine]\nclock = fast\n"), 2);
+        assert_eq!(syntax_error_line("[ram]\nstart = $10000\nend = 0\n"), 2);
+        assert_eq!(
+            syntax_error_line("[device]\ntype = timer\nstart = 0\nend = 3\nirq = reset\n"),
+            5
+        );
+    }
+
+    #[test]
+    fn should_report_key_outside_of_section_and_repeated_keys() {
+        assert_eq!(syntax_error_line("cpu = 6502\n"), 1);
+        assert_eq!(syntax_error_line("[ram]\nstart = 0\nstart = 1\n"), 3);
+    }
+
+    #[test]
+    fn should_reject_region_starting_after_its_end() {
+        assert_eq!(syntax_error_line("[ram]\nstart = $2000\nend = $1000\n"), 1);
+    }
+}
+
+#[cfg(test)]
+mod build {
+    use crate::machine::description::{DescriptionError, DeviceRegistry, MachineDescription};
+    use crate::memory::{decoder::OPEN_BUS_VALUE, VecMemory};
+    use std::{env, fs, path::Path};
+
+    #[test]
+    fn should_build_machine_with_rom_image_relative_to_description() {
+        let dir = env::temp_dir().join("cpu6502_description_build_test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("vectors.bin"), [0x00, 0xC0]).unwrap();
+        let path = dir.join("board.ini");
+        fs::write(
+            &path,
+            "[machine]\nreset = vector\n\
+             [ram]\nstart = $0000\nend = $1FFF\nmask = $07FF\n\
+             [rom]\nstart = $FFFC\nend = $FFFF\nimage = vectors.bin\nmirrored = true\n",
+        )
+        .unwrap();
+
+        let description = MachineDescription::from_file(&path).unwrap();
+        let mut uut = description
+            .build(&DeviceRegistry::with_builtin_devices())
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        uut.memory_mut().write(0x0001, 0x42);
+        assert_eq!(uut.memory().peek(0x0801), 0x42);
+        assert_eq!(uut.memory().peek(0xFFFE), 0x00);
+        assert_eq!(uut.memory().peek(0x2000), OPEN_BUS_VALUE);
+        assert_eq!(uut.registers().program_counter, 0xC000);
+    }
+
+    #[test]
+    fn should_report_missing_rom_image() {
+        let description = MachineDescription::parse(
+            "[rom]\nstart = $F000\nend = $FFFF\nimage = missing.bin\n",
+            Path::new("/nonexistent"),
+        )
+        .unwrap();
+
+        let result = description.build(&DeviceRegistry::new());
+
+        assert!(matches!(result, Err(DescriptionError::Io { .. })));
+    }
+
+    #[test]
+    fn should_create_devices_with_registered_factories() {
+        let description = MachineDescription::parse(
+            "[device]\ntype = scratch\nstart = $0200\nend = $02FF\n\
+             [ram]\nstart = $F000\nend = $FFFF\n",
+            Path::new(""),
+        )
+        .unwrap();
+        let registry = DeviceRegistry::new().register(
+            "scratch",
+            Box::new(|_| Ok(Box::new(VecMemory::with_size(0x0100)))),
+        );
+
+        let mut uut = description.build(&registry).unwrap();
+        uut.memory_mut().write(0x0210, 0x42);
+
+        assert_eq!(uut.memory().peek(0x0210), 0x42);
+    }
+
+    #[test]
+    fn should_report_unknown_device_type_and_options() {
+        let registry = DeviceRegistry::with_builtin_devices();
+        let unknown_type = MachineDescription::parse(
+            "[ram]\nstart = 0\nend = $FF\n\n[device]\ntype = sid\nstart = $D400\nend = $D41F\n",
+            Path::new(""),
+        )
+        .unwrap();
+        let unknown_option = MachineDescription::parse(
+            "[device]\ntype = timer\nstart = $D000\nend = $D003\nperiod = 5\n",
+            Path::new(""),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            unknown_type.build(&registry),
+            Err(DescriptionError::Device { line: 5, .. })
+        ));
+        assert!(matches!(
+            unknown_option.build(&registry),
+            Err(DescriptionError::Device { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn should_create_console_printing_to_stderr() {
+        let registry = DeviceRegistry::with_builtin_devices().with_console_on_stderr();
+        let console = MachineDescription::parse(
+            "[device]\ntype = console\nstart = $D000\nend = $D000\n\
+             [ram]\nstart = $F000\nend = $FFFF\n",
+            Path::new(""),
+        )
+        .unwrap();
+        let unknown_option = MachineDescription::parse(
+            "[device]\ntype = console\nstart = $D000\nend = $D000\necho = 1\n",
+            Path::new(""),
+        )
+        .unwrap();
+
+        assert!(console.build(&registry).is_ok());
+        assert!(matches!(
+            unknown_option.build(&registry),
+            Err(DescriptionError::Device { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn should_report_invalid_memory_map() {
+        let description = MachineDescription::parse(
+            "[ram]\nstart = 0\nend = $FF\n[ram]\nstart = $80\nend = $1FF\n",
+            Path::new(""),
+        )
+        .unwrap();
+
+        let result = description.build(&DeviceRegistry::new());
+
+        assert!(matches!(result, Err(DescriptionError::Machine(_))));
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    fmt, thread,
+    time::{Duration, Instant},
+};
+
+use super::Machine;
+
+/// Host time between two synchronisations with the clock.
+pub const DEFAULT_SLICE: Duration = Duration::from_millis(10);
+/// Lag after which the schedule is abandoned instead of trying to catch up.
+pub const DEFAULT_MAX_LAG: Duration = Duration::from_millis(250);
+
+/// Monotonic time source used for pacing, replaceable in tests.
+pub trait Clock {
+    /// Time elapsed since an arbitrary fixed point.
+    fn now(&self) -> Duration;
+    fn sleep(&mut self, duration: Duration);
+}
+
+pub struct HostClock {
+    origin: Instant,
+}
+
+impl HostClock {
+    pub fn new() -> Self {
+        return HostClock {
+            origin: Instant::now(),
+        };
+    }
+}
+
+impl Default for HostClock {
+    fn default() -> Self {
+        return HostClock::new();
+    }
+}
+
+impl Clock for HostClock {
+    fn now(&self) -> Duration {
+        return self.origin.elapsed();
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PacingError {
+    InvalidSpeed(f64),
+    InvalidSlice,
+}
+
+impl fmt::Display for PacingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            PacingError::InvalidSpeed(speed) => write!(f, "speed multiplier {speed} is not valid"),
+            PacingError::InvalidSlice => write!(f, "pacing slice has to be longer than zero"),
+        };
+    }
+}
+
+impl std::error::Error for PacingError {}
+
+/// Summary of a paced run. `drift` is how far behind the schedule the run
+/// ended, `max_drift` the worst lag seen at the end of any slice.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PacingReport {
+    pub cycles: u64,
+    pub elapsed: Duration,
+    pub drift: Duration,
+    pub max_drift: Duration,
+    /// Slices which finished after their deadline.
+    pub late_slices: u64,
+    /// Times the lag exceeded the maximum and the schedule was restarted.
+    pub resyncs: u64,
+}
+
+impl PacingReport {
+    pub fn kept_up(&self) -> bool {
+        return self.late_slices == 0;
+    }
+}
+
+/// Runs a [`Machine`] at its clock rate, scaled by a speed multiplier, against
+/// a monotonic clock.
+///
+/// Execution proceeds in slices of [`DEFAULT_SLICE`] worth of host time; after
+/// each one the pacer sleeps until the host catches up with the emulated time.
+/// When the host is too slow the lag is reported instead, and once it exceeds
+/// the maximum lag the schedule restarts from the current time rather than
+/// running flat out to catch up.
+pub struct Pacer<C: Clock> {
+    clock: C,
+    speed: f64,
+    slice: Duration,
+    max_lag: Duration,
+}
+
+impl Pacer<HostClock> {
+    pub fn new() -> Self {
+        return Pacer::with_clock(HostClock::new());
+    }
+}
+
+impl Default for Pacer<HostClock> {
+    fn default() -> Self {
+        return Pacer::new();
+    }
+}
+
+impl<C: Clock> Pacer<C> {
+    pub fn with_clock(clock: C) -> Self {
+        return Pacer {
+            clock,
+            speed: 1.0,
+            slice: DEFAULT_SLICE,
+            max_lag: DEFAULT_MAX_LAG,
+        };
+    }
+
+    /// Multiplies the clock rate, e.g. 2.0 fast-forwards and 0.5 runs in slow motion.
+    pub fn with_speed(mut self, speed: f64) -> Result<Self, PacingError> {
+        self.set_speed(speed)?;
+        return Ok(self);
+    }
+
+    pub fn with_slice(mut self, slice: Duration) -> Result<Self, PacingError> {
+        if slice.is_zero() {
+            return Err(PacingError::InvalidSlice);
+        }
+        self.slice = slice;
+        return Ok(self);
+    }
+
+    pub fn with_max_lag(mut self, max_lag: Duration) -> Self {
+        self.max_lag = max_lag;
+        return self;
+    }
+
+    pub fn set_speed(&mut self, speed: f64) -> Result<(), PacingError> {
+        if !speed.is_finite() || speed <= 0.0 {
+            return Err(PacingError::InvalidSpeed(speed));
+        }
+        self.speed = speed;
+        return Ok(());
+    }
+
+    pub fn speed(&self) -> f64 {
+        return self.speed;
+    }
+
+    pub fn clock(&self) -> &C {
+        return &self.clock;
+    }
+
+    /// Runs the machine for `cycles` cycles, or until it stops early.
+    pub fn run(&mut self, machine: &mut Machine, cycles: u64) -> PacingReport {
+        let cycles_per_second = machine.clock_hz() * self.speed;
+        let slice_cycles = ((self.slice.as_secs_f64() * cycles_per_second) as u64).max(1);
+        let start_cycle = machine.registers().cycle;
+        let start_time = self.clock.now();
+        let mut origin = (start_time, start_cycle);
+        let mut report = PacingReport::default();
+
+        loop {
+            let cycle = machine.registers().cycle;
+            if cycle - start_cycle >= cycles {
+                break;
+            }
+
+            // A stop left over from an earlier run is cleared by running again.
+            let budget = (cycles - (cycle - start_cycle)).min(slice_cycles);
+            machine.run_cycles(budget);
+            let cycle = machine.registers().cycle;
+
+            let emulated = Duration::from_secs_f64((cycle - origin.1) as f64 / cycles_per_second);
+            let deadline = origin.0 + emulated;
+            let now = self.clock.now();
+            if now <= deadline {
+                self.clock.sleep(deadline - now);
+                report.drift = Duration::ZERO;
+            } else {
+                report.drift = now - deadline;
+                report.max_drift = report.max_drift.max(report.drift);
+                report.late_slices += 1;
+                if report.drift > self.max_lag {
+                    origin = (now, cycle);
+                    report.resyncs += 1;
+                }
+            }
+
+            if machine.stop_reason().is_some() {
+                break;
+            }
+        }
+
+        report.cycles = machine.registers().cycle - start_cycle;
+        report.elapsed = self.clock.now() - start_time;
+
+        return report;
+    }
+}
+
+#[cfg(test)]
+mod tests;
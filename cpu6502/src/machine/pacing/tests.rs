@@ -0,0 +1,164 @@
+use super::Clock;
+use std::{cell::Cell, time::Duration};
+
+/// Clock which advances only when slept on, or by `cost` on every reading to
+/// simulate a host which needs that long to emulate a slice.
+struct FakeClock {
+    now: Cell<Duration>,
+    cost: Duration,
+    slept: Duration,
+}
+
+impl FakeClock {
+    fn new(cost: Duration) -> Self {
+        return FakeClock {
+            now: Cell::new(Duration::ZERO),
+            cost,
+            slept: Duration::ZERO,
+        };
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        self.now.set(self.now.get() + self.cost);
+        return self.now.get();
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.slept += duration;
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+fn looping_machine() -> crate::machine::Machine {
+    let mut machine = crate::machine::Machine::new();
+    machine.load(&[(0x0200, 0xEE), (0x0201, 0x00), (0x0202, 0x03)]); // INC $0300
+    machine.load(&[(0x0203, 0x4C), (0x0204, 0x00), (0x0205, 0x02)]); // JMP $0200
+    machine.cpu_mut().set_program_counter(0x0200);
+
+    return machine;
+}
+
+#[cfg(test)]
+mod run {
+    use super::{looping_machine, FakeClock};
+    use crate::cpu::breakpoints::Breakpoint;
+    use crate::machine::pacing::{Pacer, PacingError};
+    use std::time::Duration;
+
+    #[test]
+    fn should_take_as_long_as_the_emulated_hardware_would() {
+        let mut machine = looping_machine();
+        let mut uut = Pacer::with_clock(FakeClock::new(Duration::ZERO));
+
+        let report = uut.run(&mut machine, 100_000);
+
+        assert!(report.cycles >= 100_000 && report.cycles < 100_010);
+        let expected = report.cycles as f64 / 1_000_000.0;
+        assert!((report.elapsed.as_secs_f64() - expected).abs() < 1e-6);
+        assert!(report.kept_up());
+        assert_eq!(report.drift, Duration::ZERO);
+    }
+
+    #[test]
+    fn should_scale_time_with_speed_multiplier() {
+        let mut machine = looping_machine();
+        let mut uut = Pacer::with_clock(FakeClock::new(Duration::ZERO))
+            .with_speed(4.0)
+            .unwrap();
+
+        let report = uut.run(&mut machine, 100_000);
+
+        let expected = report.cycles as f64 / 4_000_000.0;
+        assert!((report.elapsed.as_secs_f64() - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn should_sleep_in_slices() {
+        let mut machine = looping_machine();
+        let mut uut = Pacer::with_clock(FakeClock::new(Duration::ZERO))
+            .with_slice(Duration::from_millis(20))
+            .unwrap();
+
+        uut.run(&mut machine, 10_000);
+
+        assert!(uut.clock().slept >= Duration::from_millis(10));
+        assert!(uut.clock().slept < Duration::from_millis(11));
+    }
+
+    #[test]
+    fn should_report_drift_when_host_cannot_keep_up() {
+        let mut machine = looping_machine();
+        // every 10 ms slice takes 15 ms of host time
+        let mut uut = Pacer::with_clock(FakeClock::new(Duration::from_millis(15)))
+            .with_max_lag(Duration::from_secs(10));
+
+        let report = uut.run(&mut machine, 50_000);
+
+        assert!(!report.kept_up());
+        assert_eq!(report.late_slices, 5);
+        assert!(report.drift >= Duration::from_millis(24));
+        assert_eq!(report.max_drift, report.drift);
+        assert_eq!(uut.clock().slept, Duration::ZERO);
+        assert_eq!(report.resyncs, 0);
+    }
+
+    #[test]
+    fn should_restart_schedule_once_lag_exceeds_maximum() {
+        let mut machine = looping_machine();
+        let mut uut = Pacer::with_clock(FakeClock::new(Duration::from_millis(100)))
+            .with_max_lag(Duration::from_millis(250));
+
+        let report = uut.run(&mut machine, 100_000);
+
+        assert!(report.resyncs > 0);
+        assert!(report.max_drift < Duration::from_millis(400));
+    }
+
+    #[test]
+    fn should_stop_when_machine_stops() {
+        let mut machine = crate::machine::Machine::new();
+        machine.load(&[(0x0200, 0x02)]);
+        machine.cpu_mut().set_program_counter(0x0200);
+        let mut uut = Pacer::with_clock(FakeClock::new(Duration::ZERO));
+
+        let report = uut.run(&mut machine, 100_000);
+
+        assert_eq!(report.cycles, 0);
+        assert!(machine.stop_reason().is_some());
+    }
+
+    #[test]
+    fn should_resume_after_stopping_at_breakpoint() {
+        let mut machine = looping_machine();
+        machine.cpu_mut().add_breakpoint(Breakpoint::at(0x0203));
+        let mut uut = Pacer::with_clock(FakeClock::new(Duration::ZERO));
+
+        let first = uut.run(&mut machine, 100_000);
+        let second = uut.run(&mut machine, 100_000);
+
+        assert_eq!(first.cycles, 6);
+        assert!(second.cycles > 0);
+        assert_eq!(machine.registers().program_counter, 0x0203);
+        assert_eq!(machine.memory().peek(0x0300), 2);
+    }
+
+    #[test]
+    fn should_reject_invalid_speed_and_slice() {
+        let clock = || FakeClock::new(Duration::ZERO);
+
+        assert_eq!(
+            Pacer::with_clock(clock()).with_speed(0.0).err(),
+            Some(PacingError::InvalidSpeed(0.0))
+        );
+        assert_eq!(
+            Pacer::with_clock(clock()).with_speed(f64::INFINITY).err(),
+            Some(PacingError::InvalidSpeed(f64::INFINITY))
+        );
+        assert_eq!(
+            Pacer::with_clock(clock()).with_slice(Duration::ZERO).err(),
+            Some(PacingError::InvalidSlice)
+        );
+    }
+}
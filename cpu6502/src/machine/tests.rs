@@ -0,0 +1,162 @@
+const PROGRAM: &[(u16, u8)] = &[
+    (0xFFFC, 0x4C), // JMP $1234
+    (0xFFFD, 0x34),
+    (0xFFFE, 0x12),
+    (0x1234, 0xA9), // LDA #$42
+    (0x1235, 0x42),
+    (0x1236, 0x85), // STA $10
+    (0x1237, 0x10),
+    (0x1238, 0xE6), // INC $10
+    (0x1239, 0x10),
+];
+
+#[cfg(test)]
+mod load {
+    use super::PROGRAM;
+    use crate::machine::Machine;
+
+    #[test]
+    fn should_write_program_into_memory_without_running_it() {
+        let mut uut = Machine::new();
+
+        uut.load(PROGRAM);
+
+        assert_eq!(uut.memory().peek(0x1234), 0xA9);
+        assert_eq!(uut.registers().cycle, 0);
+        assert_eq!(uut.registers().program_counter, 0xFFFC);
+    }
+
+    #[test]
+    fn should_jump_to_entry_point_of_loaded_image() {
+        let mut uut = Machine::new();
+        let image = crate::loaders::Image {
+            segments: vec![],
+            entry_point: Some(0x0400),
+        };
+
+        uut.load_image(&image);
+
+        assert_eq!(uut.registers().program_counter, 0x0400);
+    }
+}
+
+#[cfg(test)]
+mod run_cycles {
+    use super::PROGRAM;
+    use crate::machine::Machine;
+
+    #[test]
+    fn should_continue_execution_across_calls() {
+        let mut uut = Machine::new();
+        uut.load(PROGRAM);
+
+        uut.run_cycles(3);
+        assert_eq!(uut.registers().program_counter, 0x1234);
+
+        uut.run_cycles(2);
+        assert_eq!(uut.registers().accumulator, 0x42);
+
+        uut.run_cycles(3);
+        assert_eq!(uut.memory().peek(0x0010), 0x42);
+        assert_eq!(uut.registers().program_counter, 0x1238);
+    }
+
+    #[test]
+    fn should_keep_memory_modified_between_calls() {
+        let mut uut = Machine::new();
+        uut.load(PROGRAM);
+        uut.run_cycles(8);
+
+        uut.memory_mut().write(0x0010, 0x80);
+        uut.run_cycles(5);
+
+        assert_eq!(uut.memory().peek(0x0010), 0x81);
+    }
+
+    #[test]
+    fn should_return_cycle_counter_after_last_instruction() {
+        let mut uut = Machine::new();
+        uut.load(PROGRAM);
+
+        assert_eq!(uut.run_cycles(4), 5);
+        assert_eq!(uut.run_cycles(1), 8);
+    }
+}
+
+#[cfg(test)]
+mod run_instructions {
+    use super::PROGRAM;
+    use crate::cpu::StopReason;
+    use crate::machine::Machine;
+
+    #[test]
+    fn should_execute_requested_number_of_instructions() {
+        let mut uut = Machine::new();
+        uut.load(PROGRAM);
+
+        let executed = uut.run_instructions(3);
+
+        assert_eq!(executed, 3);
+        assert_eq!(uut.registers().program_counter, 0x1238);
+        assert_eq!(uut.memory().peek(0x0010), 0x42);
+    }
+
+    #[test]
+    fn should_stop_at_illegal_opcode_without_counting_it() {
+        let mut uut = Machine::new();
+        uut.load(PROGRAM);
+        uut.load(&[(0x123A, 0x02)]);
+
+        let executed = uut.run_instructions(10);
+
+        assert_eq!(executed, 4);
+        assert_eq!(
+            uut.stop_reason(),
+            Some(StopReason::IllegalOpcode {
+                address: 0x123A,
+                opcode: 0x02
+            })
+        );
+        assert_eq!(uut.registers().program_counter, 0x123A);
+    }
+}
+
+#[cfg(test)]
+mod step {
+    use super::PROGRAM;
+    use crate::machine::Machine;
+
+    #[test]
+    fn should_execute_one_instruction_at_a_time() {
+        let mut uut = Machine::new();
+        uut.load(PROGRAM);
+
+        assert_eq!(uut.step(), 3);
+        assert_eq!(uut.step(), 2);
+
+        let registers = uut.registers();
+        assert_eq!(registers.program_counter, 0x1236);
+        assert_eq!(registers.accumulator, 0x42);
+        assert_eq!(registers.cycle, 5);
+    }
+}
+
+#[cfg(test)]
+mod set_registers {
+    use super::PROGRAM;
+    use crate::machine::Machine;
+
+    #[test]
+    fn should_continue_from_modified_registers() {
+        let mut uut = Machine::new();
+        uut.load(PROGRAM);
+        let mut registers = uut.registers();
+        registers.program_counter = 0x1236;
+        registers.accumulator = 0x07;
+        uut.set_registers(registers);
+
+        uut.step();
+
+        assert_eq!(uut.memory().peek(0x0010), 0x07);
+    }
+}
@@ -1,11 +1,128 @@
 use crate::consts::Word;
 
 use super::consts::Byte;
-use std::ops::{Index, IndexMut};
+use std::{
+    cell::RefCell,
+    io,
+    ops::{Index, IndexMut},
+    path::Path,
+    rc::Rc,
+};
+
+use binary::{BinaryError, WrapPolicy};
+
+pub mod binary;
+pub mod decoder;
+pub mod mapper;
+pub mod rom;
 
 const MAX_MEMORY_KB: usize = 64 * 1024;
 
-pub trait Memory: IndexMut<Word, Output = Byte> + Index<Word, Output = Byte> {}
+/// Kind of access the CPU performs on the bus.
+/// `Fetch` is a read of an opcode, operands and data are fetched with `Read`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AccessKind {
+    Read,
+    Write,
+    Fetch,
+}
+
+/// Interrupt inputs of the CPU a device can be wired to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InterruptLine {
+    Irq,
+    Nmi,
+}
+
+/// Anything that can be attached to the CPU address lines.
+///
+/// `read` and `write` are the accesses performed by the CPU and may have side
+/// effects (e.g. acknowledging an interrupt when a status register is read).
+/// `peek` is used by debuggers and tooling and must never change any state.
+///
+/// Devices counting time receive the cycles of every executed instruction in
+/// `tick` and request an interrupt through `interrupt_asserted`. A device
+/// attached directly to the CPU drives IRQ, decoders route each region to the
+/// line it is wired to.
+pub trait Bus {
+    fn read(&mut self, addr: Word) -> Byte;
+    fn write(&mut self, addr: Word, value: Byte);
+    fn peek(&self, addr: Word) -> Byte;
+
+    fn tick(&mut self, _cycles: u64) {}
+
+    fn interrupt_asserted(&self) -> bool {
+        return false;
+    }
+
+    fn line_asserted(&self, line: InterruptLine) -> bool {
+        return line == InterruptLine::Irq && self.interrupt_asserted();
+    }
+
+    /// Number of addresses from 0 on the bus can serve, `None` if it answers at any address.
+    fn size(&self) -> Option<usize> {
+        return None;
+    }
+}
+
+/// Plain RAM-like storage without any side effects on access.
+/// Every `Memory` is a `Bus` through the blanket implementation below.
+pub trait Memory: IndexMut<Word, Output = Byte> + Index<Word, Output = Byte> {
+    /// Number of addressable bytes, `None` if every address is backed.
+    fn size(&self) -> Option<usize> {
+        return None;
+    }
+}
+
+impl<T: Memory> Bus for T {
+    fn read(&mut self, addr: Word) -> Byte {
+        return self[addr];
+    }
+
+    fn write(&mut self, addr: Word, value: Byte) {
+        self[addr] = value;
+    }
+
+    fn peek(&self, addr: Word) -> Byte {
+        return self[addr];
+    }
+
+    fn size(&self) -> Option<usize> {
+        return Memory::size(self);
+    }
+}
+
+/// Shared handle to a bus, for devices which stay inspectable (e.g. a mapper
+/// queried by a debugger) after being handed to the CPU or a decoder.
+impl<T: Bus> Bus for Rc<RefCell<T>> {
+    fn read(&mut self, addr: Word) -> Byte {
+        return self.borrow_mut().read(addr);
+    }
+
+    fn write(&mut self, addr: Word, value: Byte) {
+        self.borrow_mut().write(addr, value);
+    }
+
+    fn peek(&self, addr: Word) -> Byte {
+        return self.borrow().peek(addr);
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        self.borrow_mut().tick(cycles);
+    }
+
+    fn interrupt_asserted(&self) -> bool {
+        return self.borrow().interrupt_asserted();
+    }
+
+    fn line_asserted(&self, line: InterruptLine) -> bool {
+        return self.borrow().line_asserted(line);
+    }
+
+    fn size(&self) -> Option<usize> {
+        return self.borrow().size();
+    }
+}
 
 pub struct VecMemory {
     pub data: Vec<Byte>,
@@ -18,15 +135,57 @@ impl VecMemory {
         };
     }
 
+    /// Creates RAM smaller than the whole address space, to be mapped into
+    /// a region of an [`decoder::AddressDecoder`].
+    pub fn with_size(size: usize) -> Self {
+        return VecMemory {
+            data: vec![0; size],
+        };
+    }
+
     pub fn store(&mut self, payload: &[(Word, Byte)]) {
         for (address, value) in payload {
             let idx: usize = (*address).into();
             self.data[idx] = *value;
         }
     }
+
+    pub fn load_binary(
+        &mut self,
+        origin: Word,
+        data: &[Byte],
+        wrap: WrapPolicy,
+    ) -> Result<usize, BinaryError> {
+        return binary::load(self, origin, data, wrap);
+    }
+
+    pub fn load_binary_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        origin: Word,
+        wrap: WrapPolicy,
+    ) -> Result<usize, BinaryError> {
+        return binary::load_file(self, path, origin, wrap);
+    }
+
+    pub fn dump(&self, start: Word, end: Word) -> Vec<Byte> {
+        return binary::dump(self, start, end);
+    }
+
+    pub fn dump_to_file<P: AsRef<Path>>(&self, start: Word, end: Word, path: P) -> io::Result<()> {
+        return binary::dump_to_file(self, start, end, path);
+    }
+
+    pub fn hexdump(&self, start: Word, end: Word, writer: &mut dyn io::Write) -> io::Result<()> {
+        return binary::hexdump(self, start, end, writer);
+    }
 }
 
-impl Memory for VecMemory {}
+impl Memory for VecMemory {
+    fn size(&self) -> Option<usize> {
+        return Some(self.data.len());
+    }
+}
 
 impl Index<Word> for VecMemory {
     type Output = Byte;
@@ -52,3 +211,6 @@ impl From<&[(Word, Byte)]> for VecMemory {
         return res;
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,141 @@
+use std::{fmt, fs, io, path::Path};
+
+use super::Bus;
+use crate::consts::{Byte, Word};
+
+const ADDRESS_SPACE_SIZE: usize = 0x10000;
+const HEXDUMP_ROW_LEN: usize = 16;
+
+/// What to do with bytes of a binary which would end up past `$FFFF`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WrapPolicy {
+    /// Refuse to load anything.
+    Reject,
+    /// Continue loading from `$0000`.
+    Wrap,
+    /// Load only the bytes which fit.
+    Truncate,
+}
+
+#[derive(Debug)]
+pub enum BinaryError {
+    Io(io::Error),
+    OutOfRange { origin: Word, len: usize },
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            BinaryError::Io(err) => write!(f, "{err}"),
+            BinaryError::OutOfRange { origin, len } => write!(
+                f,
+                "{len} bytes loaded at ${origin:04X} do not fit in the address space"
+            ),
+        };
+    }
+}
+
+impl std::error::Error for BinaryError {}
+
+impl From<io::Error> for BinaryError {
+    fn from(err: io::Error) -> Self {
+        return BinaryError::Io(err);
+    }
+}
+
+/// Writes `data` starting at `origin` and returns the number of bytes written.
+/// Binaries bigger than the whole address space are always rejected.
+pub fn load(
+    memory: &mut dyn Bus,
+    origin: Word,
+    data: &[Byte],
+    wrap: WrapPolicy,
+) -> Result<usize, BinaryError> {
+    let space_left = ADDRESS_SPACE_SIZE - usize::from(origin);
+    let out_of_range = BinaryError::OutOfRange {
+        origin,
+        len: data.len(),
+    };
+    let len = match wrap {
+        _ if data.len() > ADDRESS_SPACE_SIZE => return Err(out_of_range),
+        WrapPolicy::Reject if data.len() > space_left => return Err(out_of_range),
+        WrapPolicy::Truncate => data.len().min(space_left),
+        _ => data.len(),
+    };
+
+    let mut address = origin;
+    for value in &data[..len] {
+        memory.write(address, *value);
+        address = address.wrapping_add(1);
+    }
+
+    return Ok(len);
+}
+
+pub fn load_file<P: AsRef<Path>>(
+    memory: &mut dyn Bus,
+    path: P,
+    origin: Word,
+    wrap: WrapPolicy,
+) -> Result<usize, BinaryError> {
+    let data = fs::read(path)?;
+    return load(memory, origin, &data, wrap);
+}
+
+/// Copies `start..=end` without triggering side effects of memory mapped devices.
+/// An empty vector is returned when `start` is past `end`.
+pub fn dump(memory: &dyn Bus, start: Word, end: Word) -> Vec<Byte> {
+    if start > end {
+        return vec![];
+    }
+
+    return (start..=end).map(|addr| memory.peek(addr)).collect();
+}
+
+pub fn dump_to_file<P: AsRef<Path>>(
+    memory: &dyn Bus,
+    start: Word,
+    end: Word,
+    path: P,
+) -> io::Result<()> {
+    return fs::write(path, dump(memory, start, end));
+}
+
+/// Writes `start..=end` as rows of 16 bytes with a printable ASCII side-bar:
+///
+/// `0200  A9 42 8D 00 03 60 00 00  00 00 00 00 00 00 00 00  |.B...`..........|`
+pub fn hexdump(
+    memory: &dyn Bus,
+    start: Word,
+    end: Word,
+    writer: &mut dyn io::Write,
+) -> io::Result<()> {
+    let bytes = dump(memory, start, end);
+    for (row_idx, row) in bytes.chunks(HEXDUMP_ROW_LEN).enumerate() {
+        let address = start.wrapping_add((row_idx * HEXDUMP_ROW_LEN) as Word);
+        write!(writer, "{address:04X} ")?;
+        for column in 0..HEXDUMP_ROW_LEN {
+            if column % 8 == 0 {
+                write!(writer, " ")?;
+            }
+            match row.get(column) {
+                Some(value) => write!(writer, "{value:02X} ")?,
+                None => write!(writer, "   ")?,
+            }
+        }
+
+        let ascii: String = row
+            .iter()
+            .map(|value| match value {
+                0x20..=0x7E => *value as char,
+                _ => '.',
+            })
+            .collect();
+        writeln!(writer, " |{ascii}|")?;
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,131 @@
+#[cfg(test)]
+mod load {
+    use crate::memory::{
+        binary::{load, BinaryError, WrapPolicy},
+        Bus, VecMemory,
+    };
+
+    #[test]
+    fn should_write_data_at_origin() {
+        let mut memory = VecMemory::new();
+
+        let written = load(&mut memory, 0x0200, &[0xA9, 0x42], WrapPolicy::Reject).unwrap();
+
+        assert_eq!(written, 2);
+        assert_eq!(memory.peek(0x0200), 0xA9);
+        assert_eq!(memory.peek(0x0201), 0x42);
+    }
+
+    #[test]
+    fn should_reject_data_past_end_of_address_space() {
+        let mut memory = VecMemory::new();
+
+        let result = load(&mut memory, 0xFFFF, &[0x01, 0x02], WrapPolicy::Reject);
+
+        assert!(matches!(
+            result,
+            Err(BinaryError::OutOfRange {
+                origin: 0xFFFF,
+                len: 2
+            })
+        ));
+        assert_eq!(memory.peek(0xFFFF), 0x00);
+    }
+
+    #[test]
+    fn should_continue_from_start_of_address_space_when_wrapping() {
+        let mut memory = VecMemory::new();
+
+        let written = load(&mut memory, 0xFFFF, &[0x01, 0x02], WrapPolicy::Wrap).unwrap();
+
+        assert_eq!(written, 2);
+        assert_eq!(memory.peek(0xFFFF), 0x01);
+        assert_eq!(memory.peek(0x0000), 0x02);
+    }
+
+    #[test]
+    fn should_write_only_bytes_which_fit_when_truncating() {
+        let mut memory = VecMemory::new();
+
+        let written = load(&mut memory, 0xFFFF, &[0x01, 0x02], WrapPolicy::Truncate).unwrap();
+
+        assert_eq!(written, 1);
+        assert_eq!(memory.peek(0x0000), 0x00);
+    }
+
+    #[test]
+    fn should_reject_data_bigger_than_address_space_even_when_wrapping() {
+        let mut memory = VecMemory::new();
+
+        let result = load(&mut memory, 0x0000, &vec![0; 0x10001], WrapPolicy::Wrap);
+
+        assert!(matches!(result, Err(BinaryError::OutOfRange { .. })));
+    }
+}
+
+#[cfg(test)]
+mod dump {
+    use crate::memory::{binary::dump, VecMemory};
+
+    #[test]
+    fn should_return_inclusive_range() {
+        let memory = VecMemory::from(&[(0x0200, 0x01), (0x0202, 0x03)][..]);
+
+        assert_eq!(dump(&memory, 0x0200, 0x0202), vec![0x01, 0x00, 0x03]);
+    }
+
+    #[test]
+    fn should_return_nothing_when_start_is_past_end() {
+        let memory = VecMemory::new();
+
+        assert_eq!(dump(&memory, 0x0201, 0x0200), vec![]);
+    }
+}
+
+#[cfg(test)]
+mod files {
+    use crate::memory::{binary::WrapPolicy, Bus, VecMemory};
+    use std::env;
+
+    #[test]
+    fn should_restore_dumped_range() {
+        let path = env::temp_dir().join("cpu6502_binary_dump_restore_test.bin");
+        let memory = VecMemory::from(&[(0xC000, 0xDE), (0xC001, 0xAD)][..]);
+        memory.dump_to_file(0xC000, 0xC001, &path).unwrap();
+
+        let mut restored = VecMemory::new();
+        let written = restored
+            .load_binary_file(&path, 0x1000, WrapPolicy::Reject)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(written, 2);
+        assert_eq!(restored.peek(0x1000), 0xDE);
+        assert_eq!(restored.peek(0x1001), 0xAD);
+    }
+}
+
+#[cfg(test)]
+mod hexdump {
+    use crate::memory::{binary::hexdump, VecMemory};
+
+    #[test]
+    fn should_write_rows_with_ascii_side_bar() {
+        let mut memory = VecMemory::new();
+        memory.store(&[
+            (0x0200, 0xA9),
+            (0x0201, 0x42),
+            (0x020F, 0x7E),
+            (0x0210, 0x41),
+        ]);
+        let mut output = vec![];
+
+        hexdump(&memory, 0x0200, 0x0211, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "0200  A9 42 00 00 00 00 00 00  00 00 00 00 00 00 00 7E  |.B.............~|\n\
+             0210  41 00                                             |A.|\n"
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt;
+
+use super::{Bus, InterruptLine};
+use crate::consts::{Byte, Word};
+
+/// Value returned when the CPU reads from an address no region responds to.
+pub const OPEN_BUS_VALUE: Byte = 0xFF;
+
+struct Region {
+    start: Word,
+    end: Word,
+    mask: Word,
+    interrupt: Option<InterruptLine>,
+    handler: Box<dyn Bus>,
+}
+
+impl Region {
+    fn contains(&self, addr: Word) -> bool {
+        return addr >= self.start && addr <= self.end;
+    }
+
+    fn local_address(&self, addr: Word) -> Word {
+        return (addr - self.start) & self.mask;
+    }
+
+    /// Highest address the handler is passed, the largest `offset & mask`
+    /// for offsets within the region.
+    fn highest_local_address(&self) -> Word {
+        let length = self.end - self.start;
+        let mut highest = 0;
+        for bit in (0..Word::BITS).rev().map(|shift| 1 << shift) {
+            if length & bit == 0 {
+                continue;
+            }
+            if self.mask & bit == 0 {
+                // Offsets with this bit clear are free in all lower bits.
+                return highest | (self.mask & (bit - 1));
+            }
+            highest |= bit;
+        }
+
+        return highest;
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DecoderError {
+    InvalidRange {
+        start: Word,
+        end: Word,
+    },
+    Overlap {
+        first: (Word, Word),
+        second: (Word, Word),
+    },
+    /// The handler cannot serve every address of its region, mask it to mirror instead.
+    HandlerTooSmall {
+        start: Word,
+        end: Word,
+        size: usize,
+    },
+}
+
+impl fmt::Display for DecoderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            DecoderError::InvalidRange { start, end } => {
+                write!(f, "invalid range ${start:04X}-${end:04X}: start after end")
+            }
+            DecoderError::Overlap { first, second } => write!(
+                f,
+                "range ${:04X}-${:04X} overlaps ${:04X}-${:04X}",
+                second.0, second.1, first.0, first.1
+            ),
+            DecoderError::HandlerTooSmall { start, end, size } => write!(
+                f,
+                "range ${start:04X}-${end:04X} is mapped to {size} bytes without mirroring them"
+            ),
+        };
+    }
+}
+
+impl std::error::Error for DecoderError {}
+
+/// Collects regions of the address space before producing an [`AddressDecoder`].
+///
+/// Each region spans `start..=end` (inclusive) and hands its handler addresses
+/// relative to `start`. With a mask the relative address is additionally
+/// `AND`-ed with it, which mirrors a smaller chip across a bigger window,
+/// e.g. 2 KB of RAM repeated over `$0000-$1FFF` uses mask `$07FF`.
+pub struct AddressDecoderBuilder {
+    regions: Vec<Region>,
+}
+
+impl AddressDecoderBuilder {
+    pub fn new() -> Self {
+        return AddressDecoderBuilder { regions: vec![] };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.regions.is_empty();
+    }
+
+    pub fn map(self, start: Word, end: Word, handler: Box<dyn Bus>) -> Self {
+        return self.map_mirrored(start, end, 0xFFFF, handler);
+    }
+
+    pub fn map_mirrored(
+        mut self,
+        start: Word,
+        end: Word,
+        mask: Word,
+        handler: Box<dyn Bus>,
+    ) -> Self {
+        self.regions.push(Region {
+            start,
+            end,
+            mask,
+            interrupt: None,
+            handler,
+        });
+
+        return self;
+    }
+
+    /// Maps a device whose interrupt output drives `line` of the CPU.
+    pub fn map_interrupting(
+        mut self,
+        start: Word,
+        end: Word,
+        line: InterruptLine,
+        handler: Box<dyn Bus>,
+    ) -> Self {
+        self.regions.push(Region {
+            start,
+            end,
+            mask: 0xFFFF,
+            interrupt: Some(line),
+            handler,
+        });
+
+        return self;
+    }
+
+    pub fn build(mut self) -> Result<AddressDecoder, DecoderError> {
+        for region in &self.regions {
+            if region.start > region.end {
+                return Err(DecoderError::InvalidRange {
+                    start: region.start,
+                    end: region.end,
+                });
+            }
+            if let Some(size) = region.handler.size() {
+                if usize::from(region.highest_local_address()) >= size {
+                    return Err(DecoderError::HandlerTooSmall {
+                        start: region.start,
+                        end: region.end,
+                        size,
+                    });
+                }
+            }
+        }
+
+        self.regions.sort_by_key(|region| region.start);
+        for pair in self.regions.windows(2) {
+            let (first, second) = (&pair[0], &pair[1]);
+            if second.start <= first.end {
+                return Err(DecoderError::Overlap {
+                    first: (first.start, first.end),
+                    second: (second.start, second.end),
+                });
+            }
+        }
+
+        return Ok(AddressDecoder {
+            regions: self.regions,
+        });
+    }
+}
+
+impl Default for AddressDecoderBuilder {
+    fn default() -> Self {
+        return AddressDecoderBuilder::new();
+    }
+}
+
+/// Routes every bus access to the region mapped at the accessed address.
+/// Reads from unmapped addresses return [`OPEN_BUS_VALUE`], writes to them are dropped.
+pub struct AddressDecoder {
+    regions: Vec<Region>,
+}
+
+impl AddressDecoder {
+    fn region_index(&self, addr: Word) -> Option<usize> {
+        return self.regions.iter().position(|region| region.contains(addr));
+    }
+
+    /// Returns the inclusive address ranges of all mapped regions in ascending order.
+    pub fn mapped_ranges(&self) -> Vec<(Word, Word)> {
+        return self
+            .regions
+            .iter()
+            .map(|region| (region.start, region.end))
+            .collect();
+    }
+}
+
+impl Bus for AddressDecoder {
+    fn read(&mut self, addr: Word) -> Byte {
+        return match self.region_index(addr) {
+            Some(idx) => {
+                let region = &mut self.regions[idx];
+                let local_addr = region.local_address(addr);
+                region.handler.read(local_addr)
+            }
+            None => OPEN_BUS_VALUE,
+        };
+    }
+
+    fn write(&mut self, addr: Word, value: Byte) {
+        if let Some(idx) = self.region_index(addr) {
+            let region = &mut self.regions[idx];
+            let local_addr = region.local_address(addr);
+            region.handler.write(local_addr, value);
+        }
+    }
+
+    fn peek(&self, addr: Word) -> Byte {
+        return match self.region_index(addr) {
+            Some(idx) => {
+                let region = &self.regions[idx];
+                region.handler.peek(region.local_address(addr))
+            }
+            None => OPEN_BUS_VALUE,
+        };
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        for region in self.regions.iter_mut() {
+            region.handler.tick(cycles);
+        }
+    }
+
+    fn line_asserted(&self, line: InterruptLine) -> bool {
+        return self
+            .regions
+            .iter()
+            .any(|region| region.interrupt == Some(line) && region.handler.interrupt_asserted());
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,304 @@
+use crate::{
+    consts::{Byte, Word},
+    memory::Bus,
+};
+use std::{cell::RefCell, rc::Rc};
+
+/// Local address and written value of each access, `None` for reads.
+type Accesses = Rc<RefCell<Vec<(Word, Option<Byte>)>>>;
+
+struct DeviceMock {
+    accesses: Accesses,
+}
+
+impl Bus for DeviceMock {
+    fn read(&mut self, addr: Word) -> Byte {
+        self.accesses.borrow_mut().push((addr, None));
+        return 0x42;
+    }
+
+    fn write(&mut self, addr: Word, value: Byte) {
+        self.accesses.borrow_mut().push((addr, Some(value)));
+    }
+
+    fn peek(&self, _addr: Word) -> Byte {
+        return 0x42;
+    }
+}
+
+#[cfg(test)]
+mod build {
+    use crate::memory::{
+        decoder::{AddressDecoderBuilder, DecoderError},
+        VecMemory,
+    };
+
+    #[test]
+    fn should_reject_overlapping_regions() {
+        let result = AddressDecoderBuilder::new()
+            .map(0x0000, 0x07FF, Box::new(VecMemory::with_size(0x0800)))
+            .map(0x0400, 0x0FFF, Box::new(VecMemory::with_size(0x0C00)))
+            .build();
+
+        assert_eq!(
+            result.err(),
+            Some(DecoderError::Overlap {
+                first: (0x0000, 0x07FF),
+                second: (0x0400, 0x0FFF)
+            })
+        );
+    }
+
+    #[test]
+    fn should_reject_range_with_start_after_end() {
+        let result = AddressDecoderBuilder::new()
+            .map(0x2000, 0x1000, Box::new(VecMemory::with_size(0x1000)))
+            .build();
+
+        assert_eq!(
+            result.err(),
+            Some(DecoderError::InvalidRange {
+                start: 0x2000,
+                end: 0x1000
+            })
+        );
+    }
+
+    #[test]
+    fn should_reject_memory_smaller_than_its_region() {
+        let result = AddressDecoderBuilder::new()
+            .map(0x0000, 0x1FFF, Box::new(VecMemory::with_size(0x0800)))
+            .build();
+
+        assert_eq!(
+            result.err(),
+            Some(DecoderError::HandlerTooSmall {
+                start: 0x0000,
+                end: 0x1FFF,
+                size: 0x0800
+            })
+        );
+    }
+
+    #[test]
+    fn should_accept_small_memory_mirrored_across_its_region() {
+        let mirrored = AddressDecoderBuilder::new()
+            .map_mirrored(
+                0x0000,
+                0x1FFF,
+                0x07FF,
+                Box::new(VecMemory::with_size(0x0800)),
+            )
+            .build();
+        let masked_tail = AddressDecoderBuilder::new()
+            .map_mirrored(
+                0x1000,
+                0x17FF,
+                0x04FF,
+                Box::new(VecMemory::with_size(0x0500)),
+            )
+            .build();
+        let too_short = AddressDecoderBuilder::new()
+            .map_mirrored(
+                0x1000,
+                0x17FF,
+                0x04FF,
+                Box::new(VecMemory::with_size(0x04FF)),
+            )
+            .build();
+
+        assert!(mirrored.is_ok());
+        assert!(masked_tail.is_ok());
+        assert!(too_short.is_err());
+    }
+
+    #[test]
+    fn should_accept_adjacent_regions_and_list_them_in_ascending_order() {
+        let uut = AddressDecoderBuilder::new()
+            .map(0x8000, 0xFFFF, Box::new(VecMemory::with_size(0x8000)))
+            .map(0x0000, 0x7FFF, Box::new(VecMemory::with_size(0x8000)))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            uut.mapped_ranges(),
+            vec![(0x0000, 0x7FFF), (0x8000, 0xFFFF)]
+        );
+    }
+}
+
+#[cfg(test)]
+mod routing {
+    use super::DeviceMock;
+    use crate::memory::{
+        decoder::{AddressDecoderBuilder, OPEN_BUS_VALUE},
+        Bus, VecMemory,
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn should_pass_addresses_relative_to_region_start_to_handler() {
+        let accesses = Rc::new(RefCell::new(vec![]));
+        let mut uut = AddressDecoderBuilder::new()
+            .map(0x0000, 0x7FFF, Box::new(VecMemory::with_size(0x8000)))
+            .map(
+                0xD000,
+                0xD00F,
+                Box::new(DeviceMock {
+                    accesses: accesses.clone(),
+                }),
+            )
+            .build()
+            .unwrap();
+
+        assert_eq!(uut.read(0xD004), 0x42);
+        uut.write(0xD00F, 0x11);
+
+        assert_eq!(
+            *accesses.borrow(),
+            vec![(0x0004, None), (0x000F, Some(0x11))]
+        );
+    }
+
+    #[test]
+    fn should_not_route_peeks_through_side_effecting_read() {
+        let accesses = Rc::new(RefCell::new(vec![]));
+        let uut = AddressDecoderBuilder::new()
+            .map(
+                0xD000,
+                0xD00F,
+                Box::new(DeviceMock {
+                    accesses: accesses.clone(),
+                }),
+            )
+            .build()
+            .unwrap();
+
+        assert_eq!(uut.peek(0xD000), 0x42);
+        assert_eq!(accesses.borrow().len(), 0);
+    }
+
+    #[test]
+    fn should_mirror_region_using_mask() {
+        let mut uut = AddressDecoderBuilder::new()
+            .map_mirrored(
+                0x0000,
+                0x1FFF,
+                0x07FF,
+                Box::new(VecMemory::with_size(0x0800)),
+            )
+            .build()
+            .unwrap();
+
+        uut.write(0x0010, 0x99);
+
+        assert_eq!(uut.read(0x0810), 0x99);
+        assert_eq!(uut.read(0x1810), 0x99);
+    }
+
+    #[test]
+    fn should_return_open_bus_value_for_unmapped_reads_and_drop_unmapped_writes() {
+        let mut uut = AddressDecoderBuilder::new()
+            .map(0x0000, 0x00FF, Box::new(VecMemory::with_size(0x0100)))
+            .build()
+            .unwrap();
+
+        uut.write(0x4000, 0x01);
+
+        assert_eq!(uut.read(0x4000), OPEN_BUS_VALUE);
+        assert_eq!(uut.peek(0x4000), OPEN_BUS_VALUE);
+    }
+}
+
+#[cfg(test)]
+mod cpu {
+    use super::DeviceMock;
+    use crate::{
+        cpu::CPU,
+        memory::{decoder::AddressDecoderBuilder, VecMemory},
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn should_route_cpu_accesses_to_mapped_regions() {
+        let accesses = Rc::new(RefCell::new(vec![]));
+        let mut rom = VecMemory::with_size(0x0100);
+        rom.store(&[
+            (0xFC, 0xA5), // LDA $20
+            (0xFD, 0x20),
+            (0xFE, 0x85), // STA $10
+            (0xFF, 0x10),
+        ]);
+        let decoder = AddressDecoderBuilder::new()
+            .map(
+                0x0000,
+                0x00FF,
+                Box::new(DeviceMock {
+                    accesses: accesses.clone(),
+                }),
+            )
+            .map(0xFF00, 0xFFFF, Box::new(rom))
+            .build()
+            .unwrap();
+        let mut cpu = CPU::new(Box::new(decoder));
+
+        cpu.execute(6);
+
+        assert_eq!(
+            *accesses.borrow(),
+            vec![(0x0020, None), (0x0010, Some(0x42))]
+        );
+    }
+}
+
+#[cfg(test)]
+mod interrupts {
+    use crate::devices::timer::{
+        Timer, CONTROL_ENABLE, CONTROL_IRQ_ENABLE, REGISTER_CONTROL, REGISTER_STATUS,
+    };
+    use crate::memory::{decoder::AddressDecoderBuilder, Bus, InterruptLine, VecMemory};
+
+    fn expired_timer() -> Box<Timer> {
+        let mut timer = Timer::new().with_reload(1);
+        timer.write(REGISTER_CONTROL, CONTROL_ENABLE | CONTROL_IRQ_ENABLE);
+        timer.tick(1);
+
+        return Box::new(timer);
+    }
+
+    #[test]
+    fn should_route_device_interrupt_to_the_line_it_is_wired_to() {
+        let uut = AddressDecoderBuilder::new()
+            .map_interrupting(0xD000, 0xD003, InterruptLine::Nmi, expired_timer())
+            .build()
+            .unwrap();
+
+        assert!(uut.line_asserted(InterruptLine::Nmi));
+        assert!(!uut.line_asserted(InterruptLine::Irq));
+    }
+
+    #[test]
+    fn should_ignore_interrupts_of_devices_which_are_not_wired() {
+        let uut = AddressDecoderBuilder::new()
+            .map(0xD000, 0xD003, expired_timer())
+            .build()
+            .unwrap();
+
+        assert!(!uut.line_asserted(InterruptLine::Irq));
+    }
+
+    #[test]
+    fn should_tick_every_mapped_region() {
+        let mut timer = Timer::new().with_reload(5);
+        timer.write(REGISTER_CONTROL, CONTROL_ENABLE);
+        let mut uut = AddressDecoderBuilder::new()
+            .map(0x0000, 0x00FF, Box::new(VecMemory::with_size(0x0100)))
+            .map_interrupting(0xD000, 0xD003, InterruptLine::Irq, Box::new(timer))
+            .build()
+            .unwrap();
+
+        uut.tick(5);
+
+        assert_eq!(uut.peek(0xD000 + REGISTER_STATUS), 0x80);
+    }
+}
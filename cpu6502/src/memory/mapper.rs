@@ -0,0 +1,308 @@
+use std::fmt;
+
+use super::{decoder::OPEN_BUS_VALUE, Bus};
+use crate::consts::{Byte, Word};
+
+pub const BANK_SIZE_8K: usize = 8 * 1024;
+pub const BANK_SIZE_16K: usize = 16 * 1024;
+
+#[derive(Debug, PartialEq)]
+pub enum MapperError {
+    ImageSize { image_size: usize, bank_size: usize },
+    NotEnoughBanks { required: usize, available: usize },
+    WindowCount { expected: usize, provided: usize },
+    BankOutOfRange { window: usize, bank: usize },
+}
+
+impl fmt::Display for MapperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            MapperError::ImageSize {
+                image_size,
+                bank_size,
+            } => write!(
+                f,
+                "image of {image_size} bytes is not a non-empty multiple of {bank_size} byte banks"
+            ),
+            MapperError::NotEnoughBanks {
+                required,
+                available,
+            } => write!(f, "mapper requires {required} banks, image has {available}"),
+            MapperError::WindowCount { expected, provided } => write!(
+                f,
+                "mapper has {expected} switchable windows, {provided} banks provided"
+            ),
+            MapperError::BankOutOfRange { window, bank } => {
+                write!(f, "bank {bank} selected for window {window} does not exist")
+            }
+        };
+    }
+}
+
+impl std::error::Error for MapperError {}
+
+/// Banked memory: windows of the address space whose backing bank is
+/// chosen by writes the CPU makes to the mapper control registers.
+///
+/// Selection is exposed so debuggers can show it and save states can restore it.
+pub trait Mapper: Bus {
+    fn bank_count(&self) -> usize;
+
+    /// Bank currently visible in every switchable window, in window order.
+    fn selected_banks(&self) -> Vec<usize>;
+
+    fn select_banks(&mut self, banks: &[usize]) -> Result<(), MapperError>;
+}
+
+struct BankedImage {
+    data: Vec<Byte>,
+    bank_size: usize,
+}
+
+impl BankedImage {
+    fn new(image: &[Byte], bank_size: usize) -> Result<Self, MapperError> {
+        if bank_size == 0 || image.is_empty() || !image.len().is_multiple_of(bank_size) {
+            return Err(MapperError::ImageSize {
+                image_size: image.len(),
+                bank_size,
+            });
+        }
+
+        return Ok(BankedImage {
+            data: image.to_vec(),
+            bank_size,
+        });
+    }
+
+    fn bank_count(&self) -> usize {
+        return self.data.len() / self.bank_size;
+    }
+
+    fn byte(&self, bank: usize, offset: usize) -> Byte {
+        return self.data[bank * self.bank_size + offset];
+    }
+
+    fn select(&self, selection: &mut [usize], banks: &[usize]) -> Result<(), MapperError> {
+        if banks.len() != selection.len() {
+            return Err(MapperError::WindowCount {
+                expected: selection.len(),
+                provided: banks.len(),
+            });
+        }
+
+        for (window, bank) in banks.iter().enumerate() {
+            if *bank >= self.bank_count() {
+                return Err(MapperError::BankOutOfRange {
+                    window,
+                    bank: *bank,
+                });
+            }
+        }
+        selection.copy_from_slice(banks);
+
+        return Ok(());
+    }
+}
+
+/// 32 KB window split into a switchable lower 16 KB and an upper 16 KB fixed
+/// to the last bank of the image. Writing anywhere in the window selects the
+/// lower bank (modulo the number of banks), as on UxROM-style cartridges.
+pub struct FixedLastBankMapper {
+    image: BankedImage,
+    bank: usize,
+}
+
+impl FixedLastBankMapper {
+    pub fn new(image: &[Byte]) -> Result<Self, MapperError> {
+        let image = BankedImage::new(image, BANK_SIZE_16K)?;
+        if image.bank_count() < 2 {
+            return Err(MapperError::NotEnoughBanks {
+                required: 2,
+                available: image.bank_count(),
+            });
+        }
+
+        return Ok(FixedLastBankMapper { image, bank: 0 });
+    }
+}
+
+impl Bus for FixedLastBankMapper {
+    fn read(&mut self, addr: Word) -> Byte {
+        return self.peek(addr);
+    }
+
+    fn write(&mut self, _addr: Word, value: Byte) {
+        self.bank = usize::from(value) % self.image.bank_count();
+    }
+
+    fn peek(&self, addr: Word) -> Byte {
+        let addr: usize = addr.into();
+        let window = addr / BANK_SIZE_16K;
+        let offset = addr % BANK_SIZE_16K;
+
+        return match window {
+            0 => self.image.byte(self.bank, offset),
+            1 => self.image.byte(self.image.bank_count() - 1, offset),
+            _ => OPEN_BUS_VALUE,
+        };
+    }
+}
+
+impl Mapper for FixedLastBankMapper {
+    fn bank_count(&self) -> usize {
+        return self.image.bank_count();
+    }
+
+    fn selected_banks(&self) -> Vec<usize> {
+        return vec![self.bank];
+    }
+
+    fn select_banks(&mut self, banks: &[usize]) -> Result<(), MapperError> {
+        let mut selection = [self.bank];
+        self.image.select(&mut selection, banks)?;
+        self.bank = selection[0];
+
+        return Ok(());
+    }
+}
+
+/// 32 KB window split into four independently switchable 8 KB windows.
+/// Writing to any address inside a window selects the bank shown in it.
+/// Initially window `n` shows bank `n` (modulo the number of banks).
+pub struct QuadBankMapper {
+    image: BankedImage,
+    banks: [usize; 4],
+}
+
+impl QuadBankMapper {
+    pub fn new(image: &[Byte]) -> Result<Self, MapperError> {
+        let image = BankedImage::new(image, BANK_SIZE_8K)?;
+        let count = image.bank_count();
+
+        return Ok(QuadBankMapper {
+            image,
+            banks: [0, 1 % count, 2 % count, 3 % count],
+        });
+    }
+}
+
+impl Bus for QuadBankMapper {
+    fn read(&mut self, addr: Word) -> Byte {
+        return self.peek(addr);
+    }
+
+    fn write(&mut self, addr: Word, value: Byte) {
+        let window = usize::from(addr) / BANK_SIZE_8K;
+        if window < self.banks.len() {
+            self.banks[window] = usize::from(value) % self.image.bank_count();
+        }
+    }
+
+    fn peek(&self, addr: Word) -> Byte {
+        let addr: usize = addr.into();
+        return match self.banks.get(addr / BANK_SIZE_8K) {
+            Some(bank) => self.image.byte(*bank, addr % BANK_SIZE_8K),
+            None => OPEN_BUS_VALUE,
+        };
+    }
+}
+
+impl Mapper for QuadBankMapper {
+    fn bank_count(&self) -> usize {
+        return self.image.bank_count();
+    }
+
+    fn selected_banks(&self) -> Vec<usize> {
+        return self.banks.to_vec();
+    }
+
+    fn select_banks(&mut self, banks: &[usize]) -> Result<(), MapperError> {
+        let mut selection = self.banks;
+        self.image.select(&mut selection, banks)?;
+        self.banks = selection;
+
+        return Ok(());
+    }
+}
+
+/// Single switchable window backed by a latch register.
+///
+/// The window starts at `window_start` and is one bank long; the latch lives
+/// at `register` (both relative to the mapped region). A value written to the
+/// latch is `AND`-ed with `bank_mask` and then selects the bank modulo the
+/// number of banks. Reads outside the window return [`OPEN_BUS_VALUE`].
+pub struct LatchMapper {
+    image: BankedImage,
+    window_start: Word,
+    register: Word,
+    bank_mask: Byte,
+    bank: usize,
+}
+
+impl LatchMapper {
+    pub fn new(
+        image: &[Byte],
+        bank_size: usize,
+        window_start: Word,
+        register: Word,
+    ) -> Result<Self, MapperError> {
+        return Ok(LatchMapper {
+            image: BankedImage::new(image, bank_size)?,
+            window_start,
+            register,
+            bank_mask: 0xFF,
+            bank: 0,
+        });
+    }
+
+    pub fn with_bank_mask(mut self, bank_mask: Byte) -> Self {
+        self.bank_mask = bank_mask;
+        return self;
+    }
+}
+
+impl Bus for LatchMapper {
+    fn read(&mut self, addr: Word) -> Byte {
+        return self.peek(addr);
+    }
+
+    fn write(&mut self, addr: Word, value: Byte) {
+        if addr == self.register {
+            self.bank = usize::from(value & self.bank_mask) % self.image.bank_count();
+        }
+    }
+
+    fn peek(&self, addr: Word) -> Byte {
+        if addr < self.window_start {
+            return OPEN_BUS_VALUE;
+        }
+
+        let offset: usize = (addr - self.window_start).into();
+        if offset >= self.image.bank_size {
+            return OPEN_BUS_VALUE;
+        }
+
+        return self.image.byte(self.bank, offset);
+    }
+}
+
+impl Mapper for LatchMapper {
+    fn bank_count(&self) -> usize {
+        return self.image.bank_count();
+    }
+
+    fn selected_banks(&self) -> Vec<usize> {
+        return vec![self.bank];
+    }
+
+    fn select_banks(&mut self, banks: &[usize]) -> Result<(), MapperError> {
+        let mut selection = [self.bank];
+        self.image.select(&mut selection, banks)?;
+        self.bank = selection[0];
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests;
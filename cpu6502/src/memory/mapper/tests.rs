@@ -0,0 +1,241 @@
+use crate::consts::Byte;
+use crate::memory::mapper::{BANK_SIZE_16K, BANK_SIZE_8K};
+
+fn image_with_bank_markers(bank_size: usize, bank_count: usize) -> Vec<Byte> {
+    let mut image = vec![0x00; bank_size * bank_count];
+    for bank in 0..bank_count {
+        image[bank * bank_size] = bank as Byte;
+        image[bank * bank_size + bank_size - 1] = 0xF0 | bank as Byte;
+    }
+
+    return image;
+}
+
+fn image_16k(bank_count: usize) -> Vec<Byte> {
+    return image_with_bank_markers(BANK_SIZE_16K, bank_count);
+}
+
+fn image_8k(bank_count: usize) -> Vec<Byte> {
+    return image_with_bank_markers(BANK_SIZE_8K, bank_count);
+}
+
+#[cfg(test)]
+mod fixed_last_bank_mapper {
+    use super::image_16k;
+    use crate::memory::{
+        mapper::{FixedLastBankMapper, Mapper, MapperError},
+        Bus,
+    };
+
+    #[test]
+    fn should_reject_image_which_is_not_a_multiple_of_bank_size() {
+        let result = FixedLastBankMapper::new(&[0x00; 0x5000]);
+
+        assert_eq!(
+            result.err(),
+            Some(MapperError::ImageSize {
+                image_size: 0x5000,
+                bank_size: 0x4000
+            })
+        );
+    }
+
+    #[test]
+    fn should_reject_image_with_a_single_bank() {
+        let result = FixedLastBankMapper::new(&image_16k(1));
+
+        assert_eq!(
+            result.err(),
+            Some(MapperError::NotEnoughBanks {
+                required: 2,
+                available: 1
+            })
+        );
+    }
+
+    #[test]
+    fn should_show_first_bank_in_lower_window_and_last_bank_in_upper_window_initially() {
+        let mut uut = FixedLastBankMapper::new(&image_16k(4)).unwrap();
+
+        assert_eq!(uut.read(0x0000), 0x00);
+        assert_eq!(uut.read(0x4000), 0x03);
+        assert_eq!(uut.read(0x7FFF), 0xF3);
+    }
+
+    #[test]
+    fn should_switch_lower_window_on_write() {
+        let mut uut = FixedLastBankMapper::new(&image_16k(4)).unwrap();
+
+        uut.write(0x7FF0, 0x02);
+
+        assert_eq!(uut.read(0x0000), 0x02);
+        assert_eq!(uut.read(0x3FFF), 0xF2);
+        assert_eq!(uut.read(0x4000), 0x03);
+        assert_eq!(uut.selected_banks(), vec![2]);
+    }
+
+    #[test]
+    fn should_wrap_selected_bank_around_bank_count() {
+        let mut uut = FixedLastBankMapper::new(&image_16k(4)).unwrap();
+
+        uut.write(0x0000, 0x05);
+
+        assert_eq!(uut.selected_banks(), vec![1]);
+    }
+
+    #[test]
+    fn should_restore_selection() {
+        let mut uut = FixedLastBankMapper::new(&image_16k(4)).unwrap();
+
+        uut.select_banks(&[3]).unwrap();
+
+        assert_eq!(uut.peek(0x0000), 0x03);
+    }
+
+    #[test]
+    fn should_reject_restoring_bank_out_of_range() {
+        let mut uut = FixedLastBankMapper::new(&image_16k(4)).unwrap();
+
+        let result = uut.select_banks(&[4]);
+
+        assert_eq!(
+            result,
+            Err(MapperError::BankOutOfRange { window: 0, bank: 4 })
+        );
+        assert_eq!(uut.selected_banks(), vec![0]);
+    }
+}
+
+#[cfg(test)]
+mod quad_bank_mapper {
+    use super::image_8k;
+    use crate::memory::{
+        mapper::{Mapper, MapperError, QuadBankMapper},
+        Bus,
+    };
+
+    #[test]
+    fn should_show_consecutive_banks_initially() {
+        let uut = QuadBankMapper::new(&image_8k(8)).unwrap();
+
+        assert_eq!(uut.selected_banks(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn should_switch_only_the_window_written_to() {
+        let mut uut = QuadBankMapper::new(&image_8k(8)).unwrap();
+
+        uut.write(0x4000, 0x07);
+
+        assert_eq!(uut.read(0x0000), 0x00);
+        assert_eq!(uut.read(0x2000), 0x01);
+        assert_eq!(uut.read(0x4000), 0x07);
+        assert_eq!(uut.read(0x7FFF), 0xF3);
+        assert_eq!(uut.selected_banks(), vec![0, 1, 7, 3]);
+    }
+
+    #[test]
+    fn should_require_a_bank_for_every_window_when_restoring() {
+        let mut uut = QuadBankMapper::new(&image_8k(8)).unwrap();
+
+        let result = uut.select_banks(&[1, 2]);
+
+        assert_eq!(
+            result,
+            Err(MapperError::WindowCount {
+                expected: 4,
+                provided: 2
+            })
+        );
+    }
+}
+
+#[cfg(test)]
+mod latch_mapper {
+    use super::image_8k;
+    use crate::memory::{
+        decoder::OPEN_BUS_VALUE,
+        mapper::{LatchMapper, Mapper, MapperError, BANK_SIZE_8K},
+        Bus,
+    };
+
+    #[test]
+    fn should_switch_bank_on_write_to_latch_register() {
+        let mut uut = LatchMapper::new(&image_8k(4), BANK_SIZE_8K, 0x2000, 0x0000).unwrap();
+
+        uut.write(0x0000, 0x02);
+
+        assert_eq!(uut.read(0x2000), 0x02);
+        assert_eq!(uut.read(0x3FFF), 0xF2);
+        assert_eq!(uut.selected_banks(), vec![2]);
+    }
+
+    #[test]
+    fn should_reject_zero_bank_size() {
+        let result = LatchMapper::new(&image_8k(4), 0, 0x2000, 0x0000);
+
+        assert_eq!(
+            result.err(),
+            Some(MapperError::ImageSize {
+                image_size: 0x8000,
+                bank_size: 0
+            })
+        );
+    }
+
+    #[test]
+    fn should_ignore_writes_outside_latch_register() {
+        let mut uut = LatchMapper::new(&image_8k(4), BANK_SIZE_8K, 0x2000, 0x0000).unwrap();
+
+        uut.write(0x2000, 0x02);
+
+        assert_eq!(uut.selected_banks(), vec![0]);
+    }
+
+    #[test]
+    fn should_apply_bank_mask_to_latched_value() {
+        let mut uut = LatchMapper::new(&image_8k(4), BANK_SIZE_8K, 0x2000, 0x0000)
+            .unwrap()
+            .with_bank_mask(0x03);
+
+        uut.write(0x0000, 0xF1);
+
+        assert_eq!(uut.selected_banks(), vec![1]);
+    }
+
+    #[test]
+    fn should_return_open_bus_value_outside_window() {
+        let mut uut = LatchMapper::new(&image_8k(4), BANK_SIZE_8K, 0x2000, 0x0000).unwrap();
+
+        assert_eq!(uut.read(0x1FFF), OPEN_BUS_VALUE);
+        assert_eq!(uut.read(0x4000), OPEN_BUS_VALUE);
+    }
+}
+
+#[cfg(test)]
+mod shared {
+    use super::image_16k;
+    use crate::memory::{
+        decoder::AddressDecoderBuilder,
+        mapper::{FixedLastBankMapper, Mapper},
+        Bus, VecMemory,
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn should_expose_bank_selection_made_through_decoder() {
+        let mapper = Rc::new(RefCell::new(
+            FixedLastBankMapper::new(&image_16k(4)).unwrap(),
+        ));
+        let mut decoder = AddressDecoderBuilder::new()
+            .map(0x0000, 0x7FFF, Box::new(VecMemory::with_size(0x8000)))
+            .map(0x8000, 0xFFFF, Box::new(mapper.clone()))
+            .build()
+            .unwrap();
+
+        decoder.write(0x8000, 0x01);
+
+        assert_eq!(mapper.borrow().selected_banks(), vec![1]);
+        assert_eq!(decoder.peek(0x8000), 0x01);
+    }
+}
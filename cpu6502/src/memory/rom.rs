@@ -0,0 +1,87 @@
+use std::{fs, io, path::Path};
+
+use super::{decoder::OPEN_BUS_VALUE, Bus};
+use crate::consts::{Byte, Word};
+
+type WriteLogger = Box<dyn FnMut(Word, Byte)>;
+
+/// Read-only memory built from an image. Writes never change its contents.
+///
+/// Addresses are relative to the start of the region the ROM is mapped into.
+/// Without mirroring, reads past the end of the image return [`OPEN_BUS_VALUE`];
+/// with mirroring the image repeats until the end of the window, so a 2 KB
+/// monitor mapped over `$F000-$FFFF` answers at both `$F000` and `$F800`.
+pub struct Rom {
+    data: Vec<Byte>,
+    mirrored: bool,
+    write_logger: Option<WriteLogger>,
+}
+
+impl Rom {
+    pub fn new(image: &[Byte]) -> Self {
+        return Rom {
+            data: image.to_vec(),
+            mirrored: false,
+            write_logger: None,
+        };
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let image = fs::read(path)?;
+        return Ok(Rom::new(&image));
+    }
+
+    pub fn mirrored(mut self) -> Self {
+        self.mirrored = true;
+        return self;
+    }
+
+    /// Calls `logger` with the local address and value of every ignored write.
+    pub fn with_write_logger(mut self, logger: WriteLogger) -> Self {
+        self.write_logger = Some(logger);
+        return self;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.data.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.data.is_empty();
+    }
+
+    fn byte_at(&self, addr: Word) -> Byte {
+        if self.data.is_empty() {
+            return OPEN_BUS_VALUE;
+        }
+
+        let mut idx: usize = addr.into();
+        if self.mirrored {
+            idx %= self.data.len();
+        }
+
+        return match self.data.get(idx) {
+            Some(value) => *value,
+            None => OPEN_BUS_VALUE,
+        };
+    }
+}
+
+impl Bus for Rom {
+    fn read(&mut self, addr: Word) -> Byte {
+        return self.byte_at(addr);
+    }
+
+    fn write(&mut self, addr: Word, value: Byte) {
+        if let Some(logger) = self.write_logger.as_mut() {
+            logger(addr, value);
+        }
+    }
+
+    fn peek(&self, addr: Word) -> Byte {
+        return self.byte_at(addr);
+    }
+}
+
+#[cfg(test)]
+mod tests;
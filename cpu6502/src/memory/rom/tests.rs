@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod read {
+    use crate::memory::{decoder::OPEN_BUS_VALUE, rom::Rom, Bus};
+
+    #[test]
+    fn should_return_bytes_of_the_image() {
+        let mut uut = Rom::new(&[0x11, 0x22, 0x33]);
+
+        assert_eq!(uut.read(0x0000), 0x11);
+        assert_eq!(uut.read(0x0002), 0x33);
+    }
+
+    #[test]
+    fn should_return_open_bus_value_past_the_image_when_not_mirrored() {
+        let mut uut = Rom::new(&[0x11, 0x22, 0x33]);
+
+        assert_eq!(uut.read(0x0003), OPEN_BUS_VALUE);
+    }
+
+    #[test]
+    fn should_repeat_the_image_when_mirrored() {
+        let mut uut = Rom::new(&[0x11, 0x22, 0x33]).mirrored();
+
+        assert_eq!(uut.read(0x0003), 0x11);
+        assert_eq!(uut.read(0x0007), 0x22);
+    }
+}
+
+#[cfg(test)]
+mod write {
+    use crate::memory::{rom::Rom, Bus};
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn should_ignore_writes() {
+        let mut uut = Rom::new(&[0x11, 0x22, 0x33]);
+
+        uut.write(0x0001, 0xFF);
+
+        assert_eq!(uut.read(0x0001), 0x22);
+    }
+
+    #[test]
+    fn should_pass_ignored_writes_to_logger() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let logger_log = log.clone();
+        let mut uut =
+            Rom::new(&[0x11, 0x22, 0x33]).with_write_logger(Box::new(move |addr, value| {
+                logger_log.borrow_mut().push((addr, value))
+            }));
+
+        uut.write(0x0002, 0xAB);
+
+        assert_eq!(*log.borrow(), vec![(0x0002, 0xAB)]);
+    }
+}
+
+#[cfg(test)]
+mod from_file {
+    use crate::memory::{rom::Rom, Bus};
+    use std::{env, fs};
+
+    #[test]
+    fn should_load_image_from_file() {
+        let path = env::temp_dir().join("cpu6502_rom_from_file_test.bin");
+        fs::write(&path, [0xEA, 0x4C]).unwrap();
+
+        let uut = Rom::from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(uut.len(), 2);
+        assert!(!uut.is_empty());
+        assert_eq!(uut.peek(0x0001), 0x4C);
+    }
+}
+
+#[cfg(test)]
+mod mapped {
+    use crate::memory::{decoder::AddressDecoderBuilder, rom::Rom, Bus, VecMemory};
+
+    #[test]
+    fn should_mirror_small_image_across_its_whole_window() {
+        let mut monitor = vec![0x00; 0x0800];
+        monitor[0x07FC] = 0x00;
+        monitor[0x07FD] = 0xF8;
+        let mut uut = AddressDecoderBuilder::new()
+            .map(0x0000, 0x7FFF, Box::new(VecMemory::with_size(0x8000)))
+            .map(0xF000, 0xFFFF, Box::new(Rom::new(&monitor).mirrored()))
+            .build()
+            .unwrap();
+
+        uut.write(0xFFFD, 0x12);
+
+        assert_eq!(uut.read(0xFFFD), 0xF8);
+        assert_eq!(uut.read(0xF7FD), 0xF8);
+    }
+}
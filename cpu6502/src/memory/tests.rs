@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod vec_memory {
+    use crate::memory::{Bus, VecMemory};
+
+    #[test]
+    fn should_read_values_written_through_bus() {
+        let mut uut = VecMemory::new();
+
+        uut.write(0x1234, 0x42);
+
+        assert_eq!(uut.read(0x1234), 0x42);
+    }
+
+    #[test]
+    fn should_peek_the_same_value_as_read() {
+        let mut uut = VecMemory::from(&[(0xFFFF, 0x88)][..]);
+
+        assert_eq!(uut.peek(0xFFFF), 0x88);
+        assert_eq!(uut.read(0xFFFF), 0x88);
+    }
+}
+
+#[cfg(test)]
+mod bus {
+    use crate::consts::{Byte, Word};
+    use crate::memory::Bus;
+
+    struct ClearOnReadMock {
+        flag: Byte,
+    }
+
+    impl Bus for ClearOnReadMock {
+        fn read(&mut self, _addr: Word) -> Byte {
+            let value = self.flag;
+            self.flag = 0;
+            return value;
+        }
+
+        fn write(&mut self, _addr: Word, value: Byte) {
+            self.flag = value;
+        }
+
+        fn peek(&self, _addr: Word) -> Byte {
+            return self.flag;
+        }
+    }
+
+    #[test]
+    fn should_not_have_side_effects_when_peeking() {
+        let uut = ClearOnReadMock { flag: 0x80 };
+
+        uut.peek(0x0000);
+
+        assert_eq!(uut.peek(0x0000), 0x80);
+    }
+
+    #[test]
+    fn should_allow_reads_to_have_side_effects() {
+        let mut uut = ClearOnReadMock { flag: 0x80 };
+
+        assert_eq!(uut.read(0x0000), 0x80);
+        assert_eq!(uut.read(0x0000), 0x00);
+    }
+}
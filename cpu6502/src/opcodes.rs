@@ -0,0 +1,244 @@
+//! Table of the documented NMOS 6502 instructions, shared by the
+//! disassembler and the assembler.
+
+use crate::consts::Byte;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Implied,
+    Accumulator,
+    Immediate,
+    ZeroPage,
+    ZeroPageX,
+    ZeroPageY,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    Indirect,
+    IndexedIndirectX,
+    IndirectIndexedY,
+    Relative,
+}
+
+impl Mode {
+    /// Number of operand bytes following the opcode.
+    pub fn operand_len(self) -> usize {
+        return match self {
+            Mode::Implied | Mode::Accumulator => 0,
+            Mode::Absolute | Mode::AbsoluteX | Mode::AbsoluteY | Mode::Indirect => 2,
+            _ => 1,
+        };
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Opcode {
+    pub opcode: Byte,
+    pub mnemonic: &'static str,
+    pub mode: Mode,
+}
+
+impl Opcode {
+    /// Length of the whole instruction in bytes.
+    pub fn size(&self) -> usize {
+        return 1 + self.mode.operand_len();
+    }
+}
+
+const fn op(opcode: Byte, mnemonic: &'static str, mode: Mode) -> Opcode {
+    return Opcode {
+        opcode,
+        mnemonic,
+        mode,
+    };
+}
+
+pub const OPCODES: [Opcode; 151] = [
+    op(0x69, "ADC", Mode::Immediate),
+    op(0x65, "ADC", Mode::ZeroPage),
+    op(0x75, "ADC", Mode::ZeroPageX),
+    op(0x6D, "ADC", Mode::Absolute),
+    op(0x7D, "ADC", Mode::AbsoluteX),
+    op(0x79, "ADC", Mode::AbsoluteY),
+    op(0x61, "ADC", Mode::IndexedIndirectX),
+    op(0x71, "ADC", Mode::IndirectIndexedY),
+    op(0x29, "AND", Mode::Immediate),
+    op(0x25, "AND", Mode::ZeroPage),
+    op(0x35, "AND", Mode::ZeroPageX),
+    op(0x2D, "AND", Mode::Absolute),
+    op(0x3D, "AND", Mode::AbsoluteX),
+    op(0x39, "AND", Mode::AbsoluteY),
+    op(0x21, "AND", Mode::IndexedIndirectX),
+    op(0x31, "AND", Mode::IndirectIndexedY),
+    op(0x0A, "ASL", Mode::Accumulator),
+    op(0x06, "ASL", Mode::ZeroPage),
+    op(0x16, "ASL", Mode::ZeroPageX),
+    op(0x0E, "ASL", Mode::Absolute),
+    op(0x1E, "ASL", Mode::AbsoluteX),
+    op(0x90, "BCC", Mode::Relative),
+    op(0xB0, "BCS", Mode::Relative),
+    op(0xF0, "BEQ", Mode::Relative),
+    op(0x24, "BIT", Mode::ZeroPage),
+    op(0x2C, "BIT", Mode::Absolute),
+    op(0x30, "BMI", Mode::Relative),
+    op(0xD0, "BNE", Mode::Relative),
+    op(0x10, "BPL", Mode::Relative),
+    op(0x00, "BRK", Mode::Implied),
+    op(0x50, "BVC", Mode::Relative),
+    op(0x70, "BVS", Mode::Relative),
+    op(0x18, "CLC", Mode::Implied),
+    op(0xD8, "CLD", Mode::Implied),
+    op(0x58, "CLI", Mode::Implied),
+    op(0xB8, "CLV", Mode::Implied),
+    op(0xC9, "CMP", Mode::Immediate),
+    op(0xC5, "CMP", Mode::ZeroPage),
+    op(0xD5, "CMP", Mode::ZeroPageX),
+    op(0xCD, "CMP", Mode::Absolute),
+    op(0xDD, "CMP", Mode::AbsoluteX),
+    op(0xD9, "CMP", Mode::AbsoluteY),
+    op(0xC1, "CMP", Mode::IndexedIndirectX),
+    op(0xD1, "CMP", Mode::IndirectIndexedY),
+    op(0xE0, "CPX", Mode::Immediate),
+    op(0xE4, "CPX", Mode::ZeroPage),
+    op(0xEC, "CPX", Mode::Absolute),
+    op(0xC0, "CPY", Mode::Immediate),
+    op(0xC4, "CPY", Mode::ZeroPage),
+    op(0xCC, "CPY", Mode::Absolute),
+    op(0xC6, "DEC", Mode::ZeroPage),
+    op(0xD6, "DEC", Mode::ZeroPageX),
+    op(0xCE, "DEC", Mode::Absolute),
+    op(0xDE, "DEC", Mode::AbsoluteX),
+    op(0xCA, "DEX", Mode::Implied),
+    op(0x88, "DEY", Mode::Implied),
+    op(0x49, "EOR", Mode::Immediate),
+    op(0x45, "EOR", Mode::ZeroPage),
+    op(0x55, "EOR", Mode::ZeroPageX),
+    op(0x4D, "EOR", Mode::Absolute),
+    op(0x5D, "EOR", Mode::AbsoluteX),
+    op(0x59, "EOR", Mode::AbsoluteY),
+    op(0x41, "EOR", Mode::IndexedIndirectX),
+    op(0x51, "EOR", Mode::IndirectIndexedY),
+    op(0xE6, "INC", Mode::ZeroPage),
+    op(0xF6, "INC", Mode::ZeroPageX),
+    op(0xEE, "INC", Mode::Absolute),
+    op(0xFE, "INC", Mode::AbsoluteX),
+    op(0xE8, "INX", Mode::Implied),
+    op(0xC8, "INY", Mode::Implied),
+    op(0x4C, "JMP", Mode::Absolute),
+    op(0x6C, "JMP", Mode::Indirect),
+    op(0x20, "JSR", Mode::Absolute),
+    op(0xA9, "LDA", Mode::Immediate),
+    op(0xA5, "LDA", Mode::ZeroPage),
+    op(0xB5, "LDA", Mode::ZeroPageX),
+    op(0xAD, "LDA", Mode::Absolute),
+    op(0xBD, "LDA", Mode::AbsoluteX),
+    op(0xB9, "LDA", Mode::AbsoluteY),
+    op(0xA1, "LDA", Mode::IndexedIndirectX),
+    op(0xB1, "LDA", Mode::IndirectIndexedY),
+    op(0xA2, "LDX", Mode::Immediate),
+    op(0xA6, "LDX", Mode::ZeroPage),
+    op(0xB6, "LDX", Mode::ZeroPageY),
+    op(0xAE, "LDX", Mode::Absolute),
+    op(0xBE, "LDX", Mode::AbsoluteY),
+    op(0xA0, "LDY", Mode::Immediate),
+    op(0xA4, "LDY", Mode::ZeroPage),
+    op(0xB4, "LDY", Mode::ZeroPageX),
+    op(0xAC, "LDY", Mode::Absolute),
+    op(0xBC, "LDY", Mode::AbsoluteX),
+    op(0x4A, "LSR", Mode::Accumulator),
+    op(0x46, "LSR", Mode::ZeroPage),
+    op(0x56, "LSR", Mode::ZeroPageX),
+    op(0x4E, "LSR", Mode::Absolute),
+    op(0x5E, "LSR", Mode::AbsoluteX),
+    op(0xEA, "NOP", Mode::Implied),
+    op(0x09, "ORA", Mode::Immediate),
+    op(0x05, "ORA", Mode::ZeroPage),
+    op(0x15, "ORA", Mode::ZeroPageX),
+    op(0x0D, "ORA", Mode::Absolute),
+    op(0x1D, "ORA", Mode::AbsoluteX),
+    op(0x19, "ORA", Mode::AbsoluteY),
+    op(0x01, "ORA", Mode::IndexedIndirectX),
+    op(0x11, "ORA", Mode::IndirectIndexedY),
+    op(0x48, "PHA", Mode::Implied),
+    op(0x08, "PHP", Mode::Implied),
+    op(0x68, "PLA", Mode::Implied),
+    op(0x28, "PLP", Mode::Implied),
+    op(0x2A, "ROL", Mode::Accumulator),
+    op(0x26, "ROL", Mode::ZeroPage),
+    op(0x36, "ROL", Mode::ZeroPageX),
+    op(0x2E, "ROL", Mode::Absolute),
+    op(0x3E, "ROL", Mode::AbsoluteX),
+    op(0x6A, "ROR", Mode::Accumulator),
+    op(0x66, "ROR", Mode::ZeroPage),
+    op(0x76, "ROR", Mode::ZeroPageX),
+    op(0x6E, "ROR", Mode::Absolute),
+    op(0x7E, "ROR", Mode::AbsoluteX),
+    op(0x40, "RTI", Mode::Implied),
+    op(0x60, "RTS", Mode::Implied),
+    op(0xE9, "SBC", Mode::Immediate),
+    op(0xE5, "SBC", Mode::ZeroPage),
+    op(0xF5, "SBC", Mode::ZeroPageX),
+    op(0xED, "SBC", Mode::Absolute),
+    op(0xFD, "SBC", Mode::AbsoluteX),
+    op(0xF9, "SBC", Mode::AbsoluteY),
+    op(0xE1, "SBC", Mode::IndexedIndirectX),
+    op(0xF1, "SBC", Mode::IndirectIndexedY),
+    op(0x38, "SEC", Mode::Implied),
+    op(0xF8, "SED", Mode::Implied),
+    op(0x78, "SEI", Mode::Implied),
+    op(0x85, "STA", Mode::ZeroPage),
+    op(0x95, "STA", Mode::ZeroPageX),
+    op(0x8D, "STA", Mode::Absolute),
+    op(0x9D, "STA", Mode::AbsoluteX),
+    op(0x99, "STA", Mode::AbsoluteY),
+    op(0x81, "STA", Mode::IndexedIndirectX),
+    op(0x91, "STA", Mode::IndirectIndexedY),
+    op(0x86, "STX", Mode::ZeroPage),
+    op(0x96, "STX", Mode::ZeroPageY),
+    op(0x8E, "STX", Mode::Absolute),
+    op(0x84, "STY", Mode::ZeroPage),
+    op(0x94, "STY", Mode::ZeroPageX),
+    op(0x8C, "STY", Mode::Absolute),
+    op(0xAA, "TAX", Mode::Implied),
+    op(0xA8, "TAY", Mode::Implied),
+    op(0xBA, "TSX", Mode::Implied),
+    op(0x8A, "TXA", Mode::Implied),
+    op(0x9A, "TXS", Mode::Implied),
+    op(0x98, "TYA", Mode::Implied),
+];
+
+const fn build_table() -> [Option<Opcode>; 256] {
+    let mut table = [None; 256];
+    let mut idx = 0;
+    while idx < OPCODES.len() {
+        table[OPCODES[idx].opcode as usize] = Some(OPCODES[idx]);
+        idx += 1;
+    }
+
+    return table;
+}
+
+const TABLE: [Option<Opcode>; 256] = build_table();
+
+/// Returns the documented instruction encoded by `opcode`, `None` for illegal opcodes.
+pub fn lookup(opcode: Byte) -> Option<Opcode> {
+    return TABLE[usize::from(opcode)];
+}
+
+/// Finds the opcode of `mnemonic` (case insensitive) in the given addressing mode.
+pub fn find(mnemonic: &str, mode: Mode) -> Option<Opcode> {
+    return OPCODES
+        .iter()
+        .find(|entry| entry.mode == mode && entry.mnemonic.eq_ignore_ascii_case(mnemonic))
+        .copied();
+}
+
+/// Whether `mnemonic` names any documented instruction.
+pub fn is_mnemonic(mnemonic: &str) -> bool {
+    return OPCODES
+        .iter()
+        .any(|entry| entry.mnemonic.eq_ignore_ascii_case(mnemonic));
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod table {
+    use crate::opcodes::{find, is_mnemonic, lookup, Mode, OPCODES};
+    use std::collections::HashSet;
+
+    #[test]
+    fn should_list_every_opcode_once() {
+        let unique: HashSet<u8> = OPCODES.iter().map(|entry| entry.opcode).collect();
+
+        assert_eq!(unique.len(), OPCODES.len());
+        assert_eq!((0..=255u8).filter(|op| lookup(*op).is_some()).count(), 151);
+    }
+
+    #[test]
+    fn should_look_up_opcodes_and_their_lengths() {
+        let lda = lookup(0xB1).unwrap();
+
+        assert_eq!(lda.mnemonic, "LDA");
+        assert_eq!(lda.mode, Mode::IndirectIndexedY);
+        assert_eq!(lda.size(), 2);
+        assert_eq!(lookup(0x6C).unwrap().size(), 3);
+        assert_eq!(lookup(0x0A).unwrap().size(), 1);
+        assert_eq!(lookup(0x02), None);
+    }
+
+    #[test]
+    fn should_find_opcode_by_mnemonic_and_mode() {
+        assert_eq!(find("sta", Mode::AbsoluteY).unwrap().opcode, 0x99);
+        assert_eq!(find("STA", Mode::Immediate), None);
+        assert!(is_mnemonic("rts"));
+        assert!(!is_mnemonic("XYZ"));
+    }
+}
@@ -0,0 +1,406 @@
+//! Cycle profiler attributing the cycles of every instruction to its address.
+//!
+//! [`Profiler`] is an [`InstructionTracer`]: set it on the CPU, run, then call
+//! [`Profiler::finish`]. Functions are entered by `JSR` and by interrupts and left by
+//! `RTS` and `RTI`, the code running when profiling started is the root function.
+//! The cycles of an interrupt sequence are charged to the first instruction of its handler.
+//!
+//! Profiles are written in the callgrind format for KCachegrind, or as a plain text
+//! report of the hottest instructions and functions.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use crate::{
+    consts::{Byte, Word},
+    cpu::{
+        trace::{InstructionTracer, TraceEntry},
+        CpuState,
+    },
+    memory::Bus,
+};
+
+const INSTRUCTION_JSR: Byte = 0x20;
+const INSTRUCTION_RTI: Byte = 0x40;
+const INSTRUCTION_RTS: Byte = 0x60;
+/// Distance from a symbol up to which addresses are named relative to it.
+const MAX_SYMBOL_OFFSET: Word = 0xFF;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct InstructionCost {
+    pub count: u64,
+    pub cycles: u64,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct CallCost {
+    pub count: u64,
+    /// Cycles spent in the callee and everything it called.
+    pub cycles: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FunctionCost {
+    pub calls: u64,
+    /// Cycles spent in the function's own instructions.
+    pub exclusive: u64,
+    /// Cycles spent in the function and everything it called, recursive calls counted once.
+    pub inclusive: u64,
+    /// Own cycles by instruction address.
+    pub instructions: BTreeMap<Word, u64>,
+    /// Calls made by the function by call site and callee.
+    pub callees: BTreeMap<(Word, Word), CallCost>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Profile {
+    pub instructions: BTreeMap<Word, InstructionCost>,
+    /// Functions by entry address.
+    pub functions: BTreeMap<Word, FunctionCost>,
+}
+
+/// Names of addresses, used to label profiles.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Symbols {
+    names: BTreeMap<Word, String>,
+}
+
+impl Symbols {
+    pub fn new() -> Self {
+        return Symbols::default();
+    }
+
+    /// Reads `NAME $ADDR` lines as in the symbol section of linker maps and
+    /// `al C:ADDR .NAME` lines of VICE label files, other lines are skipped.
+    pub fn parse(text: &str) -> Self {
+        let mut symbols = Symbols::new();
+        for line in text.lines() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let symbol = match tokens[..] {
+                [name, address] => address.strip_prefix('$').map(|address| (address, name)),
+                ["al", address, name] => address.strip_prefix("C:").zip(name.strip_prefix('.')),
+                _ => None,
+            };
+            let symbol = symbol
+                .and_then(|(address, name)| Some((Word::from_str_radix(address, 16).ok()?, name)));
+            if let Some((address, name)) = symbol {
+                symbols.insert(address, name);
+            }
+        }
+
+        return symbols;
+    }
+
+    /// Names `address`, the first name given to an address is kept.
+    pub fn insert(&mut self, address: Word, name: &str) {
+        self.names
+            .entry(address)
+            .or_insert_with(|| name.to_string());
+    }
+
+    /// Names `address` after the closest symbol at or below it, e.g. `print+3`.
+    pub fn label(&self, address: Word) -> Option<String> {
+        return match self.names.range(..=address).next_back() {
+            Some((symbol, name)) if *symbol == address => Some(name.clone()),
+            Some((symbol, name)) if address - symbol <= MAX_SYMBOL_OFFSET => {
+                Some(format!("{name}+{}", address - symbol))
+            }
+            _ => None,
+        };
+    }
+
+    /// Like [`Symbols::label`], falling back to the address, e.g. `$0203`.
+    pub fn describe(&self, address: Word) -> String {
+        return self
+            .label(address)
+            .unwrap_or_else(|| format!("${address:04X}"));
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum PendingKind {
+    Instruction(Byte),
+    Interrupt,
+}
+
+/// Instruction or interrupt sequence whose cost is known once the next one starts.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Pending {
+    /// Instruction address, the return address for interrupts.
+    address: Word,
+    cycle: u64,
+    kind: PendingKind,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Frame {
+    function: Word,
+    /// Call site and calling function, `None` for the root function.
+    caller: Option<(Word, Word)>,
+    start_cycle: u64,
+}
+
+#[derive(Default)]
+pub struct Profiler {
+    profile: Profile,
+    stack: Vec<Frame>,
+    pending: Option<Pending>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        return Profiler::default();
+    }
+
+    pub fn profile(&self) -> &Profile {
+        return &self.profile;
+    }
+
+    /// Charges the last instruction with the cycles up to `cycle` and leaves all
+    /// functions still running, so their inclusive cycles are complete.
+    pub fn finish(&mut self, cycle: u64) {
+        if let Some(pending) = self.pending.take() {
+            self.charge(pending, cycle);
+        }
+        while !self.stack.is_empty() {
+            self.leave(cycle);
+        }
+    }
+
+    fn charge(&mut self, pending: Pending, cycle: u64) {
+        let cycles = cycle.saturating_sub(pending.cycle);
+        let (address, count) = match pending.kind {
+            PendingKind::Instruction(_) => (pending.address, 1),
+            PendingKind::Interrupt => match self.stack.last() {
+                Some(frame) => (frame.function, 0),
+                None => return,
+            },
+        };
+
+        let instruction = self.profile.instructions.entry(address).or_default();
+        instruction.count += count;
+        instruction.cycles += cycles;
+        if let Some(frame) = self.stack.last() {
+            let function = self.profile.functions.entry(frame.function).or_default();
+            function.exclusive += cycles;
+            *function.instructions.entry(address).or_default() += cycles;
+        }
+    }
+
+    fn enter(&mut self, function: Word, call_site: Word, start_cycle: u64) {
+        let caller = self.stack.last().map(|frame| (call_site, frame.function));
+        if let Some((call_site, caller)) = caller {
+            let callee = self
+                .profile
+                .functions
+                .entry(caller)
+                .or_default()
+                .callees
+                .entry((call_site, function))
+                .or_default();
+            callee.count += 1;
+        }
+        self.profile.functions.entry(function).or_default().calls += 1;
+        self.stack.push(Frame {
+            function,
+            caller,
+            start_cycle,
+        });
+    }
+
+    fn leave(&mut self, cycle: u64) {
+        let Some(frame) = self.stack.pop() else {
+            return;
+        };
+        let cycles = cycle.saturating_sub(frame.start_cycle);
+
+        let recursive = self
+            .stack
+            .iter()
+            .any(|outer| outer.function == frame.function);
+        if !recursive {
+            self.profile
+                .functions
+                .entry(frame.function)
+                .or_default()
+                .inclusive += cycles;
+        }
+        if let Some((call_site, caller)) = frame.caller {
+            let call = self
+                .profile
+                .functions
+                .entry(caller)
+                .or_default()
+                .callees
+                .entry((call_site, frame.function))
+                .or_default();
+            call.cycles += cycles;
+        }
+    }
+
+    /// Completes the pending instruction or interrupt now that the CPU moved on to
+    /// `next_address` at `cycle`.
+    fn complete(&mut self, cycle: u64, next_address: Word) {
+        if self.stack.is_empty() {
+            self.stack.push(Frame {
+                function: next_address,
+                caller: None,
+                start_cycle: cycle,
+            });
+            self.profile
+                .functions
+                .entry(next_address)
+                .or_default()
+                .calls += 1;
+        }
+        let Some(pending) = self.pending.take() else {
+            return;
+        };
+
+        match pending.kind {
+            PendingKind::Instruction(opcode) => {
+                self.charge(pending, cycle);
+                match opcode {
+                    INSTRUCTION_JSR => self.enter(next_address, pending.address, cycle),
+                    // The root function is never left, RTS is also used as an indirect jump.
+                    INSTRUCTION_RTS | INSTRUCTION_RTI if self.stack.len() > 1 => self.leave(cycle),
+                    _ => (),
+                }
+            }
+            PendingKind::Interrupt => {
+                self.enter(next_address, pending.address, pending.cycle);
+                self.charge(pending, cycle);
+            }
+        }
+    }
+}
+
+impl InstructionTracer for Profiler {
+    fn trace(&mut self, entry: &TraceEntry, _memory: &dyn Bus) {
+        let state = &entry.state;
+        self.complete(state.cycle, state.program_counter);
+        self.pending = Some(Pending {
+            address: state.program_counter,
+            cycle: state.cycle,
+            kind: PendingKind::Instruction(entry.instruction.opcode),
+        });
+    }
+
+    fn interrupt(&mut self, state: &CpuState) {
+        self.complete(state.cycle, state.program_counter);
+        self.pending = Some(Pending {
+            address: state.program_counter,
+            cycle: state.cycle,
+            kind: PendingKind::Interrupt,
+        });
+    }
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    return part as f64 * 100.0 / total as f64;
+}
+
+impl Profile {
+    pub fn total_cycles(&self) -> u64 {
+        return self
+            .instructions
+            .values()
+            .map(|instruction| instruction.cycles)
+            .sum();
+    }
+
+    pub fn total_instructions(&self) -> u64 {
+        return self
+            .instructions
+            .values()
+            .map(|instruction| instruction.count)
+            .sum();
+    }
+
+    /// Writes the profile in the callgrind format, positions being instruction addresses.
+    pub fn write_callgrind(&self, symbols: &Symbols, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "# callgrind format")?;
+        writeln!(out, "version: 1")?;
+        writeln!(out, "creator: emu6502")?;
+        writeln!(out, "positions: instr")?;
+        writeln!(out, "events: Cycles")?;
+        writeln!(out, "summary: {}", self.total_cycles())?;
+
+        for (address, function) in &self.functions {
+            writeln!(out)?;
+            writeln!(out, "fn={}", symbols.describe(*address))?;
+            for (instruction, cycles) in &function.instructions {
+                writeln!(out, "0x{instruction:04X} {cycles}")?;
+            }
+            for ((call_site, callee), call) in &function.callees {
+                writeln!(out, "cfn={}", symbols.describe(*callee))?;
+                writeln!(out, "calls={} 0x{callee:04X}", call.count)?;
+                writeln!(out, "0x{call_site:04X} {}", call.cycles)?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Writes the `top` instructions and functions with the most cycles.
+    pub fn write_report(
+        &self,
+        symbols: &Symbols,
+        top: usize,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        let total = self.total_cycles();
+        writeln!(
+            out,
+            "{total} cycles in {} instructions",
+            self.total_instructions()
+        )?;
+
+        let mut instructions: Vec<(&Word, &InstructionCost)> = self.instructions.iter().collect();
+        instructions.sort_by(|a, b| b.1.cycles.cmp(&a.1.cycles).then(a.0.cmp(b.0)));
+        writeln!(out)?;
+        writeln!(out, "hottest instructions:")?;
+        writeln!(out, "      cycles       %      count  address")?;
+        for (address, cost) in instructions.into_iter().take(top) {
+            let line = format!(
+                "{:>12} {:>6.2}% {:>10}  ${address:04X}  {}",
+                cost.cycles,
+                percent(cost.cycles, total),
+                cost.count,
+                symbols.label(*address).unwrap_or_default()
+            );
+            writeln!(out, "{}", line.trim_end())?;
+        }
+
+        let mut functions: Vec<(&Word, &FunctionCost)> = self.functions.iter().collect();
+        functions.sort_by(|a, b| b.1.inclusive.cmp(&a.1.inclusive).then(a.0.cmp(b.0)));
+        writeln!(out)?;
+        writeln!(out, "hottest functions:")?;
+        writeln!(
+            out,
+            "   exclusive       %    inclusive       %      calls  function"
+        )?;
+        for (address, cost) in functions.into_iter().take(top) {
+            writeln!(
+                out,
+                "{:>12} {:>6.2}% {:>12} {:>6.2}% {:>10}  {}",
+                cost.exclusive,
+                percent(cost.exclusive, total),
+                cost.inclusive,
+                percent(cost.inclusive, total),
+                cost.calls,
+                symbols.describe(*address)
+            )?;
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,243 @@
+use crate::{
+    consts::{Byte, Word},
+    cpu::{
+        trace::{InstructionTracer, TraceEntry},
+        CpuState,
+    },
+    disasm::decode_bytes,
+    memory::VecMemory,
+    profiler::Profiler,
+};
+
+const NOP: &[Byte] = &[0xEA];
+const RTS: &[Byte] = &[0x60];
+const RTI: &[Byte] = &[0x40];
+
+fn state(program_counter: Word, cycle: u64) -> CpuState {
+    return CpuState {
+        cycle,
+        program_counter,
+        stack_pointer: 0xFD,
+        accumulator: 0x00,
+        index_register_x: 0x00,
+        index_register_y: 0x00,
+        processor_status: 0x24,
+    };
+}
+
+fn jsr(target: Word) -> [Byte; 3] {
+    let [lo, hi] = target.to_le_bytes();
+    return [0x20, lo, hi];
+}
+
+fn trace(uut: &mut Profiler, program_counter: Word, bytes: &[Byte], cycle: u64) {
+    let entry = TraceEntry {
+        state: state(program_counter, cycle),
+        instruction: decode_bytes(bytes, program_counter),
+    };
+    uut.trace(&entry, &VecMemory::new());
+}
+
+/// Root at $0200 calling $0300, which returns after a NOP.
+fn profile_call() -> Profiler {
+    let mut uut = Profiler::new();
+    trace(&mut uut, 0x0200, &jsr(0x0300), 0);
+    trace(&mut uut, 0x0300, NOP, 6);
+    trace(&mut uut, 0x0301, RTS, 8);
+    trace(&mut uut, 0x0203, NOP, 14);
+    uut.finish(16);
+
+    return uut;
+}
+
+#[cfg(test)]
+mod profiler {
+    use super::{jsr, profile_call, state, trace, NOP, RTI, RTS};
+    use crate::cpu::trace::InstructionTracer;
+    use crate::profiler::{CallCost, InstructionCost, Profiler};
+
+    #[test]
+    fn should_attribute_cycles_to_instructions_and_functions() {
+        let uut = profile_call();
+        let profile = uut.profile();
+
+        assert_eq!(profile.total_cycles(), 16);
+        assert_eq!(profile.total_instructions(), 4);
+        assert_eq!(
+            profile.instructions[&0x0301],
+            InstructionCost {
+                count: 1,
+                cycles: 6
+            }
+        );
+        let root = &profile.functions[&0x0200];
+        let callee = &profile.functions[&0x0300];
+        assert_eq!((root.calls, root.exclusive, root.inclusive), (1, 8, 16));
+        assert_eq!(
+            (callee.calls, callee.exclusive, callee.inclusive),
+            (1, 8, 8)
+        );
+        assert_eq!(
+            root.callees[&(0x0200, 0x0300)],
+            CallCost {
+                count: 1,
+                cycles: 8
+            }
+        );
+    }
+
+    #[test]
+    fn should_charge_interrupt_sequence_to_handler() {
+        let mut uut = Profiler::new();
+        trace(&mut uut, 0x0200, NOP, 0);
+        uut.interrupt(&state(0x0201, 2));
+        trace(&mut uut, 0x0400, NOP, 9);
+        trace(&mut uut, 0x0401, RTI, 11);
+        trace(&mut uut, 0x0201, NOP, 17);
+        uut.finish(19);
+        let profile = uut.profile();
+
+        let handler = &profile.functions[&0x0400];
+        assert_eq!(
+            (handler.calls, handler.exclusive, handler.inclusive),
+            (1, 15, 15)
+        );
+        assert_eq!(
+            profile.instructions[&0x0400],
+            InstructionCost {
+                count: 1,
+                cycles: 9
+            }
+        );
+        assert_eq!(profile.functions[&0x0200].exclusive, 4);
+        assert_eq!(
+            profile.functions[&0x0200].callees[&(0x0201, 0x0400)],
+            CallCost {
+                count: 1,
+                cycles: 15
+            }
+        );
+    }
+
+    #[test]
+    fn should_count_recursive_calls_once_in_inclusive_cycles() {
+        let mut uut = Profiler::new();
+        trace(&mut uut, 0x0200, &jsr(0x0300), 0);
+        trace(&mut uut, 0x0300, &jsr(0x0300), 6);
+        trace(&mut uut, 0x0300, RTS, 12);
+        trace(&mut uut, 0x0303, RTS, 18);
+        trace(&mut uut, 0x0203, NOP, 24);
+        uut.finish(26);
+
+        let function = &uut.profile().functions[&0x0300];
+        assert_eq!(function.calls, 2);
+        assert_eq!(function.exclusive, 18);
+        assert_eq!(function.inclusive, 18);
+    }
+
+    #[test]
+    fn should_stay_in_root_function_on_unbalanced_return() {
+        let mut uut = Profiler::new();
+        trace(&mut uut, 0x0200, RTS, 0);
+        trace(&mut uut, 0x1234, NOP, 6);
+        uut.finish(8);
+
+        let profile = uut.profile();
+        assert_eq!(profile.functions.len(), 1);
+        assert_eq!(profile.functions[&0x0200].exclusive, 8);
+        assert_eq!(profile.functions[&0x0200].inclusive, 8);
+    }
+}
+
+#[cfg(test)]
+mod symbols {
+    use crate::profiler::Symbols;
+
+    #[test]
+    fn should_parse_linker_maps_and_vice_labels() {
+        let uut = Symbols::parse(
+            "symbols:\n\
+             main $0200\n\
+             al C:0300 .print\n\
+             al C:0300 .duplicate\n\
+             segment CODE $0200 $02FF\n",
+        );
+
+        assert_eq!(uut.describe(0x0200), "main");
+        assert_eq!(uut.describe(0x0300), "print");
+        assert_eq!(uut.describe(0x0303), "print+3");
+    }
+
+    #[test]
+    fn should_fall_back_to_addresses_far_from_symbols() {
+        let mut uut = Symbols::new();
+        uut.insert(0x0200, "main");
+
+        assert_eq!(uut.label(0x01FF), None);
+        assert_eq!(uut.describe(0x01FF), "$01FF");
+        assert_eq!(uut.describe(0x02FF), "main+255");
+        assert_eq!(uut.describe(0x0300), "$0300");
+    }
+}
+
+#[cfg(test)]
+mod output {
+    use super::profile_call;
+    use crate::profiler::Symbols;
+
+    #[test]
+    fn should_write_callgrind_format() {
+        let uut = profile_call();
+        let mut symbols = Symbols::new();
+        symbols.insert(0x0300, "print");
+        let mut out = vec![];
+
+        uut.profile().write_callgrind(&symbols, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "# callgrind format\n\
+             version: 1\n\
+             creator: emu6502\n\
+             positions: instr\n\
+             events: Cycles\n\
+             summary: 16\n\
+             \n\
+             fn=$0200\n\
+             0x0200 6\n\
+             0x0203 2\n\
+             cfn=print\n\
+             calls=1 0x0300\n\
+             0x0200 8\n\
+             \n\
+             fn=print\n\
+             0x0300 2\n\
+             0x0301 6\n"
+        );
+    }
+
+    #[test]
+    fn should_write_top_hotspots() {
+        let uut = profile_call();
+        let mut symbols = Symbols::new();
+        symbols.insert(0x0300, "print");
+        let mut out = vec![];
+
+        uut.profile().write_report(&symbols, 2, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "16 cycles in 4 instructions\n\
+             \n\
+             hottest instructions:\n\
+             \x20     cycles       %      count  address\n\
+             \x20          6  37.50%          1  $0200\n\
+             \x20          6  37.50%          1  $0301  print+1\n\
+             \n\
+             hottest functions:\n\
+             \x20  exclusive       %    inclusive       %      calls  function\n\
+             \x20          8  50.00%           16 100.00%          1  $0200\n\
+             \x20          8  50.00%            8  50.00%          1  print\n"
+        );
+    }
+}
@@ -0,0 +1,407 @@
+//! Compares execution traces with logs of other emulators.
+//!
+//! Logs are parsed into [`TraceRecord`]s, one per executed instruction. Two logs are
+//! aligned at the first instruction they have in common and compared instruction by
+//! instruction until the registers, flags or cycle counts of a pair differ.
+//!
+//! Supported logs are the ones written by [`crate::cpu::trace::Tracer`] in every
+//! [`TraceFormat`](crate::cpu::trace::TraceFormat), nestest.log (with or without the
+//! PPU columns) and the VICE monitor history. MAME traces are read from lines holding
+//! `ADDR:`, optionally with `A=`, `X=`, `Y=`, `P=` and `S=` register values from a
+//! `tracelog` format; trace them with `noloop`, as collapsed loops cannot be aligned.
+
+use std::{fmt, io};
+
+use crate::consts::{Byte, Word};
+
+/// Bits of the processor status compared by default; B and bit 5 only exist on the
+/// stack and emulators disagree on how to show them.
+pub const STATUS_MASK: Byte = 0b1100_1111;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LogFormat {
+    Plain,
+    Nestest,
+    Vice,
+    Mame,
+}
+
+impl LogFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        return match name.to_ascii_lowercase().as_str() {
+            "plain" => Some(LogFormat::Plain),
+            "nestest" | "nes" => Some(LogFormat::Nestest),
+            "vice" => Some(LogFormat::Vice),
+            "mame" => Some(LogFormat::Mame),
+            _ => None,
+        };
+    }
+
+    /// Guesses the format from the first line that looks like a traced instruction.
+    pub fn detect(text: &str) -> Option<Self> {
+        for line in text.lines() {
+            let first = line.split_whitespace().next().unwrap_or("");
+            if first.starts_with(".C:") {
+                return Some(LogFormat::Vice);
+            }
+            if line.contains("CYC:") {
+                return Some(LogFormat::Nestest);
+            }
+            if line.contains("A=$") && parse_word(first).is_some() {
+                return Some(LogFormat::Plain);
+            }
+            if line
+                .split_whitespace()
+                .any(|token| mame_address(token).is_some())
+            {
+                return Some(LogFormat::Mame);
+            }
+        }
+
+        return None;
+    }
+}
+
+/// State before one instruction as found in a log, values the log lacks are `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceRecord {
+    /// 1-based line number in the log.
+    pub line: usize,
+    pub text: String,
+    pub program_counter: Word,
+    pub accumulator: Option<Byte>,
+    pub index_register_x: Option<Byte>,
+    pub index_register_y: Option<Byte>,
+    pub stack_pointer: Option<Byte>,
+    pub processor_status: Option<Byte>,
+    pub cycle: Option<u64>,
+}
+
+fn parse_word(text: &str) -> Option<Word> {
+    if text.len() != 4 {
+        return None;
+    }
+    return Word::from_str_radix(text, 16).ok();
+}
+
+fn parse_byte(text: &str) -> Option<Byte> {
+    let text = text.strip_prefix('$').unwrap_or(text);
+    return Byte::from_str_radix(text, 16).ok();
+}
+
+fn mame_address(token: &str) -> Option<Word> {
+    return parse_word(token.strip_suffix(':')?);
+}
+
+/// Processor status shown as `NV-BDIZC` letters with dots for clear flags.
+fn parse_flags(token: &str) -> Option<Byte> {
+    let token = token
+        .strip_prefix('[')
+        .and_then(|token| token.strip_suffix(']'))
+        .unwrap_or(token);
+    if token.len() != 8 {
+        return None;
+    }
+
+    let mut status = 0;
+    for (idx, (flag, name)) in token.chars().zip("NV-BDIZC".chars()).enumerate() {
+        if flag == name || flag.eq_ignore_ascii_case(&name) {
+            status |= 0x80 >> idx;
+        } else if flag != '.' {
+            return None;
+        }
+    }
+
+    return Some(status);
+}
+
+/// Parses one line, `None` for lines not describing an instruction.
+pub fn parse_line(line: &str, number: usize, format: LogFormat) -> Option<TraceRecord> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let program_counter = match format {
+        LogFormat::Plain | LogFormat::Nestest => parse_word(tokens.first()?)?,
+        LogFormat::Vice => parse_word(tokens.first()?.strip_prefix(".C:")?)?,
+        LogFormat::Mame => tokens.iter().find_map(|token| mame_address(token))?,
+    };
+
+    let mut record = TraceRecord {
+        line: number,
+        text: line.trim_end().to_string(),
+        program_counter,
+        accumulator: None,
+        index_register_x: None,
+        index_register_y: None,
+        stack_pointer: None,
+        processor_status: None,
+        cycle: None,
+    };
+    for token in &tokens {
+        let Some((key, value)) = token.split_once([':', '=']) else {
+            if format == LogFormat::Vice {
+                record.processor_status = record.processor_status.or(parse_flags(token));
+            }
+            continue;
+        };
+        match key.to_ascii_uppercase().as_str() {
+            "A" => record.accumulator = parse_byte(value),
+            "X" => record.index_register_x = parse_byte(value),
+            "Y" => record.index_register_y = parse_byte(value),
+            "SP" | "S" => record.stack_pointer = parse_byte(value),
+            "P" => record.processor_status = parse_byte(value),
+            "CYC" => record.cycle = value.parse().ok(),
+            _ => (),
+        }
+    }
+    if matches!(format, LogFormat::Plain | LogFormat::Vice) {
+        record.cycle = tokens.last().and_then(|token| token.parse().ok());
+    }
+
+    return Some(record);
+}
+
+/// Parses every instruction line of a log, skipping anything else.
+pub fn parse_log(text: &str, format: LogFormat) -> Vec<TraceRecord> {
+    return text
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| parse_line(line, idx + 1, format))
+        .collect();
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Field {
+    ProgramCounter,
+    Accumulator,
+    IndexRegisterX,
+    IndexRegisterY,
+    StackPointer,
+    ProcessorStatus,
+    /// Cycles since the first aligned instruction, the logs may count from different points.
+    Cycles,
+}
+
+/// Differing column of an instruction pair.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FieldDifference {
+    pub field: Field,
+    pub ours: u64,
+    pub reference: u64,
+}
+
+impl fmt::Display for FieldDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (ours, reference) = (self.ours, self.reference);
+        return match self.field {
+            Field::ProgramCounter => write!(f, "PC: ${ours:04X} != ${reference:04X}"),
+            Field::Accumulator => write!(f, "A: ${ours:02X} != ${reference:02X}"),
+            Field::IndexRegisterX => write!(f, "X: ${ours:02X} != ${reference:02X}"),
+            Field::IndexRegisterY => write!(f, "Y: ${ours:02X} != ${reference:02X}"),
+            Field::StackPointer => write!(f, "SP: ${ours:02X} != ${reference:02X}"),
+            Field::ProcessorStatus => {
+                let flags: String = "NV-BDIZC"
+                    .chars()
+                    .enumerate()
+                    .filter(|(idx, _)| (ours ^ reference) & (0x80 >> idx) != 0)
+                    .map(|(_, name)| name)
+                    .collect();
+                write!(f, "P: ${ours:02X} != ${reference:02X} (flags {flags})")
+            }
+            Field::Cycles => write!(f, "cycles: +{ours} != +{reference}"),
+        };
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AlignmentError;
+
+impl fmt::Display for AlignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "the traces have no instruction in common to start from");
+    }
+}
+
+impl std::error::Error for AlignmentError {}
+
+/// Result of comparing two logs.
+#[derive(Debug, PartialEq)]
+pub struct Comparison {
+    /// Indices of the first aligned record in our log and in the reference log.
+    pub ours_start: usize,
+    pub reference_start: usize,
+    /// Number of instruction pairs that matched.
+    pub matched: usize,
+    /// Differences of the first pair that did not match, `None` if all of them did.
+    pub divergence: Option<Vec<FieldDifference>>,
+}
+
+/// Starts both logs at the first program counter they share.
+fn align(
+    ours: &[TraceRecord],
+    reference: &[TraceRecord],
+) -> Result<(usize, usize), AlignmentError> {
+    let (Some(our_first), Some(reference_first)) = (ours.first(), reference.first()) else {
+        return Err(AlignmentError);
+    };
+    if let Some(idx) = ours
+        .iter()
+        .position(|record| record.program_counter == reference_first.program_counter)
+    {
+        return Ok((idx, 0));
+    }
+
+    return reference
+        .iter()
+        .position(|record| record.program_counter == our_first.program_counter)
+        .map(|idx| (0, idx))
+        .ok_or(AlignmentError);
+}
+
+fn differences(
+    ours: &TraceRecord,
+    reference: &TraceRecord,
+    cycles: (Option<u64>, Option<u64>),
+    status_mask: Byte,
+) -> Vec<FieldDifference> {
+    let mut differences = vec![];
+    let mut compare = |field, ours: Option<u64>, reference: Option<u64>| {
+        if let (Some(ours), Some(reference)) = (ours, reference) {
+            if ours != reference {
+                differences.push(FieldDifference {
+                    field,
+                    ours,
+                    reference,
+                });
+            }
+        }
+    };
+    let byte = |value: Option<Byte>| value.map(u64::from);
+    let status = |value: Option<Byte>| value.map(|status| u64::from(status & status_mask));
+
+    compare(
+        Field::ProgramCounter,
+        Some(u64::from(ours.program_counter)),
+        Some(u64::from(reference.program_counter)),
+    );
+    compare(
+        Field::Accumulator,
+        byte(ours.accumulator),
+        byte(reference.accumulator),
+    );
+    compare(
+        Field::IndexRegisterX,
+        byte(ours.index_register_x),
+        byte(reference.index_register_x),
+    );
+    compare(
+        Field::IndexRegisterY,
+        byte(ours.index_register_y),
+        byte(reference.index_register_y),
+    );
+    compare(
+        Field::StackPointer,
+        byte(ours.stack_pointer),
+        byte(reference.stack_pointer),
+    );
+    compare(
+        Field::ProcessorStatus,
+        status(ours.processor_status),
+        status(reference.processor_status),
+    );
+    compare(Field::Cycles, cycles.0, cycles.1);
+
+    return differences;
+}
+
+/// Compares the logs from their alignment point until the first differing pair or
+/// the end of the shorter log. Only bits of `status_mask` of the status are compared.
+pub fn compare(
+    ours: &[TraceRecord],
+    reference: &[TraceRecord],
+    status_mask: Byte,
+) -> Result<Comparison, AlignmentError> {
+    let (ours_start, reference_start) = align(ours, reference)?;
+    let since_start = |records: &[TraceRecord], start: usize, idx: usize| {
+        let first = records[start].cycle?;
+        return records[idx].cycle?.checked_sub(first);
+    };
+
+    let mut matched = 0;
+    let pairs = ours[ours_start..].iter().zip(&reference[reference_start..]);
+    for (offset, (our_record, reference_record)) in pairs.enumerate() {
+        let cycles = (
+            since_start(ours, ours_start, ours_start + offset),
+            since_start(reference, reference_start, reference_start + offset),
+        );
+        let differences = differences(our_record, reference_record, cycles, status_mask);
+        if !differences.is_empty() {
+            return Ok(Comparison {
+                ours_start,
+                reference_start,
+                matched,
+                divergence: Some(differences),
+            });
+        }
+        matched += 1;
+    }
+
+    return Ok(Comparison {
+        ours_start,
+        reference_start,
+        matched,
+        divergence: None,
+    });
+}
+
+impl Comparison {
+    /// Writes a summary, and for a divergence up to `context` matching pairs before it,
+    /// the differing pair and its differing columns.
+    pub fn write_report(
+        &self,
+        ours: &[TraceRecord],
+        reference: &[TraceRecord],
+        context: usize,
+        out: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        let Some(differences) = &self.divergence else {
+            writeln!(out, "traces match for {} instructions", self.matched)?;
+            let ours_left = ours.len() - self.ours_start - self.matched;
+            let reference_left = reference.len() - self.reference_start - self.matched;
+            if ours_left > 0 {
+                writeln!(out, "our trace continues for {ours_left} more instructions")?;
+            }
+            if reference_left > 0 {
+                writeln!(
+                    out,
+                    "reference trace continues for {reference_left} more instructions"
+                )?;
+            }
+            return Ok(());
+        };
+
+        let our_record = &ours[self.ours_start + self.matched];
+        let reference_record = &reference[self.reference_start + self.matched];
+        writeln!(
+            out,
+            "traces diverge after {} matching instructions, at line {} of ours and line {} of the reference:",
+            self.matched, our_record.line, reference_record.line
+        )?;
+        for offset in self.matched.saturating_sub(context)..self.matched {
+            let record = &reference[self.reference_start + offset];
+            writeln!(out, "  {:>8}  {}", record.line, record.text)?;
+        }
+        writeln!(out, "- {:>8}  {}", our_record.line, our_record.text)?;
+        writeln!(
+            out,
+            "+ {:>8}  {}",
+            reference_record.line, reference_record.text
+        )?;
+        for difference in differences {
+            writeln!(out, "  {difference}")?;
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests;
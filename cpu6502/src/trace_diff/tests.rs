@@ -0,0 +1,226 @@
+const NESTEST: &str = "\
+C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7
+C5F5  A2 00     LDX #$00                        A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 30 CYC:10
+C5F7  86 00     STX $00 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 36 CYC:12
+C5F9  86 10     STX $10 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 45 CYC:15
+C5FB  86 11     STX $11 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 54 CYC:18
+";
+
+/// Our run of the same code, starting with a reset and counting cycles from 0.
+const OURS: &str = "\
+FFFC  A9 00     LDA #$00      A=$00 X=$00 Y=$00 SP=$FD P=$04 [.....I..] 0
+C000  4C F5 C5  JMP $C5F5     A=$00 X=$00 Y=$00 SP=$FD P=$04 [.....I..] 2
+C5F5  A2 00     LDX #$00      A=$00 X=$00 Y=$00 SP=$FD P=$04 [.....I..] 5
+C5F7  86 00     STX $00       A=$00 X=$00 Y=$00 SP=$FD P=$06 [.....IZ.] 7
+C5F9  86 10     STX $10       A=$00 X=$00 Y=$00 SP=$FD P=$04 [.....I..] 10
+C5FB  86 11     STX $11       A=$00 X=$00 Y=$00 SP=$FD P=$06 [.....IZ.] 14
+";
+
+#[cfg(test)]
+mod parse {
+    use super::NESTEST;
+    use crate::trace_diff::{parse_line, parse_log, LogFormat, TraceRecord};
+
+    #[test]
+    fn should_detect_log_formats() {
+        assert_eq!(LogFormat::detect(NESTEST), Some(LogFormat::Nestest));
+        assert_eq!(
+            LogFormat::detect(&super::OURS[super::OURS.find('\n').unwrap() + 1..]),
+            Some(LogFormat::Plain)
+        );
+        assert_eq!(
+            LogFormat::detect("\n.C:c000  4C F5 C5  JMP $C5F5      - A:00"),
+            Some(LogFormat::Vice)
+        );
+        assert_eq!(
+            LogFormat::detect("C000: jmp   $c5f5\n"),
+            Some(LogFormat::Mame)
+        );
+        assert_eq!(LogFormat::detect("hello\nworld"), None);
+    }
+
+    #[test]
+    fn should_parse_nestest_lines() {
+        let records = parse_log(&format!("header\n{NESTEST}"), LogFormat::Nestest);
+
+        assert_eq!(records.len(), 5);
+        assert_eq!(
+            records[2],
+            TraceRecord {
+                line: 4,
+                text: NESTEST.lines().nth(2).unwrap().to_string(),
+                program_counter: 0xC5F7,
+                accumulator: Some(0x00),
+                index_register_x: Some(0x00),
+                index_register_y: Some(0x00),
+                stack_pointer: Some(0xFD),
+                processor_status: Some(0x26),
+                cycle: Some(12),
+            }
+        );
+    }
+
+    #[test]
+    fn should_parse_vice_lines() {
+        let record = parse_line(
+            ".C:e5cd  A5 C6     LDA $C6        - A:01 X:02 Y:0A SP:f3 N.-...Z.    5634521",
+            1,
+            LogFormat::Vice,
+        )
+        .unwrap();
+
+        assert_eq!(record.program_counter, 0xE5CD);
+        assert_eq!(record.accumulator, Some(0x01));
+        assert_eq!(record.stack_pointer, Some(0xF3));
+        assert_eq!(record.processor_status, Some(0xA2));
+        assert_eq!(record.cycle, Some(5634521));
+    }
+
+    #[test]
+    fn should_parse_mame_lines_with_and_without_registers() {
+        let plain = parse_line("C000: jmp   $c5f5", 1, LogFormat::Mame).unwrap();
+        let registers = parse_line(
+            "A=01 X=02 Y=03 P=24 S=FD C5F5: ldx   #$00",
+            2,
+            LogFormat::Mame,
+        )
+        .unwrap();
+
+        assert_eq!(plain.program_counter, 0xC000);
+        assert_eq!(plain.accumulator, None);
+        assert_eq!(registers.program_counter, 0xC5F5);
+        assert_eq!(registers.index_register_y, Some(0x03));
+        assert_eq!(registers.stack_pointer, Some(0xFD));
+        assert_eq!(registers.cycle, None);
+        assert!(parse_line("   (loops for 3 instructions)", 3, LogFormat::Mame).is_none());
+    }
+}
+
+#[cfg(test)]
+mod compare {
+    use super::{NESTEST, OURS};
+    use crate::trace_diff::{
+        compare, parse_log, Comparison, Field, FieldDifference, LogFormat, STATUS_MASK,
+    };
+
+    #[test]
+    fn should_align_logs_and_find_first_divergence() {
+        let ours = parse_log(OURS, LogFormat::Plain);
+        let reference = parse_log(NESTEST, LogFormat::Nestest);
+
+        let comparison = compare(&ours, &reference, STATUS_MASK).unwrap();
+
+        assert_eq!(
+            comparison,
+            Comparison {
+                ours_start: 1,
+                reference_start: 0,
+                matched: 3,
+                divergence: Some(vec![FieldDifference {
+                    field: Field::ProcessorStatus,
+                    ours: 0x04,
+                    reference: 0x06,
+                }]),
+            }
+        );
+    }
+
+    #[test]
+    fn should_report_cycle_differences_relative_to_start() {
+        let ours = parse_log(
+            &OURS.replace("P=$04 [.....I..] 10", "P=$06 [.....IZ.] 10"),
+            LogFormat::Plain,
+        );
+        let reference = parse_log(NESTEST, LogFormat::Nestest);
+
+        let comparison = compare(&ours, &reference, STATUS_MASK).unwrap();
+
+        assert_eq!(comparison.matched, 4);
+        assert_eq!(
+            comparison.divergence,
+            Some(vec![FieldDifference {
+                field: Field::Cycles,
+                ours: 12,
+                reference: 11,
+            }])
+        );
+    }
+
+    #[test]
+    fn should_match_identical_logs() {
+        let reference = parse_log(NESTEST, LogFormat::Nestest);
+
+        let comparison = compare(&reference[1..], &reference, STATUS_MASK).unwrap();
+
+        assert_eq!(comparison.ours_start, 0);
+        assert_eq!(comparison.reference_start, 1);
+        assert_eq!(comparison.matched, 4);
+        assert_eq!(comparison.divergence, None);
+    }
+
+    #[test]
+    fn should_fail_without_common_instruction() {
+        let ours = parse_log(
+            "0200  EA        NOP           A=$00 X=$00 Y=$00 SP=$FD P=$04 [.....I..] 0",
+            LogFormat::Plain,
+        );
+        let reference = parse_log(NESTEST, LogFormat::Nestest);
+
+        assert_eq!(
+            compare(&ours, &reference, STATUS_MASK)
+                .unwrap_err()
+                .to_string(),
+            "the traces have no instruction in common to start from"
+        );
+    }
+}
+
+#[cfg(test)]
+mod report {
+    use super::{NESTEST, OURS};
+    use crate::trace_diff::{compare, parse_log, LogFormat, STATUS_MASK};
+
+    fn report(ours: &str, context: usize) -> String {
+        let ours = parse_log(ours, LogFormat::Plain);
+        let reference = parse_log(NESTEST, LogFormat::Nestest);
+        let comparison = compare(&ours, &reference, STATUS_MASK).unwrap();
+        let mut out = vec![];
+        comparison
+            .write_report(&ours, &reference, context, &mut out)
+            .unwrap();
+
+        return String::from_utf8(out).unwrap();
+    }
+
+    #[test]
+    fn should_show_context_and_differing_columns() {
+        let expected = format!(
+            "traces diverge after 3 matching instructions, at line 5 of ours and line 4 of the reference:\n\
+             \x20        3  {}\n\
+             - {:>8}  {}\n\
+             + {:>8}  {}\n\
+             \x20 P: $04 != $06 (flags Z)\n",
+            NESTEST.lines().nth(2).unwrap(),
+            5,
+            OURS.lines().nth(4).unwrap(),
+            4,
+            NESTEST.lines().nth(3).unwrap(),
+        );
+
+        assert_eq!(report(OURS, 1), expected);
+    }
+
+    #[test]
+    fn should_summarize_matching_traces() {
+        let ours: String = OURS
+            .lines()
+            .take(4)
+            .map(|line| format!("{line}\n"))
+            .collect();
+
+        assert_eq!(
+            report(&ours, 3),
+            "traces match for 3 instructions\nreference trace continues for 2 more instructions\n"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use cpu6502::{
+    consts::Word,
+    cpu::{CpuVariant, StopReason},
+    machine::Machine,
+    memory::AccessKind,
+};
+
+use crate::{
+    json::JsonValue,
+    run::{Outcome, Stop},
+};
+
+fn number<T: Into<u64>>(value: T) -> JsonValue {
+    return JsonValue::Number(value.into());
+}
+
+fn stop_json(stop: &Stop) -> JsonValue {
+    return match stop {
+        Stop::CycleLimit => JsonValue::object(vec![("kind", JsonValue::string("cycle_limit"))]),
+        Stop::InstructionLimit => {
+            JsonValue::object(vec![("kind", JsonValue::string("instruction_limit"))])
+        }
+        Stop::Cpu(StopReason::IllegalOpcode { address, opcode }) => JsonValue::object(vec![
+            ("kind", JsonValue::string("illegal_opcode")),
+            ("address", number(*address)),
+            ("opcode", number(*opcode)),
+        ]),
+        Stop::Cpu(StopReason::Breakpoint(hit)) => JsonValue::object(vec![
+            ("kind", JsonValue::string("breakpoint")),
+            ("id", number(hit.id as u64)),
+            ("program_counter", number(hit.program_counter)),
+            ("hit_count", number(hit.hit_count)),
+        ]),
+        Stop::Cpu(StopReason::Watchpoint(hit)) => {
+            let access = match hit.kind {
+                AccessKind::Read => "read",
+                AccessKind::Write => "write",
+                AccessKind::Fetch => "fetch",
+            };
+            JsonValue::object(vec![
+                ("kind", JsonValue::string("watchpoint")),
+                ("program_counter", number(hit.program_counter)),
+                ("address", number(hit.address)),
+                ("access", JsonValue::string(access)),
+                ("old_value", number(hit.old_value)),
+                ("new_value", number(hit.new_value)),
+            ])
+        }
+    };
+}
+
+/// Document describing the final state of a run, addresses and values are plain numbers.
+pub fn report(machine: &Machine, outcome: &Outcome, dumps: &[(Word, Word)]) -> JsonValue {
+    let registers = machine.registers();
+    let cpu = match machine.cpu().variant() {
+        CpuVariant::Nmos6502 => "6502",
+        CpuVariant::Cmos65C02 => "65c02",
+    };
+    let memory = dumps
+        .iter()
+        .map(|(start, end)| {
+            let bytes = machine.dump(*start, *end).into_iter().map(number).collect();
+            JsonValue::object(vec![
+                ("start", number(*start)),
+                ("end", number(*end)),
+                ("bytes", JsonValue::Array(bytes)),
+            ])
+        })
+        .collect();
+
+    return JsonValue::object(vec![
+        ("cpu", JsonValue::string(cpu)),
+        (
+            "registers",
+            JsonValue::object(vec![
+                ("pc", number(registers.program_counter)),
+                ("a", number(registers.accumulator)),
+                ("x", number(registers.index_register_x)),
+                ("y", number(registers.index_register_y)),
+                ("sp", number(registers.stack_pointer)),
+                ("p", number(registers.processor_status)),
+            ]),
+        ),
+        ("cycles", number(outcome.cycles)),
+        ("instructions", number(outcome.instructions)),
+        ("stop_reason", stop_json(&outcome.stop)),
+        ("memory", JsonValue::Array(memory)),
+    ]);
+}
+
+/// Document reported when the run could not be set up.
+pub fn error_report(message: &str) -> JsonValue {
+    return JsonValue::object(vec![("error", JsonValue::string(message))]);
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod report {
+    use crate::batch::{error_report, report};
+    use crate::json::JsonValue;
+    use crate::run::{execute, Outcome, Stop};
+    use cpu6502::cpu::StopReason;
+    use cpu6502::machine::Machine;
+
+    fn field<'a>(value: &'a JsonValue, name: &str) -> &'a JsonValue {
+        return match value {
+            JsonValue::Object(fields) => &fields.iter().find(|(key, _)| key == name).unwrap().1,
+            _ => panic!("not an object: {value:?}"),
+        };
+    }
+
+    #[test]
+    fn should_describe_final_state_and_requested_memory() {
+        let mut machine = Machine::new();
+        machine.load(&[
+            (0x0200, 0xA9),
+            (0x0201, 0x42),
+            (0x0202, 0x85),
+            (0x0203, 0x10),
+        ]);
+        machine.cpu_mut().set_program_counter(0x0200);
+        let outcome = execute(&mut machine, None, Some(2));
+
+        let uut = report(&machine, &outcome, &[(0x0010, 0x0011)]);
+
+        assert_eq!(field(&uut, "cpu"), &JsonValue::string("6502"));
+        assert_eq!(
+            field(field(&uut, "registers"), "pc"),
+            &JsonValue::Number(0x0204)
+        );
+        assert_eq!(
+            field(field(&uut, "registers"), "a"),
+            &JsonValue::Number(0x42)
+        );
+        assert_eq!(field(&uut, "instructions"), &JsonValue::Number(2));
+        assert_eq!(field(&uut, "cycles"), &JsonValue::Number(5));
+        assert_eq!(
+            field(&uut, "stop_reason"),
+            &JsonValue::object(vec![("kind", JsonValue::string("instruction_limit"))])
+        );
+        assert_eq!(
+            field(&uut, "memory"),
+            &JsonValue::Array(vec![JsonValue::object(vec![
+                ("start", JsonValue::Number(0x10)),
+                ("end", JsonValue::Number(0x11)),
+                (
+                    "bytes",
+                    JsonValue::Array(vec![JsonValue::Number(0x42), JsonValue::Number(0)])
+                ),
+            ])])
+        );
+    }
+
+    #[test]
+    fn should_report_illegal_opcode_with_its_address() {
+        let machine = Machine::new();
+        let outcome = Outcome {
+            instructions: 0,
+            cycles: 0,
+            stop: Stop::Cpu(StopReason::IllegalOpcode {
+                address: 0x0300,
+                opcode: 0x02,
+            }),
+        };
+
+        let uut = report(&machine, &outcome, &[]);
+
+        assert_eq!(
+            field(&uut, "stop_reason"),
+            &JsonValue::object(vec![
+                ("kind", JsonValue::string("illegal_opcode")),
+                ("address", JsonValue::Number(0x0300)),
+                ("opcode", JsonValue::Number(0x02)),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_wrap_setup_errors() {
+        assert_eq!(
+            error_report("a.bin: missing").to_string(),
+            "{\n  \"error\": \"a.bin: missing\"\n}"
+        );
+    }
+}
@@ -0,0 +1,480 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use cpu6502::{
+    consts::Word,
+    cpu::{
+        trace::{TraceFilter, TraceFormat},
+        CpuVariant,
+    },
+    machine::description::parse_number,
+    trace_diff::LogFormat,
+};
+
+pub const USAGE: &str = "\
+usage: emu6502 [OPTIONS] [IMAGE]
+       emu6502 trace-diff [TRACE-DIFF OPTIONS] OURS REFERENCE
+
+Loads IMAGE into the machine and runs it. trace-diff compares a trace written
+with --trace against a reference emulator log and reports the first divergence.
+
+options:
+  -m, --machine FILE       machine description file (default: 64 KB of RAM)
+  -f, --format FORMAT      image format: bin, prg, ihex, srec or o65
+                           (default: guessed from the file extension, else bin)
+  -l, --load ADDRESS       load address of bin images, text base of o65 objects
+  -e, --entry ADDRESS      start executing at ADDRESS
+      --reset-vector       start at the address stored in the reset vector
+  -c, --cpu CPU            6502 or 65c02
+      --cycles COUNT       stop after COUNT cycles
+      --instructions COUNT stop after COUNT instructions
+  -r, --registers          print registers once execution stops
+  -d, --dump RANGE         print memory in START:END (inclusive) or START+LENGTH,
+                           may be repeated
+      --json               print registers, counters, stop reason and dumped
+                           memory as a JSON document instead of text, console
+                           devices then print to stderr
+      --monitor            start the interactive monitor instead of running,
+                           type h in it for a list of commands
+      --gdb ADDRESS        wait for a GDB remote debugger on ADDRESS, a PORT or
+                           HOST:PORT to listen on TCP or a Unix socket path
+      --trace FILE         write every executed instruction to FILE, - for stdout
+      --trace-format FORMAT
+                           trace line format: plain, nestest or vice
+                           (default: plain)
+      --trace-range RANGE  only trace instructions in RANGE, may be repeated
+      --trace-cycles RANGE only trace while the cycle counter is in FIRST:LAST
+      --trace-subroutine ADDRESS
+                           only trace the subroutine at ADDRESS and what it calls
+      --profile FILE       write a cycle profile with call graph to FILE in the
+                           callgrind format, for KCachegrind
+      --hotspots COUNT     print the COUNT instructions and functions taking the
+                           most cycles once execution stops
+      --symbols FILE       name addresses in profiles after the symbols in FILE,
+                           a linker map or VICE label file
+  -j, --job FILE           read options from FILE, one \"option = value\" per line
+                           using long option names, \"image = FILE\" for the image
+                           and true/false for flags; paths are relative to FILE
+  -h, --help               print this help
+
+trace-diff options:
+      --context LINES      matching instructions shown before the divergence
+                           (default: 5)
+      --ours-format FORMAT, --reference-format FORMAT
+                           log format: plain, nestest, vice or mame
+                           (default: guessed from the log)
+
+Without a limit execution stops after 1000000 cycles. Addresses and counts are
+decimal, or hexadecimal prefixed with $ or 0x.";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ImageFormat {
+    Binary,
+    Prg,
+    IntelHex,
+    Srec,
+    O65,
+}
+
+impl ImageFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        return match name.to_ascii_lowercase().as_str() {
+            "bin" | "raw" => Some(ImageFormat::Binary),
+            "prg" => Some(ImageFormat::Prg),
+            "ihex" | "hex" => Some(ImageFormat::IntelHex),
+            "srec" | "s19" | "s28" | "s37" | "mot" => Some(ImageFormat::Srec),
+            "o65" => Some(ImageFormat::O65),
+            _ => None,
+        };
+    }
+
+    /// Guesses the format from the extension, anything unknown is a raw binary.
+    pub fn from_path(path: &Path) -> Self {
+        return path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(ImageFormat::from_name)
+            .unwrap_or(ImageFormat::Binary);
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StartAddress {
+    Entry(Word),
+    ResetVector,
+}
+
+/// Where the server waits for a debugger.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GdbAddress {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl GdbAddress {
+    /// A bare port listens on localhost, `HOST:PORT` on the given host and
+    /// anything else is a Unix socket path.
+    pub fn parse(text: &str) -> Self {
+        let is_port = |port: &str| !port.is_empty() && port.bytes().all(|c| c.is_ascii_digit());
+        if is_port(text) {
+            return GdbAddress::Tcp(format!("127.0.0.1:{text}"));
+        }
+        return match text.rsplit_once(':') {
+            Some((_, port)) if is_port(port) && !text.contains('/') => {
+                GdbAddress::Tcp(text.to_string())
+            }
+            _ => GdbAddress::Unix(PathBuf::from(text)),
+        };
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Options {
+    pub machine: Option<PathBuf>,
+    pub image: Option<PathBuf>,
+    pub format: Option<ImageFormat>,
+    pub load_address: Option<Word>,
+    pub start: Option<StartAddress>,
+    pub cpu: Option<CpuVariant>,
+    pub cycle_limit: Option<u64>,
+    pub instruction_limit: Option<u64>,
+    pub print_registers: bool,
+    pub dumps: Vec<(Word, Word)>,
+    pub json: bool,
+    pub monitor: bool,
+    pub gdb: Option<GdbAddress>,
+    pub trace: Option<PathBuf>,
+    pub trace_format: Option<TraceFormat>,
+    pub trace_filter: TraceFilter,
+    pub profile: Option<PathBuf>,
+    pub hotspots: Option<usize>,
+    pub symbols: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceDiffOptions {
+    pub ours: PathBuf,
+    pub reference: PathBuf,
+    pub context: usize,
+    pub ours_format: Option<LogFormat>,
+    pub reference_format: Option<LogFormat>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(Box<Options>),
+    TraceDiff(TraceDiffOptions),
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UsageError(pub String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.0);
+    }
+}
+
+fn parse_address(option: &str, value: &str) -> Result<Word, UsageError> {
+    return parse_number(value)
+        .and_then(|number| Word::try_from(number).ok())
+        .ok_or_else(|| UsageError(format!("invalid address \"{value}\" for {option}")));
+}
+
+fn parse_count(option: &str, value: &str) -> Result<u64, UsageError> {
+    return parse_number(value)
+        .map(u64::from)
+        .or_else(|| value.parse().ok())
+        .ok_or_else(|| UsageError(format!("invalid count \"{value}\" for {option}")));
+}
+
+/// Parses `START:END` (inclusive) or `START+LENGTH`.
+pub fn parse_range(value: &str) -> Option<(Word, Word)> {
+    let to_word = |text: &str| parse_number(text).and_then(|number| Word::try_from(number).ok());
+    if let Some((start, end)) = value.split_once(':') {
+        let (start, end) = (to_word(start)?, to_word(end)?);
+        return (start <= end).then_some((start, end));
+    }
+
+    let (start, length) = value.split_once('+')?;
+    let start = to_word(start)?;
+    let length = parse_number(length)?;
+    if length == 0 {
+        return None;
+    }
+    let end = Word::try_from(u32::from(start) + length - 1).ok()?;
+
+    return Some((start, end));
+}
+
+const JOB_FLAGS: [&str; 4] = ["reset-vector", "registers", "json", "monitor"];
+const JOB_PATHS: [&str; 5] = ["image", "machine", "symbols", "trace", "profile"];
+
+/// Replaces every `--job FILE` with the options listed in the file, so options
+/// following it on the command line override the ones from the file.
+/// `read` returns the contents of a file.
+pub fn expand_jobs<R>(args: &[String], read: R) -> Result<Vec<String>, UsageError>
+where
+    R: Fn(&Path) -> io::Result<String>,
+{
+    let mut expanded = vec![];
+    let mut remaining = args.iter();
+    while let Some(arg) = remaining.next() {
+        let path = match arg.as_str() {
+            "-j" | "--job" => remaining
+                .next()
+                .ok_or_else(|| UsageError(format!("{arg} requires a value")))?,
+            _ => match arg.strip_prefix("--job=") {
+                Some(path) => path,
+                None => {
+                    expanded.push(arg.clone());
+                    continue;
+                }
+            },
+        };
+
+        let path = Path::new(path);
+        let text = read(path)
+            .map_err(|err| UsageError(format!("cannot read job {}: {err}", path.display())))?;
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        expanded.extend(job_arguments(&text, base_dir).map_err(|(line, message)| {
+            UsageError(format!("{}:{line}: {message}", path.display()))
+        })?);
+    }
+
+    return Ok(expanded);
+}
+
+fn job_arguments(text: &str, base_dir: &Path) -> Result<Vec<String>, (usize, String)> {
+    let mut args = vec![];
+    for (idx, line) in text.lines().enumerate() {
+        let line_number = idx + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or((line_number, "expected \"option = value\"".to_string()))?;
+        let (key, value) = (key.trim(), value.trim());
+        if JOB_FLAGS.contains(&key) {
+            match value {
+                "true" => args.push(format!("--{key}")),
+                "false" => (),
+                _ => return Err((line_number, format!("{key} must be true or false"))),
+            }
+            continue;
+        }
+
+        if key == "job" {
+            return Err((line_number, "jobs cannot include other jobs".to_string()));
+        }
+        if key != "image" {
+            args.push(format!("--{key}"));
+        }
+        // `-` names stdout rather than a file next to the job.
+        if JOB_PATHS.contains(&key) && value != "-" {
+            args.push(base_dir.join(value).to_string_lossy().into_owned());
+        } else {
+            args.push(value.to_string());
+        }
+    }
+
+    return Ok(args);
+}
+
+fn parse_log_format(value: &str) -> Result<LogFormat, UsageError> {
+    return LogFormat::from_name(value)
+        .ok_or_else(|| UsageError(format!("unknown log format \"{value}\"")));
+}
+
+/// Parses the arguments following `trace-diff`.
+fn parse_trace_diff_args(args: &[String]) -> Result<Command, UsageError> {
+    let mut paths = vec![];
+    let mut context = 5;
+    let (mut ours_format, mut reference_format) = (None, None);
+    let mut remaining = args.iter();
+
+    while let Some(arg) = remaining.next() {
+        if !arg.starts_with('-') {
+            paths.push(PathBuf::from(arg));
+            continue;
+        }
+
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let mut value = || -> Result<String, UsageError> {
+            match inline_value.clone() {
+                Some(value) => Ok(value),
+                None => remaining
+                    .next()
+                    .cloned()
+                    .ok_or_else(|| UsageError(format!("{name} requires a value"))),
+            }
+        };
+
+        match name {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--context" => context = parse_count(name, &value()?)? as usize,
+            "--ours-format" => ours_format = Some(parse_log_format(&value()?)?),
+            "--reference-format" => reference_format = Some(parse_log_format(&value()?)?),
+            _ => return Err(UsageError(format!("unknown option \"{name}\""))),
+        }
+    }
+
+    let [ours, reference] = <[PathBuf; 2]>::try_from(paths)
+        .map_err(|_| UsageError("trace-diff needs our trace and a reference log".to_string()))?;
+
+    return Ok(Command::TraceDiff(TraceDiffOptions {
+        ours,
+        reference,
+        context,
+        ours_format,
+        reference_format,
+    }));
+}
+
+/// Parses the arguments following the program name.
+pub fn parse_args(args: &[String]) -> Result<Command, UsageError> {
+    if args.first().is_some_and(|arg| arg == "trace-diff") {
+        return parse_trace_diff_args(&args[1..]);
+    }
+
+    let mut options = Options::default();
+    let mut remaining = args.iter();
+
+    while let Some(arg) = remaining.next() {
+        if !arg.starts_with('-') {
+            if options.image.is_some() {
+                return Err(UsageError(format!("unexpected argument \"{arg}\"")));
+            }
+            options.image = Some(PathBuf::from(arg));
+            continue;
+        }
+
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let mut value = || -> Result<String, UsageError> {
+            match inline_value.clone() {
+                Some(value) => Ok(value),
+                None => remaining
+                    .next()
+                    .cloned()
+                    .ok_or_else(|| UsageError(format!("{name} requires a value"))),
+            }
+        };
+
+        match name {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-m" | "--machine" => options.machine = Some(PathBuf::from(value()?)),
+            "-f" | "--format" => {
+                let format = value()?;
+                options.format = Some(
+                    ImageFormat::from_name(&format)
+                        .ok_or_else(|| UsageError(format!("unknown format \"{format}\"")))?,
+                );
+            }
+            "-l" | "--load" => options.load_address = Some(parse_address(name, &value()?)?),
+            "-e" | "--entry" => {
+                options.start = Some(StartAddress::Entry(parse_address(name, &value()?)?))
+            }
+            "--reset-vector" => options.start = Some(StartAddress::ResetVector),
+            "-c" | "--cpu" => {
+                let cpu = value()?;
+                options.cpu = Some(
+                    CpuVariant::from_name(&cpu)
+                        .ok_or_else(|| UsageError(format!("unknown cpu \"{cpu}\"")))?,
+                );
+            }
+            "--cycles" => options.cycle_limit = Some(parse_count(name, &value()?)?),
+            "--instructions" => options.instruction_limit = Some(parse_count(name, &value()?)?),
+            "-r" | "--registers" => options.print_registers = true,
+            "--json" => options.json = true,
+            "--monitor" => options.monitor = true,
+            "--gdb" => options.gdb = Some(GdbAddress::parse(&value()?)),
+            "--trace" => options.trace = Some(PathBuf::from(value()?)),
+            "--trace-format" => {
+                let format = value()?;
+                options.trace_format = Some(
+                    TraceFormat::from_name(&format)
+                        .ok_or_else(|| UsageError(format!("unknown trace format \"{format}\"")))?,
+                );
+            }
+            "--trace-range" => {
+                let range = value()?;
+                let (start, end) = parse_range(&range)
+                    .ok_or_else(|| UsageError(format!("invalid range \"{range}\"")))?;
+                options.trace_filter.ranges.push((start, end));
+            }
+            "--trace-cycles" => {
+                let window = value()?;
+                let invalid = || UsageError(format!("invalid cycle window \"{window}\""));
+                let (first, last) = window.split_once(':').ok_or_else(invalid)?;
+                let first = parse_count(name, first)?;
+                let last = parse_count(name, last)?;
+                if first > last {
+                    return Err(invalid());
+                }
+                options.trace_filter.cycles = Some((first, last));
+            }
+            "--trace-subroutine" => {
+                options.trace_filter.subroutine = Some(parse_address(name, &value()?)?)
+            }
+            "--profile" => options.profile = Some(PathBuf::from(value()?)),
+            "--hotspots" => options.hotspots = Some(parse_count(name, &value()?)? as usize),
+            "--symbols" => options.symbols = Some(PathBuf::from(value()?)),
+            "-d" | "--dump" => {
+                let range = value()?;
+                options.dumps.push(
+                    parse_range(&range)
+                        .ok_or_else(|| UsageError(format!("invalid range \"{range}\"")))?,
+                );
+            }
+            _ => return Err(UsageError(format!("unknown option \"{name}\""))),
+        }
+    }
+
+    if options.monitor && options.json {
+        return Err(UsageError(
+            "--monitor cannot be combined with --json".to_string(),
+        ));
+    }
+    if options.gdb.is_some() && (options.monitor || options.json) {
+        return Err(UsageError(
+            "--gdb cannot be combined with --monitor or --json".to_string(),
+        ));
+    }
+    if options.hotspots.is_some() && options.json {
+        return Err(UsageError(
+            "--hotspots cannot be combined with --json".to_string(),
+        ));
+    }
+    let trace_to_stdout = options
+        .trace
+        .as_ref()
+        .is_some_and(|path| path.as_os_str() == "-");
+    if trace_to_stdout && options.json {
+        return Err(UsageError(
+            "--trace - cannot be combined with --json".to_string(),
+        ));
+    }
+    let interactive = options.monitor || options.gdb.is_some();
+    if options.image.is_none() && options.machine.is_none() && !interactive {
+        return Err(UsageError(
+            "nothing to run, give an image or a machine".to_string(),
+        ));
+    }
+
+    return Ok(Command::Run(Box::new(options)));
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,390 @@
+#[cfg(test)]
+mod parse_args {
+    use crate::cli::{
+        parse_args, Command, GdbAddress, ImageFormat, Options, StartAddress, TraceDiffOptions,
+        UsageError,
+    };
+    use cpu6502::{
+        cpu::{
+            trace::{TraceFilter, TraceFormat},
+            CpuVariant,
+        },
+        trace_diff::LogFormat,
+    };
+    use std::path::PathBuf;
+
+    fn run_options(args: &[&str]) -> Options {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        return match parse_args(&args) {
+            Ok(Command::Run(options)) => *options,
+            result => panic!("expected options, got {result:?}"),
+        };
+    }
+
+    fn usage_error(args: &[&str]) -> UsageError {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        return match parse_args(&args) {
+            Err(err) => err,
+            result => panic!("expected usage error, got {result:?}"),
+        };
+    }
+
+    #[test]
+    fn should_read_image_and_all_options() {
+        let options = run_options(&[
+            "-f",
+            "bin",
+            "--load",
+            "$0200",
+            "--entry=0x0210",
+            "--cpu",
+            "65C02",
+            "--cycles",
+            "1000",
+            "--instructions",
+            "50",
+            "-r",
+            "-d",
+            "$10:$1F",
+            "-d",
+            "$0200+4",
+            "--json",
+            "game.bin",
+        ]);
+
+        assert_eq!(
+            options,
+            Options {
+                machine: None,
+                image: Some(PathBuf::from("game.bin")),
+                format: Some(ImageFormat::Binary),
+                load_address: Some(0x0200),
+                start: Some(StartAddress::Entry(0x0210)),
+                cpu: Some(CpuVariant::Cmos65C02),
+                cycle_limit: Some(1000),
+                instruction_limit: Some(50),
+                print_registers: true,
+                dumps: vec![(0x0010, 0x001F), (0x0200, 0x0203)],
+                json: true,
+                monitor: false,
+                gdb: None,
+                trace: None,
+                trace_format: None,
+                trace_filter: TraceFilter::default(),
+                profile: None,
+                hotspots: None,
+                symbols: None,
+            }
+        );
+    }
+
+    #[test]
+    fn should_accept_machine_without_image() {
+        let options = run_options(&["--machine", "board.ini", "--reset-vector"]);
+
+        assert_eq!(options.machine, Some(PathBuf::from("board.ini")));
+        assert_eq!(options.start, Some(StartAddress::ResetVector));
+    }
+
+    #[test]
+    fn should_start_monitor_without_image() {
+        let options = run_options(&["--monitor"]);
+
+        assert!(options.monitor);
+        assert_eq!(options.image, None);
+    }
+
+    #[test]
+    fn should_parse_gdb_addresses() {
+        let gdb = |address: &str| run_options(&["--gdb", address]).gdb.unwrap();
+
+        assert_eq!(gdb("1234"), GdbAddress::Tcp("127.0.0.1:1234".to_string()));
+        assert_eq!(
+            gdb("0.0.0.0:3333"),
+            GdbAddress::Tcp("0.0.0.0:3333".to_string())
+        );
+        assert_eq!(
+            gdb("/tmp/emu6502.sock"),
+            GdbAddress::Unix(PathBuf::from("/tmp/emu6502.sock"))
+        );
+        assert_eq!(gdb("gdb.sock"), GdbAddress::Unix(PathBuf::from("gdb.sock")));
+    }
+
+    #[test]
+    fn should_parse_trace_options() {
+        let options = run_options(&[
+            "a.bin",
+            "--trace",
+            "run.log",
+            "--trace-format",
+            "nestest",
+            "--trace-range",
+            "$C000:$C0FF",
+            "--trace-range",
+            "$E000+16",
+            "--trace-cycles",
+            "100:$200",
+            "--trace-subroutine",
+            "$C123",
+        ]);
+
+        assert_eq!(options.trace, Some(PathBuf::from("run.log")));
+        assert_eq!(options.trace_format, Some(TraceFormat::Nestest));
+        assert_eq!(
+            options.trace_filter,
+            TraceFilter::new()
+                .in_range(0xC000, 0xC0FF)
+                .in_range(0xE000, 0xE00F)
+                .during(100, 0x200)
+                .in_subroutine(0xC123)
+        );
+    }
+
+    #[test]
+    fn should_parse_profile_options() {
+        let options = run_options(&[
+            "a.bin",
+            "--profile",
+            "callgrind.out",
+            "--hotspots",
+            "10",
+            "--symbols",
+            "a.map",
+        ]);
+
+        assert_eq!(options.profile, Some(PathBuf::from("callgrind.out")));
+        assert_eq!(options.hotspots, Some(10));
+        assert_eq!(options.symbols, Some(PathBuf::from("a.map")));
+    }
+
+    #[test]
+    fn should_parse_trace_diff_command() {
+        let args: Vec<String> = [
+            "trace-diff",
+            "ours.log",
+            "--context=2",
+            "--reference-format",
+            "nestest",
+            "nestest.log",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+
+        assert_eq!(
+            parse_args(&args),
+            Ok(Command::TraceDiff(TraceDiffOptions {
+                ours: PathBuf::from("ours.log"),
+                reference: PathBuf::from("nestest.log"),
+                context: 2,
+                ours_format: None,
+                reference_format: Some(LogFormat::Nestest),
+            }))
+        );
+    }
+
+    #[test]
+    fn should_return_help_command() {
+        let args = vec!["game.bin".to_string(), "--help".to_string()];
+
+        assert_eq!(parse_args(&args), Ok(Command::Help));
+    }
+
+    #[test]
+    fn should_reject_invalid_arguments() {
+        assert_eq!(
+            usage_error(&[]),
+            UsageError("nothing to run, give an image or a machine".to_string())
+        );
+        assert_eq!(
+            usage_error(&["--frobnicate", "a.bin"]),
+            UsageError("unknown option \"--frobnicate\"".to_string())
+        );
+        assert_eq!(
+            usage_error(&["a.bin", "--load"]),
+            UsageError("--load requires a value".to_string())
+        );
+        assert_eq!(
+            usage_error(&["a.bin", "--load", "$10000"]),
+            UsageError("invalid address \"$10000\" for --load".to_string())
+        );
+        assert_eq!(
+            usage_error(&["a.bin", "-c", "z80"]),
+            UsageError("unknown cpu \"z80\"".to_string())
+        );
+        assert_eq!(
+            usage_error(&["a.bin", "b.bin"]),
+            UsageError("unexpected argument \"b.bin\"".to_string())
+        );
+        assert_eq!(
+            usage_error(&["a.bin", "--monitor", "--json"]),
+            UsageError("--monitor cannot be combined with --json".to_string())
+        );
+        assert_eq!(
+            usage_error(&["--gdb", "1234", "--monitor"]),
+            UsageError("--gdb cannot be combined with --monitor or --json".to_string())
+        );
+        assert_eq!(
+            usage_error(&["a.bin", "--trace", "-", "--json"]),
+            UsageError("--trace - cannot be combined with --json".to_string())
+        );
+        assert_eq!(
+            usage_error(&["a.bin", "--hotspots", "5", "--json"]),
+            UsageError("--hotspots cannot be combined with --json".to_string())
+        );
+        assert_eq!(
+            usage_error(&["trace-diff", "ours.log"]),
+            UsageError("trace-diff needs our trace and a reference log".to_string())
+        );
+        assert_eq!(
+            usage_error(&["trace-diff", "a", "b", "--ours-format", "bochs"]),
+            UsageError("unknown log format \"bochs\"".to_string())
+        );
+        assert_eq!(
+            usage_error(&["a.bin", "--trace-format", "mame"]),
+            UsageError("unknown trace format \"mame\"".to_string())
+        );
+        assert_eq!(
+            usage_error(&["a.bin", "--trace-cycles", "20:10"]),
+            UsageError("invalid cycle window \"20:10\"".to_string())
+        );
+        assert_eq!(
+            usage_error(&["a.bin", "-d", "$20:$10"]),
+            UsageError("invalid range \"$20:$10\"".to_string())
+        );
+    }
+}
+
+#[cfg(test)]
+mod image_format {
+    use crate::cli::ImageFormat;
+    use std::path::Path;
+
+    #[test]
+    fn should_guess_format_from_extension() {
+        assert_eq!(ImageFormat::from_path(Path::new("a.PRG")), ImageFormat::Prg);
+        assert_eq!(
+            ImageFormat::from_path(Path::new("a.hex")),
+            ImageFormat::IntelHex
+        );
+        assert_eq!(
+            ImageFormat::from_path(Path::new("a.s19")),
+            ImageFormat::Srec
+        );
+        assert_eq!(ImageFormat::from_path(Path::new("a.o65")), ImageFormat::O65);
+        assert_eq!(
+            ImageFormat::from_path(Path::new("a.rom")),
+            ImageFormat::Binary
+        );
+        assert_eq!(
+            ImageFormat::from_path(Path::new("image")),
+            ImageFormat::Binary
+        );
+    }
+}
+
+#[cfg(test)]
+mod expand_jobs {
+    use crate::cli::{expand_jobs, UsageError};
+    use std::{io, path::Path};
+
+    const JOB: &str = "
+# regression job
+image = build/test.bin
+load = $0200
+registers = true
+json = false
+dump = $10:$1F
+dump = $0200+4
+";
+
+    fn args(args: &[&str]) -> Vec<String> {
+        return args.iter().map(|arg| arg.to_string()).collect();
+    }
+
+    fn read_job(path: &Path) -> io::Result<String> {
+        return match path.to_str() {
+            Some("jobs/ci.job") => Ok(JOB.to_string()),
+            Some("jobs/nested.job") => Ok("job = other.job\n".to_string()),
+            Some("jobs/trace.job") => Ok("trace = out/run.log\n".to_string()),
+            Some("jobs/stdout.job") => Ok("trace = -\n".to_string()),
+            Some("jobs/profile.job") => Ok("profile = out/callgrind.out\n".to_string()),
+            Some("jobs/broken.job") => Ok("\nregisters = maybe\n".to_string()),
+            _ => Err(io::Error::new(io::ErrorKind::NotFound, "not found")),
+        };
+    }
+
+    #[test]
+    fn should_replace_job_with_its_options_in_place() {
+        let result = expand_jobs(
+            &args(&["--json", "--job", "jobs/ci.job", "--load", "$0300"]),
+            read_job,
+        );
+
+        assert_eq!(
+            result,
+            Ok(args(&[
+                "--json",
+                "jobs/build/test.bin",
+                "--load",
+                "$0200",
+                "--registers",
+                "--dump",
+                "$10:$1F",
+                "--dump",
+                "$0200+4",
+                "--load",
+                "$0300",
+            ]))
+        );
+    }
+
+    #[test]
+    fn should_resolve_trace_relative_to_job_unless_stdout() {
+        assert_eq!(
+            expand_jobs(&args(&["-j", "jobs/trace.job"]), read_job),
+            Ok(args(&["--trace", "jobs/out/run.log"]))
+        );
+        assert_eq!(
+            expand_jobs(&args(&["-j", "jobs/stdout.job"]), read_job),
+            Ok(args(&["--trace", "-"]))
+        );
+    }
+
+    #[test]
+    fn should_resolve_profile_relative_to_job() {
+        assert_eq!(
+            expand_jobs(&args(&["-j", "jobs/profile.job"]), read_job),
+            Ok(args(&["--profile", "jobs/out/callgrind.out"]))
+        );
+    }
+
+    #[test]
+    fn should_leave_arguments_without_jobs_untouched() {
+        let arguments = args(&["-r", "a.bin"]);
+
+        assert_eq!(expand_jobs(&arguments, read_job), Ok(arguments.clone()));
+    }
+
+    #[test]
+    fn should_report_errors_with_job_path_and_line() {
+        assert_eq!(
+            expand_jobs(&args(&["--job=jobs/broken.job"]), read_job),
+            Err(UsageError(
+                "jobs/broken.job:2: registers must be true or false".to_string()
+            ))
+        );
+        assert_eq!(
+            expand_jobs(&args(&["-j", "jobs/nested.job"]), read_job),
+            Err(UsageError(
+                "jobs/nested.job:1: jobs cannot include other jobs".to_string()
+            ))
+        );
+        assert_eq!(
+            expand_jobs(&args(&["-j", "missing.job"]), read_job),
+            Err(UsageError(
+                "cannot read job missing.job: not found".to_string()
+            ))
+        );
+    }
+}
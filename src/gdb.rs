@@ -0,0 +1,582 @@
+//! GDB remote serial protocol server, letting gdb and other front-ends drive a machine.
+//!
+//! Registers are numbered A, X, Y, SP, PC, P and sent little-endian, as described by
+//! [`TARGET_XML`]. Software and hardware breakpoints both become CPU breakpoints,
+//! watchpoints become CPU watchpoints.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use cpu6502::{
+    consts::{Byte, Word},
+    cpu::{breakpoints::Breakpoint, watchpoints::Watchpoint, CpuState, StopReason},
+    machine::Machine,
+    memory::AccessKind,
+};
+
+use crate::cli::GdbAddress;
+
+/// Target description served through `qXfer:features:read`.
+pub const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.emu6502.cpu">
+    <flags id="status" size="1">
+      <field name="C" start="0" end="0"/>
+      <field name="Z" start="1" end="1"/>
+      <field name="I" start="2" end="2"/>
+      <field name="D" start="3" end="3"/>
+      <field name="B" start="4" end="4"/>
+      <field name="V" start="6" end="6"/>
+      <field name="N" start="7" end="7"/>
+    </flags>
+    <reg name="a" bitsize="8" type="uint8" regnum="0"/>
+    <reg name="x" bitsize="8" type="uint8"/>
+    <reg name="y" bitsize="8" type="uint8"/>
+    <reg name="sp" bitsize="8" type="uint8"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+    <reg name="p" bitsize="8" type="status"/>
+  </feature>
+</target>
+"#;
+
+const INTERRUPT: u8 = 0x03;
+const ERROR: &str = "E01";
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+/// Instructions executed by `c` between checks for an interrupt from the debugger.
+const POLL_INTERVAL: u64 = 10_000;
+
+/// Byte stream to a debugger.
+pub trait Connection: Read + Write {
+    /// Returns whether the debugger asked to stop a running program, without blocking.
+    fn interrupted(&mut self) -> io::Result<bool>;
+}
+
+fn poll_interrupt(stream: &mut impl Read) -> io::Result<bool> {
+    let mut byte = [0];
+    return match stream.read(&mut byte) {
+        Ok(0) => Ok(true),
+        Ok(_) => Ok(byte[0] == INTERRUPT),
+        Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(false),
+        Err(err) => Err(err),
+    };
+}
+
+impl Connection for TcpStream {
+    fn interrupted(&mut self) -> io::Result<bool> {
+        self.set_nonblocking(true)?;
+        let result = poll_interrupt(self);
+        self.set_nonblocking(false)?;
+        return result;
+    }
+}
+
+#[cfg(unix)]
+impl Connection for std::os::unix::net::UnixStream {
+    fn interrupted(&mut self) -> io::Result<bool> {
+        self.set_nonblocking(true)?;
+        let result = poll_interrupt(self);
+        self.set_nonblocking(false)?;
+        return result;
+    }
+}
+
+/// Whether the session continues after a packet.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Flow {
+    Continue,
+    Close,
+}
+
+/// Watchpoint as requested by the debugger, `Z2` to `Z4`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Watch {
+    kind: u8,
+    start: Word,
+    end: Word,
+}
+
+impl Watch {
+    fn access_kinds(&self) -> &'static [AccessKind] {
+        return match self.kind {
+            2 => &[AccessKind::Write],
+            3 => &[AccessKind::Read],
+            _ => &[AccessKind::Read, AccessKind::Write],
+        };
+    }
+
+    fn stop_name(&self) -> &'static str {
+        return match self.kind {
+            2 => "watch",
+            3 => "rwatch",
+            _ => "awatch",
+        };
+    }
+}
+
+/// Debugger session state around a machine.
+pub struct Stub {
+    machine: Machine,
+    /// CPU breakpoint ids by breakpoint type (0 software, 1 hardware) and address.
+    breakpoints: BTreeMap<(u8, Word), usize>,
+    watches: Vec<Watch>,
+    acknowledge: bool,
+    /// Stop reasons gdb announced in `qSupported`.
+    swbreak: bool,
+    hwbreak: bool,
+}
+
+fn hex_bytes(bytes: &[Byte]) -> String {
+    return bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+}
+
+fn parse_hex_bytes(text: &str) -> Option<Vec<Byte>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    return (0..text.len())
+        .step_by(2)
+        .map(|idx| Byte::from_str_radix(text.get(idx..idx + 2)?, 16).ok())
+        .collect();
+}
+
+fn parse_number(text: &str) -> Option<u32> {
+    return u32::from_str_radix(text, 16).ok();
+}
+
+fn parse_address(text: &str) -> Option<Word> {
+    return parse_number(text).and_then(|value| Word::try_from(value).ok());
+}
+
+/// Parses `ADDR,LEN` into an inclusive range clipped to the end of memory.
+fn parse_range(text: &str) -> Option<(Word, Word)> {
+    let (address, len) = text.split_once(',')?;
+    let (address, len) = (parse_address(address)?, parse_number(len)?);
+    if len == 0 {
+        return None;
+    }
+    let end = u32::from(address)
+        .saturating_add(len - 1)
+        .min(u32::from(Word::MAX));
+
+    return Some((address, end as Word));
+}
+
+fn register_bytes(state: &CpuState) -> Vec<Byte> {
+    let [pc_lo, pc_hi] = state.program_counter.to_le_bytes();
+    return vec![
+        state.accumulator,
+        state.index_register_x,
+        state.index_register_y,
+        state.stack_pointer,
+        pc_lo,
+        pc_hi,
+        state.processor_status,
+    ];
+}
+
+/// Offset and size of register `number` within [`register_bytes`].
+fn register_slot(number: u32) -> Option<(usize, usize)> {
+    return match number {
+        0..=3 => Some((number as usize, 1)),
+        4 => Some((4, 2)),
+        5 => Some((6, 1)),
+        _ => None,
+    };
+}
+
+fn set_register_bytes(state: &mut CpuState, bytes: &[Byte]) {
+    state.accumulator = bytes[0];
+    state.index_register_x = bytes[1];
+    state.index_register_y = bytes[2];
+    state.stack_pointer = bytes[3];
+    state.program_counter = Word::from_le_bytes([bytes[4], bytes[5]]);
+    state.processor_status = bytes[6];
+}
+
+impl Stub {
+    pub fn new(machine: Machine) -> Self {
+        return Stub {
+            machine,
+            breakpoints: BTreeMap::new(),
+            watches: vec![],
+            acknowledge: true,
+            swbreak: false,
+            hwbreak: false,
+        };
+    }
+
+    /// Handles one packet and returns the reply, `None` when nothing is sent back.
+    fn packet(
+        &mut self,
+        packet: &str,
+        connection: &mut dyn Connection,
+    ) -> io::Result<(Option<String>, Flow)> {
+        let reply = match packet.as_bytes().first() {
+            Some(b'?') => self.stop_reply(None),
+            Some(b'g') => hex_bytes(&register_bytes(&self.machine.registers())),
+            Some(b'G') => self.write_registers(&packet[1..]),
+            Some(b'p') => self.read_register(&packet[1..]),
+            Some(b'P') => self.write_register(&packet[1..]),
+            Some(b'm') => self.read_memory(&packet[1..]),
+            Some(b'M') => self.write_memory(&packet[1..]),
+            Some(b's') => match self.resume_at(&packet[1..]) {
+                Some(()) => {
+                    self.machine.step();
+                    self.stop_reply(self.machine.stop_reason())
+                }
+                None => ERROR.to_string(),
+            },
+            Some(b'c') => match self.resume_at(&packet[1..]) {
+                Some(()) => self.continue_execution(connection)?,
+                None => ERROR.to_string(),
+            },
+            Some(b'Z') => self.insert(&packet[1..]),
+            Some(b'z') => self.remove(&packet[1..]),
+            Some(b'H') | Some(b'T') => "OK".to_string(),
+            Some(b'D') => return Ok((Some("OK".to_string()), Flow::Close)),
+            Some(b'k') => return Ok((None, Flow::Close)),
+            Some(b'q') | Some(b'Q') => self.query(packet),
+            _ => String::new(),
+        };
+
+        return Ok((Some(reply), Flow::Continue));
+    }
+
+    fn query(&mut self, packet: &str) -> String {
+        if let Some(features) = packet.strip_prefix("qSupported") {
+            self.swbreak = features.contains("swbreak+");
+            self.hwbreak = features.contains("hwbreak+");
+            return "PacketSize=4000;qXfer:features:read+;swbreak+;hwbreak+;QStartNoAckMode+"
+                .to_string();
+        }
+        if let Some(request) = packet.strip_prefix("qXfer:features:read:") {
+            return read_target_xml(request).unwrap_or_else(|| "E00".to_string());
+        }
+
+        return match packet {
+            "QStartNoAckMode" => {
+                self.acknowledge = false;
+                "OK".to_string()
+            }
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            _ => String::new(),
+        };
+    }
+
+    fn write_registers(&mut self, text: &str) -> String {
+        return match parse_hex_bytes(text) {
+            Some(bytes) if bytes.len() == 7 => {
+                let mut state = self.machine.registers();
+                set_register_bytes(&mut state, &bytes);
+                self.machine.set_registers(state);
+                "OK".to_string()
+            }
+            _ => ERROR.to_string(),
+        };
+    }
+
+    fn read_register(&self, text: &str) -> String {
+        return match parse_number(text).and_then(register_slot) {
+            Some((offset, size)) => {
+                hex_bytes(&register_bytes(&self.machine.registers())[offset..offset + size])
+            }
+            None => ERROR.to_string(),
+        };
+    }
+
+    fn write_register(&mut self, text: &str) -> String {
+        let Some((number, value)) = text.split_once('=') else {
+            return ERROR.to_string();
+        };
+        let slot = parse_number(number).and_then(register_slot);
+        return match (slot, parse_hex_bytes(value)) {
+            (Some((offset, size)), Some(value)) if value.len() == size => {
+                let mut state = self.machine.registers();
+                let mut bytes = register_bytes(&state);
+                bytes[offset..offset + size].copy_from_slice(&value);
+                set_register_bytes(&mut state, &bytes);
+                self.machine.set_registers(state);
+                "OK".to_string()
+            }
+            _ => ERROR.to_string(),
+        };
+    }
+
+    fn read_memory(&self, text: &str) -> String {
+        return match parse_range(text) {
+            Some((start, end)) => {
+                let memory = self.machine.memory();
+                (start..=end)
+                    .map(|address| format!("{:02x}", memory.peek(address)))
+                    .collect()
+            }
+            None => ERROR.to_string(),
+        };
+    }
+
+    fn write_memory(&mut self, text: &str) -> String {
+        let Some((range, data)) = text.split_once(':') else {
+            return ERROR.to_string();
+        };
+        return match (parse_range(range), parse_hex_bytes(data)) {
+            (Some((start, end)), Some(bytes)) if usize::from(end - start) + 1 == bytes.len() => {
+                let memory = self.machine.memory_mut();
+                for (address, byte) in (start..=end).zip(bytes) {
+                    memory.write(address, byte);
+                }
+                "OK".to_string()
+            }
+            _ => ERROR.to_string(),
+        };
+    }
+
+    /// Applies the optional resume address of `s` and `c`.
+    fn resume_at(&mut self, text: &str) -> Option<()> {
+        if !text.is_empty() {
+            let address = parse_address(text)?;
+            self.machine.cpu_mut().set_program_counter(address);
+        }
+        return Some(());
+    }
+
+    fn continue_execution(&mut self, connection: &mut dyn Connection) -> io::Result<String> {
+        let mut instructions: u64 = 0;
+        loop {
+            self.machine.step();
+            if let Some(reason) = self.machine.stop_reason() {
+                return Ok(self.stop_reply(Some(reason)));
+            }
+
+            instructions += 1;
+            if instructions.is_multiple_of(POLL_INTERVAL) && connection.interrupted()? {
+                return Ok(format!("S{SIGINT:02x}"));
+            }
+        }
+    }
+
+    fn stop_reply(&self, reason: Option<StopReason>) -> String {
+        return match reason {
+            None => format!("S{SIGTRAP:02x}"),
+            Some(StopReason::Breakpoint(hit)) => {
+                let kind = self
+                    .breakpoints
+                    .iter()
+                    .find(|(_, id)| **id == hit.id)
+                    .map(|((kind, _), _)| *kind);
+                match kind {
+                    Some(0) if self.swbreak => format!("T{SIGTRAP:02x}swbreak:;"),
+                    Some(1) if self.hwbreak => format!("T{SIGTRAP:02x}hwbreak:;"),
+                    _ => format!("S{SIGTRAP:02x}"),
+                }
+            }
+            Some(StopReason::Watchpoint(hit)) => {
+                let name = self
+                    .watches
+                    .iter()
+                    .find(|watch| {
+                        (watch.start..=watch.end).contains(&hit.address)
+                            && watch.access_kinds().contains(&hit.kind)
+                    })
+                    .map_or("awatch", Watch::stop_name);
+                format!("T{SIGTRAP:02x}{name}:{:04x};", hit.address)
+            }
+            Some(StopReason::IllegalOpcode { .. }) => format!("S{SIGILL:02x}"),
+        };
+    }
+
+    /// Parses `TYPE,ADDR,KIND` of `Z` and `z` packets.
+    fn point(text: &str) -> Option<(u8, Word, Word)> {
+        let mut fields = text.splitn(3, ',');
+        let kind = fields.next()?.parse().ok()?;
+        let address = fields.next()?;
+        let len = fields.next()?;
+        let (start, end) = parse_range(&format!("{address},{len}"))?;
+
+        return Some((kind, start, end));
+    }
+
+    fn insert(&mut self, text: &str) -> String {
+        return match Stub::point(text) {
+            Some((kind @ (0 | 1), address, _)) => {
+                if !self.breakpoints.contains_key(&(kind, address)) {
+                    let id = self
+                        .machine
+                        .cpu_mut()
+                        .add_breakpoint(Breakpoint::at(address));
+                    self.breakpoints.insert((kind, address), id);
+                }
+                "OK".to_string()
+            }
+            Some((kind @ 2..=4, start, end)) => {
+                self.watches.push(Watch { kind, start, end });
+                self.sync_watchpoints();
+                "OK".to_string()
+            }
+            Some(_) => String::new(),
+            None => ERROR.to_string(),
+        };
+    }
+
+    fn remove(&mut self, text: &str) -> String {
+        return match Stub::point(text) {
+            Some((kind @ (0 | 1), address, _)) => {
+                if let Some(id) = self.breakpoints.remove(&(kind, address)) {
+                    self.machine.cpu_mut().remove_breakpoint(id);
+                }
+                "OK".to_string()
+            }
+            Some((kind @ 2..=4, start, end)) => {
+                let watch = Watch { kind, start, end };
+                if let Some(idx) = self.watches.iter().position(|existing| *existing == watch) {
+                    self.watches.remove(idx);
+                }
+                self.sync_watchpoints();
+                "OK".to_string()
+            }
+            Some(_) => String::new(),
+            None => ERROR.to_string(),
+        };
+    }
+
+    /// Rebuilds the CPU watchpoints, overlapping watches of different kinds share them.
+    fn sync_watchpoints(&mut self) {
+        let cpu = self.machine.cpu_mut();
+        cpu.clear_watchpoints();
+        for watch in &self.watches {
+            for kind in watch.access_kinds() {
+                cpu.add_watchpoint(Watchpoint::new(watch.start, watch.end, *kind));
+            }
+        }
+    }
+}
+
+/// Answers `target.xml:OFFSET,LENGTH` with an `m` chunk, or `l` for the last one.
+fn read_target_xml(request: &str) -> Option<String> {
+    let (offset, length) = request.strip_prefix("target.xml:")?.split_once(',')?;
+    let offset = (parse_number(offset)? as usize).min(TARGET_XML.len());
+    let end = offset
+        .saturating_add(parse_number(length)? as usize)
+        .min(TARGET_XML.len());
+    let marker = if end == TARGET_XML.len() { 'l' } else { 'm' };
+
+    return Some(format!("{marker}{}", &TARGET_XML[offset..end]));
+}
+
+fn read_byte(connection: &mut dyn Connection) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    return match connection.read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    };
+}
+
+/// Reads the next `$DATA#CHECKSUM` packet, skipping acknowledgements and stray bytes.
+/// Packets with a wrong checksum are answered with `-` and skipped.
+fn read_packet(connection: &mut dyn Connection, acknowledge: bool) -> io::Result<Option<String>> {
+    loop {
+        match read_byte(connection)? {
+            None => return Ok(None),
+            Some(b'$') => (),
+            Some(_) => continue,
+        }
+
+        let mut data = vec![];
+        loop {
+            match read_byte(connection)? {
+                None => return Ok(None),
+                Some(b'#') => break,
+                Some(byte) => data.push(byte),
+            }
+        }
+        let mut checksum = [0; 2];
+        connection.read_exact(&mut checksum)?;
+
+        let expected = std::str::from_utf8(&checksum)
+            .ok()
+            .and_then(|text| u8::from_str_radix(text, 16).ok());
+        let actual = data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        if acknowledge {
+            let valid = expected == Some(actual);
+            connection.write_all(if valid { b"+" } else { b"-" })?;
+            if !valid {
+                continue;
+            }
+        }
+
+        return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+    }
+}
+
+fn write_packet(connection: &mut dyn Connection, data: &str) -> io::Result<()> {
+    let mut escaped = vec![];
+    for byte in data.bytes() {
+        if matches!(byte, b'$' | b'#' | b'}' | b'*') {
+            escaped.extend([b'}', byte ^ 0x20]);
+        } else {
+            escaped.push(byte);
+        }
+    }
+    let checksum = escaped
+        .iter()
+        .fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+
+    connection.write_all(b"$")?;
+    connection.write_all(&escaped)?;
+    write!(connection, "#{checksum:02x}")?;
+    return connection.flush();
+}
+
+/// Serves packets until the debugger detaches, kills the target or disconnects.
+pub fn serve(stub: &mut Stub, connection: &mut dyn Connection) -> io::Result<()> {
+    while let Some(packet) = read_packet(connection, stub.acknowledge)? {
+        let (reply, flow) = stub.packet(&packet, connection)?;
+        if let Some(reply) = reply {
+            write_packet(connection, &reply)?;
+        }
+        if flow == Flow::Close {
+            break;
+        }
+    }
+
+    return Ok(());
+}
+
+/// Waits for one debugger connection on `address` and serves it.
+pub fn listen(stub: &mut Stub, address: &GdbAddress) -> io::Result<()> {
+    return match address {
+        GdbAddress::Tcp(address) => {
+            let listener = TcpListener::bind(address)?;
+            eprintln!("waiting for gdb on {}", listener.local_addr()?);
+            let (mut stream, _) = listener.accept()?;
+            stream.set_nodelay(true)?;
+            serve(stub, &mut stream)
+        }
+        #[cfg(unix)]
+        GdbAddress::Unix(path) => {
+            let listener = std::os::unix::net::UnixListener::bind(path)?;
+            eprintln!("waiting for gdb on {}", path.display());
+            let result = listener
+                .accept()
+                .and_then(|(mut stream, _)| serve(stub, &mut stream));
+            let _ = std::fs::remove_file(path);
+            result
+        }
+        #[cfg(not(unix))]
+        GdbAddress::Unix(_) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Unix sockets are not supported on this platform",
+        )),
+    };
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,293 @@
+use std::io::{self, Cursor, Read, Write};
+
+use cpu6502::{asm6502, machine::Machine};
+
+use crate::gdb::{serve, Connection, Stub};
+
+/// `LDA #$42; STA $10; INX; INX; LDA $10` at $0200, followed by an illegal opcode,
+/// and `JMP $0300` at $0300.
+fn machine() -> Machine {
+    let mut machine = Machine::new();
+    machine.load(&asm6502! {
+        org 0x0200;
+        lda #0x42;
+        sta 0x10;
+        inx;
+        inx;
+        lda 0x10;
+        byte 0x02;
+        org 0x0300;
+        jmp 0x0300;
+    });
+    machine.cpu_mut().set_program_counter(0x0200);
+
+    return machine;
+}
+
+/// Replays bytes sent by a debugger and records the replies.
+struct Script {
+    input: Cursor<Vec<u8>>,
+    output: Vec<u8>,
+    interrupt: bool,
+}
+
+impl Read for Script {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        return self.input.read(buf);
+    }
+}
+
+impl Write for Script {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        return self.output.write(buf);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return Ok(());
+    }
+}
+
+impl Connection for Script {
+    fn interrupted(&mut self) -> io::Result<bool> {
+        return Ok(self.interrupt);
+    }
+}
+
+fn frame(data: &str) -> String {
+    let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+    return format!("${data}#{checksum:02x}");
+}
+
+/// Sends `input` as is and returns everything written back.
+fn raw_session(stub: &mut Stub, input: &str, interrupt: bool) -> String {
+    let mut script = Script {
+        input: Cursor::new(input.as_bytes().to_vec()),
+        output: vec![],
+        interrupt,
+    };
+    serve(stub, &mut script).unwrap();
+
+    return String::from_utf8(script.output).unwrap();
+}
+
+/// Sends `packets` and returns the payloads of the replies.
+fn session(stub: &mut Stub, packets: &[&str]) -> Vec<String> {
+    let input: String = packets.iter().map(|packet| frame(packet)).collect();
+    let output = raw_session(stub, &input, false);
+
+    return output
+        .split('$')
+        .skip(1)
+        .map(|reply| reply.rsplit_once('#').unwrap().0.to_string())
+        .collect();
+}
+
+#[cfg(test)]
+mod framing {
+    use super::{frame, machine, raw_session, session};
+    use crate::gdb::{Stub, TARGET_XML};
+
+    #[test]
+    fn should_acknowledge_packets_and_reject_bad_checksums() {
+        let mut stub = Stub::new(machine());
+
+        let output = raw_session(&mut stub, &format!("+$g#00{}", frame("g")), false);
+
+        assert_eq!(output, format!("-+{}", frame("00000000000200")));
+    }
+
+    #[test]
+    fn should_stop_acknowledging_in_no_ack_mode() {
+        let mut stub = Stub::new(machine());
+        let input = format!("{}{}", frame("QStartNoAckMode"), frame("?"));
+
+        let output = raw_session(&mut stub, &input, false);
+
+        assert_eq!(output, format!("+{}{}", frame("OK"), frame("S05")));
+    }
+
+    #[test]
+    fn should_advertise_features_and_serve_target_description() {
+        let mut stub = Stub::new(machine());
+
+        let replies = session(
+            &mut stub,
+            &[
+                "qSupported:swbreak+;xmlRegisters=i386",
+                "qXfer:features:read:target.xml:0,20",
+                "qXfer:features:read:target.xml:20,4000",
+                "qXfer:features:read:memory-map.xml:0,20",
+                "vMustReplyEmpty",
+            ],
+        );
+
+        assert!(replies[0].contains("qXfer:features:read+"));
+        assert_eq!(replies[1], format!("m{}", &TARGET_XML[..0x20]));
+        assert_eq!(replies[2], format!("l{}", &TARGET_XML[0x20..]));
+        assert_eq!(replies[3], "E00");
+        assert_eq!(replies[4], "");
+    }
+
+    #[test]
+    fn should_stop_serving_after_detach() {
+        let mut stub = Stub::new(machine());
+
+        let replies = session(&mut stub, &["D", "g"]);
+
+        assert_eq!(replies, vec!["OK"]);
+    }
+}
+
+#[cfg(test)]
+mod registers_and_memory {
+    use super::{machine, session};
+    use crate::gdb::Stub;
+
+    #[test]
+    fn should_read_and_write_all_registers() {
+        let mut stub = Stub::new(machine());
+
+        let replies = session(&mut stub, &["g", "G0102031f3412c3", "g", "G00"]);
+
+        assert_eq!(
+            replies,
+            vec!["00000000000200", "OK", "0102031f3412c3", "E01"]
+        );
+    }
+
+    #[test]
+    fn should_read_and_write_single_registers() {
+        let mut stub = Stub::new(machine());
+
+        let replies = session(&mut stub, &["P0=7f", "P4=0403", "p0", "p4", "p5", "p6"]);
+
+        assert_eq!(replies, vec!["OK", "OK", "7f", "0403", "00", "E01"]);
+    }
+
+    #[test]
+    fn should_read_and_write_memory() {
+        let mut stub = Stub::new(machine());
+
+        let replies = session(
+            &mut stub,
+            &[
+                "m200,4",
+                "M10,2:beef",
+                "m10,2",
+                "mfffe,8",
+                "M10,2:be",
+                "m10000,1",
+            ],
+        );
+
+        assert_eq!(
+            replies,
+            vec!["a9428510", "OK", "beef", "0000", "E01", "E01"]
+        );
+    }
+
+    #[test]
+    fn should_clip_lengths_running_past_end_of_memory() {
+        let mut stub = Stub::new(machine());
+
+        let replies = session(
+            &mut stub,
+            &[
+                "Mffff,1:ea",
+                "mffff,ffffffff",
+                "Mffff,ffffffff:0102",
+                "mfff0,100000000",
+                "Mfff0,100000000:01",
+            ],
+        );
+
+        assert_eq!(replies, vec!["OK", "ea", "E01", "E01", "E01"]);
+    }
+}
+
+#[cfg(test)]
+mod execution {
+    use super::{frame, machine, raw_session, session};
+    use crate::gdb::Stub;
+
+    #[test]
+    fn should_step_and_report_trap() {
+        let mut stub = Stub::new(machine());
+
+        let replies = session(&mut stub, &["s", "p4", "s204", "p4"]);
+
+        assert_eq!(replies, vec!["S05", "0202", "S05", "0502"]);
+    }
+
+    #[test]
+    fn should_continue_until_illegal_opcode() {
+        let mut stub = Stub::new(machine());
+
+        let replies = session(&mut stub, &["c", "p4"]);
+
+        assert_eq!(replies, vec!["S04", "0802"]);
+    }
+
+    #[test]
+    fn should_stop_at_breakpoints_until_removed() {
+        let mut stub = Stub::new(machine());
+
+        let replies = session(
+            &mut stub,
+            &[
+                "qSupported:swbreak+",
+                "Z0,204,1",
+                "c",
+                "p4",
+                "z0,204,1",
+                "c",
+            ],
+        );
+
+        assert_eq!(replies[1..], ["OK", "T05swbreak:;", "0402", "OK", "S04"]);
+    }
+
+    #[test]
+    fn should_report_plain_trap_for_breakpoints_without_stop_reason_support() {
+        let mut stub = Stub::new(machine());
+
+        let replies = session(&mut stub, &["Z1,205,1", "c"]);
+
+        assert_eq!(replies, vec!["OK", "S05"]);
+    }
+
+    #[test]
+    fn should_stop_at_watchpoints() {
+        let mut stub = Stub::new(machine());
+
+        let replies = session(
+            &mut stub,
+            &[
+                "Z2,10,1", "Z4,10,1", "c", "p4", "z2,10,1", "c", "z4,10,1", "c",
+            ],
+        );
+
+        assert_eq!(
+            replies,
+            vec![
+                "OK",
+                "OK",
+                "T05watch:0010;",
+                "0402",
+                "OK",
+                "T05awatch:0010;",
+                "OK",
+                "S04"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_stop_running_program_on_interrupt() {
+        let mut stub = Stub::new(machine());
+
+        let output = raw_session(&mut stub, &frame("c300"), true);
+
+        assert_eq!(output, format!("+{}", frame("S02")));
+    }
+}
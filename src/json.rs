@@ -0,0 +1,94 @@
+use std::fmt::{self, Write};
+
+/// Minimal JSON document model for the machine-readable outputs of the binary.
+/// `Display` renders it pretty-printed with two space indentation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Number(u64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub fn object(fields: Vec<(&str, JsonValue)>) -> Self {
+        return JsonValue::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        );
+    }
+
+    pub fn string(value: &str) -> Self {
+        return JsonValue::String(value.to_string());
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        return match self {
+            JsonValue::Number(value) => write!(f, "{value}"),
+            JsonValue::String(value) => write_string(f, value),
+            JsonValue::Array(items) if items.is_empty() => f.write_str("[]"),
+            JsonValue::Array(items) if items.iter().all(JsonValue::is_scalar) => {
+                f.write_char('[')?;
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    item.write(f, indent)?;
+                }
+                f.write_char(']')
+            }
+            JsonValue::Array(items) => {
+                f.write_str("[\n")?;
+                for (idx, item) in items.iter().enumerate() {
+                    write!(f, "{:width$}", "", width = indent + 2)?;
+                    item.write(f, indent + 2)?;
+                    f.write_str(if idx + 1 < items.len() { ",\n" } else { "\n" })?;
+                }
+                write!(f, "{:width$}]", "", width = indent)
+            }
+            JsonValue::Object(fields) if fields.is_empty() => f.write_str("{}"),
+            JsonValue::Object(fields) => {
+                f.write_str("{\n")?;
+                for (idx, (key, value)) in fields.iter().enumerate() {
+                    write!(f, "{:width$}", "", width = indent + 2)?;
+                    write_string(f, key)?;
+                    f.write_str(": ")?;
+                    value.write(f, indent + 2)?;
+                    f.write_str(if idx + 1 < fields.len() { ",\n" } else { "\n" })?;
+                }
+                write!(f, "{:width$}}}", "", width = indent)
+            }
+        };
+    }
+
+    fn is_scalar(&self) -> bool {
+        return !matches!(self, JsonValue::Array(_) | JsonValue::Object(_));
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for character in value.chars() {
+        match character {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if u32::from(c) < 0x20 => write!(f, "\\u{:04x}", u32::from(c))?,
+            c => f.write_char(c)?,
+        }
+    }
+    return f.write_char('"');
+}
+
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return self.write(f, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests;
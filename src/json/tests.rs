@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod display {
+    use crate::json::JsonValue;
+
+    #[test]
+    fn should_render_scalars() {
+        assert_eq!(JsonValue::Number(42).to_string(), "42");
+    }
+
+    #[test]
+    fn should_escape_strings() {
+        let value = JsonValue::string("a \"b\"\\\n\u{1}");
+
+        assert_eq!(value.to_string(), r#""a \"b\"\\\n\u0001""#);
+    }
+
+    #[test]
+    fn should_keep_arrays_of_scalars_on_one_line() {
+        let value = JsonValue::Array(vec![JsonValue::Number(1), JsonValue::Number(2)]);
+
+        assert_eq!(value.to_string(), "[1, 2]");
+    }
+
+    #[test]
+    fn should_indent_nested_objects_and_keep_field_order() {
+        let value = JsonValue::object(vec![
+            ("b", JsonValue::Number(1)),
+            (
+                "a",
+                JsonValue::Array(vec![JsonValue::object(vec![("c", JsonValue::Number(0))])]),
+            ),
+            ("empty", JsonValue::Object(vec![])),
+        ]);
+
+        assert_eq!(
+            value.to_string(),
+            "{\n  \"b\": 1,\n  \"a\": [\n    {\n      \"c\": 0\n    }\n  ],\n  \"empty\": {}\n}"
+        );
+    }
+}
@@ -1,20 +1,157 @@
-use cpu6502::machine;
-
-fn main() {
-    let program: &[(u16, u8)] = &[
-        (0xFFFC, 0x4C), // JMP $1234
-        (0xFFFD, 0x34),
-        (0xFFFE, 0x12),
-        (0x1234, 0xB5), // LDA $AB,X
-        (0x1235, 0xAB),
-        (0x00AB, 0x42),
-        (0x1236, 0x20), // JSR $0300
-        (0x1237, 0x00),
-        (0x1238, 0x03),
-        (0x0300, 0xA9), // LDA #FF
-        (0x0301, 0xFF),
-    ];
-    let mut machine = machine::Machine::new();
-    let cycles = 14;
-    machine.execute_cycles(program, cycles);
+// Functions end in explicit `return` statements like the rest of the project.
+#![allow(clippy::needless_return)]
+
+mod batch;
+mod cli;
+mod gdb;
+mod json;
+mod monitor;
+mod run;
+
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+use cli::{Command, GdbAddress, Options, TraceDiffOptions, USAGE};
+use cpu6502::trace_diff::{self, LogFormat, TraceRecord};
+use run::Stop;
+
+const EXIT_USAGE: u8 = 2;
+
+fn io_error(err: io::Error) -> String {
+    return err.to_string();
+}
+
+fn stop_result(stop: Stop) -> Result<(), String> {
+    return match stop {
+        Stop::Cpu(reason) => Err(format!("execution stopped: {reason}")),
+        Stop::CycleLimit | Stop::InstructionLimit => Ok(()),
+    };
+}
+
+fn run_text(options: &Options) -> Result<(), String> {
+    let mut machine = run::prepare(options)?;
+    let tracers = run::attach_tracers(&mut machine, options)?;
+    let outcome = run::execute(&mut machine, options.cycle_limit, options.instruction_limit);
+    run::finish_tracers(tracers, &machine, options)?;
+
+    let mut stdout = io::stdout().lock();
+    if options.print_registers {
+        writeln!(stdout, "{}", run::format_registers(&machine.registers())).map_err(io_error)?;
+        writeln!(
+            stdout,
+            "cycles: {} instructions: {}",
+            outcome.cycles, outcome.instructions
+        )
+        .map_err(io_error)?;
+    }
+    for (start, end) in &options.dumps {
+        machine
+            .hexdump(*start, *end, &mut stdout)
+            .map_err(io_error)?;
+    }
+
+    return stop_result(outcome.stop);
+}
+
+fn run_monitor(options: &Options) -> Result<(), String> {
+    let mut monitor = monitor::Monitor::new(run::prepare(options)?);
+    return monitor::run(
+        &mut monitor,
+        &mut io::stdin().lock(),
+        &mut io::stdout().lock(),
+    )
+    .map_err(io_error);
+}
+
+fn run_gdb(options: &Options, address: &GdbAddress) -> Result<(), String> {
+    let mut stub = gdb::Stub::new(run::prepare(options)?);
+    return gdb::listen(&mut stub, address).map_err(io_error);
+}
+
+/// Like `run_text`, but every outcome, setup errors included, is reported as JSON on stdout.
+fn run_json(options: &Options) -> Result<(), String> {
+    let prepared = run::prepare(options).and_then(|mut machine| {
+        let tracers = run::attach_tracers(&mut machine, options)?;
+        Ok((machine, tracers))
+    });
+    let (document, result) = match prepared {
+        Ok((mut machine, tracers)) => {
+            let outcome =
+                run::execute(&mut machine, options.cycle_limit, options.instruction_limit);
+            let document = batch::report(&machine, &outcome, &options.dumps);
+            let result =
+                run::finish_tracers(tracers, &machine, options).and(stop_result(outcome.stop));
+            (document, result)
+        }
+        Err(message) => (batch::error_report(&message), Err(message)),
+    };
+
+    println!("{document}");
+    return result;
+}
+
+fn read_log(path: &Path, format: Option<LogFormat>) -> Result<Vec<TraceRecord>, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    let format = format
+        .or_else(|| LogFormat::detect(&text))
+        .ok_or_else(|| format!("{}: cannot tell the log format", path.display()))?;
+
+    return Ok(trace_diff::parse_log(&text, format));
+}
+
+/// Prints where the traces diverge, which fails the command.
+fn run_trace_diff(options: &TraceDiffOptions) -> Result<(), String> {
+    let ours = read_log(&options.ours, options.ours_format)?;
+    let reference = read_log(&options.reference, options.reference_format)?;
+    let comparison = trace_diff::compare(&ours, &reference, trace_diff::STATUS_MASK)
+        .map_err(|err| err.to_string())?;
+
+    comparison
+        .write_report(&ours, &reference, options.context, &mut io::stdout().lock())
+        .map_err(io_error)?;
+    return match comparison.divergence {
+        Some(_) => Err("traces differ".to_string()),
+        None => Ok(()),
+    };
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    return if let Some(address) = &options.gdb {
+        run_gdb(options, address)
+    } else if options.monitor {
+        run_monitor(options)
+    } else if options.json {
+        run_json(options)
+    } else {
+        run_text(options)
+    };
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let parsed = cli::expand_jobs(&args, |path| fs::read_to_string(path))
+        .and_then(|args| cli::parse_args(&args));
+    let result = match parsed {
+        Ok(Command::Run(options)) => run(&options),
+        Ok(Command::TraceDiff(options)) => run_trace_diff(&options),
+        Ok(Command::Help) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("emu6502: {err}\n\n{USAGE}");
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    return match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("emu6502: {message}");
+            ExitCode::FAILURE
+        }
+    };
 }
@@ -0,0 +1,447 @@
+use std::{
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+use cpu6502::{
+    asm::assemble,
+    consts::{Byte, Word},
+    cpu::{
+        breakpoints::{Breakpoint, Condition},
+        StopReason,
+    },
+    disasm::decode,
+    machine::Machine,
+    memory::binary::WrapPolicy,
+    opcodes,
+};
+
+use crate::run::format_registers;
+
+pub const HELP: &str = "\
+commands, addresses and bytes are hexadecimal, counts decimal:
+  m [START [END]]        show memory, continuing where the last m stopped
+  > ADDRESS BYTE...      change memory
+  r [REG=VALUE...]       show registers, or change A, X, Y, SP, P or PC
+  d [START [END]]        disassemble, continuing where the last d stopped
+  a [ADDRESS] INSTR      assemble one instruction, continuing after the last one
+  z [COUNT]              step COUNT instructions (default 1)
+  n                      step, running subroutines called by JSR to their end
+  g [ADDRESS]            continue until a breakpoint or the CPU stops
+  b [ADDRESS] [if COND]  set a breakpoint, or list them without arguments
+  bc [ID]                clear a breakpoint, or all of them
+  ignore ID COUNT        skip the next COUNT hits of a breakpoint
+  l FILE ADDRESS         load a binary file
+  s FILE START END       save memory to a binary file
+  reset                  reset the machine
+  q                      quit
+
+breakpoint conditions are expressions like pc == $C000 && a > $10 && [$00FE] == 0
+over registers, flags, [byte] and {word} memory reads and the cycles counter";
+
+/// Cycles `g` and `n` run before handing control back to the user.
+pub const RUN_BUDGET: u64 = 10_000_000;
+
+const INSTRUCTION_JSR: Byte = 0x20;
+const DEFAULT_DUMP_LEN: Word = 0x80;
+const DEFAULT_DISASSEMBLY_LEN: usize = 16;
+
+/// Whether the session continues after a command.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+/// Machine-language monitor operating on a live machine.
+pub struct Monitor {
+    machine: Machine,
+    memory_cursor: Word,
+    disassembly_cursor: Word,
+    assembly_cursor: Word,
+}
+
+fn parse_hex(text: &str) -> Result<u32, String> {
+    let digits = text
+        .strip_prefix('$')
+        .or_else(|| text.strip_prefix("0x"))
+        .unwrap_or(text);
+    return u32::from_str_radix(digits, 16).map_err(|_| format!("invalid number \"{text}\""));
+}
+
+fn parse_address(text: &str) -> Result<Word, String> {
+    return parse_hex(text)
+        .ok()
+        .and_then(|value| Word::try_from(value).ok())
+        .ok_or_else(|| format!("invalid address \"{text}\""));
+}
+
+fn parse_byte(text: &str) -> Result<Byte, String> {
+    return parse_hex(text)
+        .ok()
+        .and_then(|value| Byte::try_from(value).ok())
+        .ok_or_else(|| format!("invalid byte \"{text}\""));
+}
+
+fn parse_count(text: &str) -> Result<u64, String> {
+    return text
+        .parse()
+        .map_err(|_| format!("invalid count \"{text}\""));
+}
+
+fn parse_id(text: &str) -> Result<usize, String> {
+    return text
+        .parse()
+        .map_err(|_| format!("invalid breakpoint \"{text}\""));
+}
+
+fn io_error(err: io::Error) -> String {
+    return err.to_string();
+}
+
+impl Monitor {
+    pub fn new(machine: Machine) -> Self {
+        let pc = machine.registers().program_counter;
+        return Monitor {
+            machine,
+            memory_cursor: pc,
+            disassembly_cursor: pc,
+            assembly_cursor: pc,
+        };
+    }
+
+    /// Prompt showing the program counter.
+    pub fn prompt(&self) -> String {
+        return format!("(${:04X}) ", self.machine.registers().program_counter);
+    }
+
+    /// Executes one command line, writing its output to `out`.
+    pub fn command(&mut self, line: &str, out: &mut dyn Write) -> Result<Flow, String> {
+        let line = line.trim();
+        let (name, rest) = match line.split_once(char::is_whitespace) {
+            Some((name, rest)) => (name, rest.trim()),
+            None => (line, ""),
+        };
+        let args: Vec<&str> = rest.split_whitespace().collect();
+
+        match name.to_ascii_lowercase().as_str() {
+            "" => (),
+            "h" | "help" | "?" => writeln!(out, "{HELP}").map_err(io_error)?,
+            "q" | "quit" | "x" => return Ok(Flow::Quit),
+            "m" => self.memory(&args, out)?,
+            ">" => self.change_memory(&args)?,
+            "r" => self.registers(&args, out)?,
+            "d" => self.disassemble(&args, out)?,
+            "a" => self.assemble(rest, out)?,
+            "z" | "step" => self.step(&args, out)?,
+            "n" | "next" => self.next(out)?,
+            "g" | "c" | "continue" => {
+                if let Some(address) = args.first() {
+                    self.machine
+                        .cpu_mut()
+                        .set_program_counter(parse_address(address)?);
+                }
+                self.resume(None, out)?;
+            }
+            "b" => self.breakpoint(rest, out)?,
+            "bc" => match args.first() {
+                Some(id) => {
+                    let id = parse_id(id)?;
+                    self.machine
+                        .cpu_mut()
+                        .remove_breakpoint(id)
+                        .ok_or_else(|| format!("no breakpoint {id}"))?;
+                }
+                None => self.machine.cpu_mut().clear_breakpoints(),
+            },
+            "ignore" => {
+                let [id, count] = args[..] else {
+                    return Err("expected a breakpoint and a count".to_string());
+                };
+                let id = parse_id(id)?;
+                let count = parse_count(count)?;
+                let breakpoint = self
+                    .machine
+                    .cpu_mut()
+                    .breakpoint_mut(id)
+                    .ok_or_else(|| format!("no breakpoint {id}"))?;
+                breakpoint.ignore_count = breakpoint.hit_count + count;
+            }
+            "l" | "load" => self.load(&args, out)?,
+            "s" | "save" => self.save(&args, out)?,
+            "reset" => {
+                self.machine.reset();
+                self.show_state(out)?;
+            }
+            _ => return Err(format!("unknown command \"{name}\", h lists them")),
+        }
+
+        return Ok(Flow::Continue);
+    }
+
+    fn range(&self, args: &[&str], cursor: Word) -> Result<(Word, Option<Word>), String> {
+        let start = match args.first() {
+            Some(start) => parse_address(start)?,
+            None => cursor,
+        };
+        let end = args.get(1).map(|end| parse_address(end)).transpose()?;
+        if end.is_some_and(|end| end < start) {
+            return Err("end is before start".to_string());
+        }
+
+        return Ok((start, end));
+    }
+
+    fn memory(&mut self, args: &[&str], out: &mut dyn Write) -> Result<(), String> {
+        let (start, end) = self.range(args, self.memory_cursor)?;
+        let end = end.unwrap_or_else(|| start.saturating_add(DEFAULT_DUMP_LEN - 1));
+        self.machine.hexdump(start, end, out).map_err(io_error)?;
+        self.memory_cursor = end.wrapping_add(1);
+
+        return Ok(());
+    }
+
+    fn change_memory(&mut self, args: &[&str]) -> Result<(), String> {
+        let (address, bytes) = match args.split_first() {
+            Some((address, bytes)) if !bytes.is_empty() => (parse_address(address)?, bytes),
+            _ => return Err("expected an address and bytes".to_string()),
+        };
+        let bytes = bytes
+            .iter()
+            .map(|byte| parse_byte(byte))
+            .collect::<Result<Vec<Byte>, String>>()?;
+
+        let memory = self.machine.memory_mut();
+        for (offset, value) in bytes.into_iter().enumerate() {
+            memory.write(address.wrapping_add(offset as Word), value);
+        }
+
+        return Ok(());
+    }
+
+    fn registers(&mut self, args: &[&str], out: &mut dyn Write) -> Result<(), String> {
+        let mut state = self.machine.registers();
+        for arg in args {
+            let (register, value) = arg
+                .split_once('=')
+                .ok_or_else(|| format!("expected REGISTER=VALUE, got \"{arg}\""))?;
+            match register.to_ascii_lowercase().as_str() {
+                "a" => state.accumulator = parse_byte(value)?,
+                "x" => state.index_register_x = parse_byte(value)?,
+                "y" => state.index_register_y = parse_byte(value)?,
+                "sp" | "s" => state.stack_pointer = parse_byte(value)?,
+                "p" => state.processor_status = parse_byte(value)?,
+                "pc" => state.program_counter = parse_address(value)?,
+                _ => return Err(format!("unknown register \"{register}\"")),
+            }
+        }
+        self.machine.set_registers(state);
+
+        return writeln!(out, "{}", format_registers(&state)).map_err(io_error);
+    }
+
+    fn disassemble(&mut self, args: &[&str], out: &mut dyn Write) -> Result<(), String> {
+        let (start, end) = self.range(args, self.disassembly_cursor)?;
+        let mut address = start;
+        let mut count = 0;
+        loop {
+            let instruction = decode(self.machine.memory(), address);
+            writeln!(out, "{}", instruction.listing()).map_err(io_error)?;
+            count += 1;
+            let next = u32::from(address) + instruction.len() as u32;
+            address = next as Word;
+            let done = match end {
+                Some(end) => next > u32::from(end),
+                None => count == DEFAULT_DISASSEMBLY_LEN,
+            };
+            if done || next > 0xFFFF {
+                break;
+            }
+        }
+        self.disassembly_cursor = address;
+
+        return Ok(());
+    }
+
+    fn assemble(&mut self, text: &str, out: &mut dyn Write) -> Result<(), String> {
+        let (first, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let (address, instruction) = if first.is_empty() || opcodes::is_mnemonic(first) {
+            (self.assembly_cursor, text)
+        } else {
+            (parse_address(first)?, rest.trim())
+        };
+        if instruction.is_empty() {
+            return Err("expected an instruction".to_string());
+        }
+
+        let source = format!(".org ${address:04X}\n{instruction}");
+        let assembly = assemble(&source, Path::new("")).map_err(|err| {
+            let message = err.to_string();
+            match message.split_once(": ") {
+                Some((_, message)) => message.to_string(),
+                None => message,
+            }
+        })?;
+        self.machine.load(&assembly.to_pairs());
+
+        let instruction = decode(self.machine.memory(), address);
+        writeln!(out, "{}", instruction.listing()).map_err(io_error)?;
+        self.assembly_cursor = instruction.next_address();
+
+        return Ok(());
+    }
+
+    fn step(&mut self, args: &[&str], out: &mut dyn Write) -> Result<(), String> {
+        let count = match args.first() {
+            Some(count) => parse_count(count)?,
+            None => 1,
+        };
+        for _ in 0..count {
+            self.machine.step();
+            if let Some(reason) = self.machine.stop_reason() {
+                report_stop(reason, out)?;
+                break;
+            }
+        }
+
+        return self.show_state(out);
+    }
+
+    fn next(&mut self, out: &mut dyn Write) -> Result<(), String> {
+        let pc = self.machine.registers().program_counter;
+        if self.machine.memory().peek(pc) != INSTRUCTION_JSR {
+            return self.step(&[], out);
+        }
+
+        return self.resume(Some(pc.wrapping_add(3)), out);
+    }
+
+    fn breakpoint(&mut self, text: &str, out: &mut dyn Write) -> Result<(), String> {
+        if text.is_empty() {
+            for (id, breakpoint) in self.machine.cpu().breakpoints() {
+                writeln!(out, "{id}: {breakpoint}").map_err(io_error)?;
+            }
+            return Ok(());
+        }
+
+        let (address, condition) = match text.strip_prefix("if ") {
+            Some(condition) => (None, Some(condition)),
+            None => match text.split_once(" if ") {
+                Some((address, condition)) => (Some(address.trim()), Some(condition)),
+                None => (Some(text), None),
+            },
+        };
+        let condition = condition
+            .map(Condition::parse)
+            .transpose()
+            .map_err(|err| err.to_string())?;
+        let breakpoint = match (address, condition) {
+            (Some(address), condition) => {
+                let breakpoint = Breakpoint::at(parse_address(address)?);
+                match condition {
+                    Some(condition) => breakpoint.with_condition(condition),
+                    None => breakpoint,
+                }
+            }
+            (None, Some(condition)) => Breakpoint::when(condition),
+            (None, None) => unreachable!("either an address or a condition is given"),
+        };
+        let id = self.machine.cpu_mut().add_breakpoint(breakpoint);
+
+        return writeln!(out, "breakpoint {id}").map_err(io_error);
+    }
+
+    /// Runs until a breakpoint, a CPU stop, `until` or [`RUN_BUDGET`] cycles.
+    fn resume(&mut self, until: Option<Word>, out: &mut dyn Write) -> Result<(), String> {
+        let start_cycle = self.machine.registers().cycle;
+        loop {
+            self.machine.step();
+            if let Some(reason) = self.machine.stop_reason() {
+                report_stop(reason, out)?;
+                break;
+            }
+
+            let state = self.machine.registers();
+            if until == Some(state.program_counter) {
+                break;
+            }
+            if state.cycle - start_cycle >= RUN_BUDGET {
+                writeln!(out, "still running after {RUN_BUDGET} cycles").map_err(io_error)?;
+                break;
+            }
+        }
+
+        return self.show_state(out);
+    }
+
+    fn load(&mut self, args: &[&str], out: &mut dyn Write) -> Result<(), String> {
+        let [path, address] = args else {
+            return Err("expected a file and an address".to_string());
+        };
+        let address = parse_address(address)?;
+        let len = self
+            .machine
+            .load_binary_file(path, address, WrapPolicy::Reject)
+            .map_err(|err| format!("{path}: {err}"))?;
+        if len > 0 {
+            let end = address as usize + len - 1;
+            writeln!(out, "loaded ${address:04X}-${end:04X}").map_err(io_error)?;
+        }
+
+        return Ok(());
+    }
+
+    fn save(&mut self, args: &[&str], out: &mut dyn Write) -> Result<(), String> {
+        let [path, start, end] = args else {
+            return Err("expected a file, a start and an end address".to_string());
+        };
+        let (start, end) = (parse_address(start)?, parse_address(end)?);
+        if end < start {
+            return Err("end is before start".to_string());
+        }
+        self.machine
+            .dump_to_file(start, end, path)
+            .map_err(|err| format!("{path}: {err}"))?;
+
+        return writeln!(out, "saved ${start:04X}-${end:04X}").map_err(io_error);
+    }
+
+    fn show_state(&self, out: &mut dyn Write) -> Result<(), String> {
+        let state = self.machine.registers();
+        let instruction = decode(self.machine.memory(), state.program_counter);
+        writeln!(out, "{}", format_registers(&state)).map_err(io_error)?;
+        return writeln!(out, "{}", instruction.listing()).map_err(io_error);
+    }
+}
+
+fn report_stop(reason: StopReason, out: &mut dyn Write) -> Result<(), String> {
+    return match reason {
+        StopReason::Breakpoint(_) => writeln!(out, "{reason}"),
+        _ => writeln!(out, "stopped: {reason}"),
+    }
+    .map_err(io_error);
+}
+
+/// Reads commands from `input` until it ends or `q` is given. Errors are
+/// reported on `out` and do not end the session.
+pub fn run(monitor: &mut Monitor, input: &mut dyn BufRead, out: &mut dyn Write) -> io::Result<()> {
+    let mut line = String::new();
+    loop {
+        write!(out, "{}", monitor.prompt())?;
+        out.flush()?;
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            writeln!(out)?;
+            return Ok(());
+        }
+
+        match monitor.command(&line, out) {
+            Ok(Flow::Continue) => (),
+            Ok(Flow::Quit) => return Ok(()),
+            Err(message) => writeln!(out, "error: {message}")?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
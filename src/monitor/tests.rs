@@ -0,0 +1,259 @@
+use cpu6502::{asm6502, machine::Machine};
+
+use crate::monitor::{run, Monitor};
+
+/// `LDA #$42; STA $10; INX; INX; LDA $10` at $0200, followed by an illegal opcode.
+fn machine() -> Machine {
+    let mut machine = Machine::new();
+    machine.load(&asm6502! {
+        org 0x0200;
+        lda #0x42;
+        sta 0x10;
+        inx;
+        inx;
+        lda 0x10;
+        byte 0x02;
+    });
+    machine.cpu_mut().set_program_counter(0x0200);
+
+    return machine;
+}
+
+/// Runs `commands` and returns everything written, prompts included.
+fn session(monitor: &mut Monitor, commands: &str) -> String {
+    let mut out = vec![];
+    run(monitor, &mut commands.as_bytes(), &mut out).unwrap();
+
+    return String::from_utf8(out).unwrap();
+}
+
+#[cfg(test)]
+mod memory {
+    use super::{machine, session};
+    use crate::monitor::Monitor;
+    use std::{env, fs};
+
+    #[test]
+    fn should_show_and_change_memory() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "> 0300 48 49 00\nm 0300 0302\n");
+
+        assert_eq!(
+            output,
+            "($0200) ($0200) 0300  48 49 00                                          |HI.|\n($0200) \n"
+        );
+    }
+
+    #[test]
+    fn should_continue_memory_dump_after_previous_one() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "m 0200 020f\nm\n");
+
+        assert!(output.contains("\n($0200) 0210  "));
+        assert!(output.contains("\n0280  "));
+        assert!(!output.contains("0290  "));
+    }
+
+    #[test]
+    fn should_load_and_save_binary_files() {
+        let dir = env::temp_dir().join("emu6502_monitor_test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("program.bin");
+        let path = path.display();
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(
+            &mut monitor,
+            &format!("s {path} 0200 0203\nl {path} 1000\nm 1000 1003\n"),
+        );
+
+        assert!(output.contains("saved $0200-$0203\n"));
+        assert!(output.contains("loaded $1000-$1003\n"));
+        assert!(output.contains("1000  A9 42 85 10 "));
+    }
+}
+
+#[cfg(test)]
+mod registers {
+    use super::{machine, session};
+    use crate::monitor::Monitor;
+
+    #[test]
+    fn should_show_and_change_registers() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "r a=7f x=01 pc=0204\nr\n");
+
+        assert_eq!(
+            output,
+            "($0200) PC=$0204 A=$7F X=$01 Y=$00 SP=$00 P=$00 [........]\n\
+             ($0204) PC=$0204 A=$7F X=$01 Y=$00 SP=$00 P=$00 [........]\n\
+             ($0204) \n"
+        );
+    }
+
+    #[test]
+    fn should_report_invalid_registers_and_continue() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "r q=1\nr a=100\n");
+
+        assert_eq!(
+            output,
+            "($0200) error: unknown register \"q\"\n\
+             ($0200) error: invalid byte \"100\"\n\
+             ($0200) \n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod code {
+    use super::{machine, session};
+    use crate::monitor::Monitor;
+
+    #[test]
+    fn should_disassemble_range() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "d 0200 0204\n");
+
+        assert_eq!(
+            output,
+            "($0200) 0200  A9 42     LDA #$42\n\
+             0202  85 10     STA $10\n\
+             0204  E8        INX\n\
+             ($0200) \n"
+        );
+    }
+
+    #[test]
+    fn should_assemble_and_continue_after_last_instruction() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "a 0300 ldy #1\na dec $10\na bne $0300\n");
+
+        assert_eq!(
+            output,
+            "($0200) 0300  A0 01     LDY #$01\n\
+             ($0200) 0302  C6 10     DEC $10\n\
+             ($0200) 0304  D0 FA     BNE $0300\n\
+             ($0200) \n"
+        );
+    }
+
+    #[test]
+    fn should_report_assembler_errors() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "a 0300 sta #1\na 0300\n");
+
+        assert_eq!(
+            output,
+            "($0200) error: addressing mode not supported by STA\n\
+             ($0200) error: expected an instruction\n\
+             ($0200) \n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod execution {
+    use super::{machine, session};
+    use crate::monitor::Monitor;
+
+    #[test]
+    fn should_step_instructions() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "z\nz 2\n");
+
+        assert_eq!(
+            output,
+            "($0200) PC=$0202 A=$42 X=$00 Y=$00 SP=$00 P=$00 [........]\n\
+             0202  85 10     STA $10\n\
+             ($0202) PC=$0205 A=$42 X=$01 Y=$00 SP=$00 P=$00 [........]\n\
+             0205  E8        INX\n\
+             ($0205) \n"
+        );
+    }
+
+    #[test]
+    fn should_stop_at_breakpoints_and_continue_from_them() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "b 0204\nb 0205\nb\ng\nbc 2\ng\n");
+
+        assert_eq!(
+            output,
+            "($0200) breakpoint 1\n\
+             ($0200) breakpoint 2\n\
+             ($0200) 1: $0204, 0 hits\n\
+             2: $0205, 0 hits\n\
+             ($0200) breakpoint 1 at $0204, hit count 1\n\
+             PC=$0204 A=$42 X=$00 Y=$00 SP=$00 P=$00 [........]\n\
+             0204  E8        INX\n\
+             ($0204) ($0204) stopped: illegal opcode $02 at $0208\n\
+             PC=$0208 A=$42 X=$02 Y=$00 SP=$00 P=$00 [........]\n\
+             0208  02        .byte $02\n\
+             ($0208) \n"
+        );
+    }
+
+    #[test]
+    fn should_stop_at_conditional_breakpoints_after_ignored_hits() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "b if x != 0\nignore 1 1\ng\nb\n");
+
+        assert!(output.contains("breakpoint 1 at $0206, hit count 2\n"));
+        assert!(output.contains("PC=$0206 A=$42 X=$02"));
+        assert!(output.contains("1: if x != 0, 2 hits, ignoring 1\n"));
+    }
+
+    #[test]
+    fn should_report_invalid_breakpoint_conditions() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "b if a ==\nbc 3\n");
+
+        assert_eq!(
+            output,
+            "($0200) error: expected a value at column 5\n\
+             ($0200) error: no breakpoint 3\n\
+             ($0200) \n"
+        );
+    }
+
+    #[test]
+    fn should_step_over_non_subroutine_calls_with_next() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "n\n");
+
+        assert!(output.starts_with("($0200) PC=$0202 A=$42"));
+    }
+
+    #[test]
+    fn should_reset_machine() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "z\nreset\n");
+
+        assert!(output.contains("($0202) PC=$FFFC A=$00"));
+    }
+
+    #[test]
+    fn should_quit_and_reject_unknown_commands() {
+        let mut monitor = Monitor::new(machine());
+
+        let output = session(&mut monitor, "frobnicate\nq\nm\n");
+
+        assert_eq!(
+            output,
+            "($0200) error: unknown command \"frobnicate\", h lists them\n($0200) "
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    cell::RefCell,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+    rc::Rc,
+};
+
+use cpu6502::{
+    consts::Word,
+    cpu::{
+        trace::{InstructionTracer, TraceFormat, Tracer},
+        CpuState, CpuVariant, StopReason,
+    },
+    loaders::{intel_hex, o65, prg, srec, Image},
+    machine::{
+        builder::MachineBuilder,
+        description::{DeviceRegistry, MachineDescription},
+        Machine,
+    },
+    memory::binary::WrapPolicy,
+    profiler::{Profiler, Symbols},
+};
+
+use crate::cli::{ImageFormat, Options, StartAddress};
+
+pub const DEFAULT_CYCLE_LIMIT: u64 = 1_000_000;
+
+/// Why [`execute`] returned.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Stop {
+    CycleLimit,
+    InstructionLimit,
+    Cpu(StopReason),
+}
+
+/// What happened while running the machine.
+pub struct Outcome {
+    pub instructions: u64,
+    pub cycles: u64,
+    pub stop: Stop,
+}
+
+/// Builds the machine, loads the image and positions the program counter.
+pub fn prepare(options: &Options) -> Result<Machine, String> {
+    let mut machine = match &options.machine {
+        Some(path) => {
+            let mut description = MachineDescription::from_file(path)
+                .map_err(|err| format!("{}: {err}", path.display()))?;
+            if let Some(variant) = options.cpu {
+                description.variant = variant;
+            }
+            // Console output would corrupt the JSON document on stdout.
+            let mut registry = DeviceRegistry::with_builtin_devices();
+            if options.json {
+                registry = registry.with_console_on_stderr();
+            }
+            description
+                .build(&registry)
+                .map_err(|err| format!("{}: {err}", path.display()))?
+        }
+        None => MachineBuilder::new()
+            .cpu(options.cpu.unwrap_or(CpuVariant::Nmos6502))
+            .build()
+            .map_err(|err| err.to_string())?,
+    };
+
+    let image_start = match &options.image {
+        Some(path) => load_image(&mut machine, path, options)
+            .map_err(|err| format!("{}: {err}", path.display()))?,
+        None => None,
+    };
+
+    let start = match options.start {
+        Some(StartAddress::Entry(address)) => Some(address),
+        Some(StartAddress::ResetVector) => {
+            let memory = machine.memory();
+            Some(Word::from_le_bytes([
+                memory.peek(0xFFFC),
+                memory.peek(0xFFFD),
+            ]))
+        }
+        None => image_start,
+    };
+    if let Some(address) = start {
+        machine.cpu_mut().set_program_counter(address);
+    }
+
+    return Ok(machine);
+}
+
+/// Tracer shared with the CPU, kept to flush the trace once execution stops.
+pub type Trace = Rc<RefCell<Tracer<Box<dyn Write>>>>;
+
+/// Tracers attached to the CPU by [`attach_tracers`].
+#[derive(Default)]
+pub struct Tracers {
+    trace: Option<Trace>,
+    profiler: Option<Rc<RefCell<Profiler>>>,
+    symbols: Symbols,
+}
+
+/// Starts tracing into the file given by `--trace`, `-` being stdout, and profiling
+/// for `--profile` and `--hotspots`.
+pub fn attach_tracers(machine: &mut Machine, options: &Options) -> Result<Tracers, String> {
+    let mut tracers = Tracers::default();
+    let mut attached: Vec<Box<dyn InstructionTracer>> = vec![];
+    if let Some(path) = &options.trace {
+        let writer: Box<dyn Write> = if path.as_os_str() == "-" {
+            Box::new(io::stdout())
+        } else {
+            let file = File::create(path).map_err(|err| format!("{}: {err}", path.display()))?;
+            Box::new(BufWriter::new(file))
+        };
+        let format = options.trace_format.unwrap_or(TraceFormat::Plain);
+        let tracer = Tracer::new(writer, format).with_filter(options.trace_filter.clone());
+        let trace = Rc::new(RefCell::new(tracer));
+        attached.push(Box::new(trace.clone()));
+        tracers.trace = Some(trace);
+    }
+    if options.profile.is_some() || options.hotspots.is_some() {
+        if let Some(path) = &options.symbols {
+            let text =
+                fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+            tracers.symbols = Symbols::parse(&text);
+        }
+        let profiler = Rc::new(RefCell::new(Profiler::new()));
+        attached.push(Box::new(profiler.clone()));
+        tracers.profiler = Some(profiler);
+    }
+
+    if !attached.is_empty() {
+        machine.cpu_mut().set_tracer(Some(Box::new(attached)));
+    }
+    return Ok(tracers);
+}
+
+/// Flushes the trace, writes the profile and prints the hotspots, reporting the
+/// first error writing any of them.
+pub fn finish_tracers(
+    tracers: Tracers,
+    machine: &Machine,
+    options: &Options,
+) -> Result<(), String> {
+    if let Some(trace) = tracers.trace {
+        let mut tracer = trace.borrow_mut();
+        let result = match tracer.take_error() {
+            Some(err) => Err(err),
+            None => tracer.writer_mut().flush(),
+        };
+        result.map_err(|err| format!("cannot write trace: {err}"))?;
+    }
+
+    let Some(profiler) = tracers.profiler else {
+        return Ok(());
+    };
+    let mut profiler = profiler.borrow_mut();
+    profiler.finish(machine.registers().cycle);
+    let profile = profiler.profile();
+    if let Some(path) = &options.profile {
+        let write = || -> io::Result<()> {
+            let mut out = BufWriter::new(File::create(path)?);
+            profile.write_callgrind(&tracers.symbols, &mut out)?;
+            out.flush()
+        };
+        write().map_err(|err| format!("{}: {err}", path.display()))?;
+    }
+    if let Some(top) = options.hotspots {
+        profile
+            .write_report(&tracers.symbols, top, &mut io::stdout().lock())
+            .map_err(|err| format!("cannot write hotspots: {err}"))?;
+    }
+
+    return Ok(());
+}
+
+/// Loads the image and returns the address execution should start at by default.
+fn load_image(
+    machine: &mut Machine,
+    path: &Path,
+    options: &Options,
+) -> Result<Option<Word>, String> {
+    let format = options
+        .format
+        .unwrap_or_else(|| ImageFormat::from_path(path));
+    let bytes = fs::read(path).map_err(|err| err.to_string())?;
+    let text = || String::from_utf8(bytes.clone()).map_err(|_| "not a text file".to_string());
+
+    let image: Image = match format {
+        ImageFormat::Binary => {
+            let origin = options.load_address.unwrap_or(0x0000);
+            machine
+                .load_binary(origin, &bytes, WrapPolicy::Reject)
+                .map_err(|err| err.to_string())?;
+            return Ok(Some(origin));
+        }
+        ImageFormat::O65 => {
+            let text_base = options
+                .load_address
+                .ok_or("o65 objects need a load address (--load)")?;
+            let layout = o65::load(&bytes, &o65::Options::new(text_base), machine.memory_mut())
+                .map_err(|err| err.to_string())?;
+            return Ok(Some(layout.text.address));
+        }
+        ImageFormat::Prg => prg::parse(&bytes).map_err(|err| err.to_string())?,
+        ImageFormat::IntelHex => intel_hex::parse(&text()?).map_err(|err| err.to_string())?,
+        ImageFormat::Srec => srec::parse(&text()?).map_err(|err| err.to_string())?,
+    };
+
+    machine.load_image(&image);
+    let first_segment = image.segments.first().map(|segment| segment.address);
+
+    return Ok(image.entry_point.or(first_segment));
+}
+
+/// Runs until a limit is reached or the CPU stops. Without any limit
+/// [`DEFAULT_CYCLE_LIMIT`] cycles are run.
+pub fn execute(
+    machine: &mut Machine,
+    cycle_limit: Option<u64>,
+    instruction_limit: Option<u64>,
+) -> Outcome {
+    let cycle_limit = match (cycle_limit, instruction_limit) {
+        (None, None) => Some(DEFAULT_CYCLE_LIMIT),
+        (cycle_limit, _) => cycle_limit,
+    };
+    let start_cycle = machine.registers().cycle;
+    let mut instructions = 0;
+
+    let stop = loop {
+        let cycles = machine.registers().cycle - start_cycle;
+        if cycle_limit.is_some_and(|limit| cycles >= limit) {
+            break Stop::CycleLimit;
+        }
+        if instruction_limit.is_some_and(|limit| instructions >= limit) {
+            break Stop::InstructionLimit;
+        }
+
+        instructions += machine.run_instructions(1);
+        if let Some(reason) = machine.stop_reason() {
+            break Stop::Cpu(reason);
+        }
+    };
+
+    return Outcome {
+        instructions,
+        cycles: machine.registers().cycle - start_cycle,
+        stop,
+    };
+}
+
+pub fn format_registers(state: &CpuState) -> String {
+    let flags: String = "NV-BDIZC"
+        .chars()
+        .enumerate()
+        .map(|(idx, name)| {
+            if state.processor_status & (0x80 >> idx) != 0 {
+                name
+            } else {
+                '.'
+            }
+        })
+        .collect();
+
+    return format!(
+        "PC=${:04X} A=${:02X} X=${:02X} Y=${:02X} SP=${:02X} P=${:02X} [{flags}]",
+        state.program_counter,
+        state.accumulator,
+        state.index_register_x,
+        state.index_register_y,
+        state.stack_pointer,
+        state.processor_status
+    );
+}